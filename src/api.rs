@@ -0,0 +1,382 @@
+//! An abstraction over the printer client.
+//!
+//! Application code that is generic over [`OctoPrintApi`] can be driven by the real
+//! [`Printer`](crate::Printer) in production and by the in-memory
+//! [`FakePrinter`](crate::fake::FakePrinter) in tests, without a mock server.
+//!
+//! # Example
+//!
+//! ```
+//! use octoprint_rs::api::OctoPrintApi;
+//! use octoprint_rs::fake::FakePrinter;
+//!
+//! async fn is_printing(printer: &impl OctoPrintApi) -> bool {
+//!     match printer.get_job().await {
+//!         Ok(job) => job.state == "Printing",
+//!         Err(_) => false,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! assert!(!is_printing(&FakePrinter::new()).await);
+//! # }
+//! ```
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::errors::*;
+use crate::heating::{self, Band, Heater};
+use crate::types::*;
+
+/// The operations to control a printer, implemented by [`Printer`](crate::Printer) and
+/// [`FakePrinter`](crate::fake::FakePrinter).
+///
+/// See the methods of the same name on [`Printer`](crate::Printer) for their documentation.
+///
+/// The language pack methods of [`Printer`](crate::Printer) are not part of the trait. They manage
+/// the translations installed on the OctoPrint server rather than a printer, and a fake would have
+/// to unpack the uploaded archives to answer them.
+pub trait OctoPrintApi: Send + Sync {
+    fn get_api_version(
+        &self,
+    ) -> impl Future<Output = Result<ApiVersion, InformationRequestError>> + Send;
+
+    fn get_connection(
+        &self,
+    ) -> impl Future<Output = Result<PrinterConnection, InformationRequestError>> + Send;
+
+    fn set_connection(
+        &self,
+        connection: ConnectionCommandDescriptor,
+    ) -> impl Future<Output = Result<(), SetConnectionError>> + Send;
+
+    fn get_printer_profiles(
+        &self,
+    ) -> impl Future<Output = Result<printer_profiles::Profiles, InformationRequestError>> + Send;
+
+    fn get_files(
+        &self,
+        files_descriptor: FilesFetchDescriptor,
+    ) -> impl Future<Output = Result<printer_files::Files, FileRequestError>> + Send;
+
+    /// Walks all files recursively and aggregates their print history, see
+    /// [`Printer::print_statistics`](crate::Printer::print_statistics).
+    fn print_statistics(
+        &self,
+    ) -> impl Future<Output = Result<printer_files::PrintStatisticsReport, FileRequestError>> + Send
+    {
+        async {
+            let files = self
+                .get_files(FilesFetchDescriptor {
+                    location: FilesLocation::Root,
+                    recursive: true,
+                    force: false,
+                })
+                .await?;
+
+            Ok(printer_files::PrintStatisticsReport::from_entries(
+                &files.files,
+            ))
+        }
+    }
+
+    fn upload_file(
+        &self,
+        upload: FileUploadDescriptor,
+    ) -> impl Future<Output = Result<UploadResponse, FileUploadError>> + Send;
+
+    fn get_file(
+        &self,
+        file_descriptor: FileFetchDescriptor,
+    ) -> impl Future<Output = Result<printer_files::Entry, FileRequestError>> + Send;
+
+    fn issue_file_command(
+        &self,
+        command: FileCommandDescriptor,
+    ) -> impl Future<Output = Result<(), FileCommandError>> + Send;
+
+    fn delete_file(
+        &self,
+        path: PathDescriptor,
+    ) -> impl Future<Output = Result<(), FileDeletionError>> + Send;
+
+    fn issue_job_command(
+        &self,
+        command: JobCommand,
+    ) -> impl Future<Output = Result<(), JobCommandError>> + Send;
+
+    fn get_job(
+        &self,
+    ) -> impl Future<Output = Result<JobInformation, InformationRequestError>> + Send;
+
+    fn get_printer_telemetry(
+        &self,
+    ) -> impl Future<Output = Result<RawPrinter, DeviceStateError>> + Send;
+
+    fn send_gcode(
+        &self,
+        commands: Vec<String>,
+    ) -> impl Future<Output = Result<(), PrinterCommandError>> + Send;
+
+    fn move_printhead(
+        &self,
+        command: PrintheadMoveDescriptor,
+    ) -> impl Future<Output = Result<(), ToolCommandError>> + Send;
+
+    fn change_printhead_feedrate(
+        &self,
+        factor: f32,
+    ) -> impl Future<Output = Result<(), ToolCommandError>> + Send;
+
+    fn tool_temperature(
+        &self,
+        command: ToolTempDescriptor,
+    ) -> impl Future<Output = Result<(), ToolCommandError>> + Send;
+
+    fn get_tool_state(
+        &self,
+        history: Option<u32>,
+    ) -> impl Future<Output = Result<ToolState, DeviceStateError>> + Send;
+
+    fn select_tool(
+        &self,
+        tool: ToolId,
+    ) -> impl Future<Output = Result<(), ToolCommandError>> + Send;
+
+    fn extrude(&self, amount: f32) -> impl Future<Output = Result<(), ToolCommandError>> + Send;
+
+    /// Retracts filament from the selected tool by extruding `-amount`.
+    fn retract(&self, amount: f32) -> impl Future<Output = Result<(), ToolCommandError>> + Send {
+        self.extrude(-amount)
+    }
+
+    fn change_tool_flowrate(
+        &self,
+        factor: f32,
+    ) -> impl Future<Output = Result<(), ToolCommandError>> + Send;
+
+    fn change_bed_temp(
+        &self,
+        command: BedTempDescriptor,
+    ) -> impl Future<Output = Result<(), ToolCommandError>> + Send;
+
+    fn get_bed_state(
+        &self,
+        history: Option<u32>,
+    ) -> impl Future<Output = Result<BedState, DeviceStateError>> + Send;
+
+    /// Sets the target of `tool` and waits until its temperature settled within `tolerance`.
+    fn heat_and_wait(
+        &self,
+        tool: ToolId,
+        target: f32,
+        tolerance: f32,
+        stable_for: Duration,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<f32, HeatError>> + Send {
+        let band = Band {
+            target,
+            tolerance,
+            stable_for,
+            timeout,
+        };
+        heating::heat_and_wait(self, Heater::Tool(tool), band)
+    }
+
+    /// Sets the target of the bed and waits until its temperature settled within `tolerance`.
+    fn heat_bed_and_wait(
+        &self,
+        target: f32,
+        tolerance: f32,
+        stable_for: Duration,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<f32, HeatError>> + Send {
+        let band = Band {
+            target,
+            tolerance,
+            stable_for,
+            timeout,
+        };
+        heating::heat_and_wait(self, Heater::Bed, band)
+    }
+
+    fn get_temperature_profiles(
+        &self,
+    ) -> impl Future<Output = Result<Vec<TemperatureProfile>, TemperatureProfileError>> + Send;
+
+    fn apply_temperature_profile(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<(), TemperatureProfileError>> + Send;
+
+    fn cool_down(&self) -> impl Future<Output = Result<(), TemperatureProfileError>> + Send;
+
+    fn get_pi_support(
+        &self,
+    ) -> impl Future<Output = Result<PiSupport, InformationRequestError>> + Send;
+
+    fn get_action_prompt(
+        &self,
+    ) -> impl Future<Output = Result<Option<ActionPrompt>, InformationRequestError>> + Send;
+
+    fn select_prompt_choice(
+        &self,
+        choice: usize,
+    ) -> impl Future<Output = Result<(), PromptCommandError>> + Send;
+
+    fn get_webcams(&self) -> impl Future<Output = Result<Webcams, InformationRequestError>> + Send;
+
+    fn snapshot(&self) -> impl Future<Output = Result<Snapshot, SnapshotError>> + Send;
+}
+
+impl OctoPrintApi for crate::Printer {
+    async fn get_api_version(&self) -> Result<ApiVersion, InformationRequestError> {
+        crate::Printer::get_api_version(self).await
+    }
+
+    async fn get_connection(&self) -> Result<PrinterConnection, InformationRequestError> {
+        crate::Printer::get_connection(self).await
+    }
+
+    async fn set_connection(
+        &self,
+        connection: ConnectionCommandDescriptor,
+    ) -> Result<(), SetConnectionError> {
+        crate::Printer::set_connection(self, connection).await
+    }
+
+    async fn get_printer_profiles(
+        &self,
+    ) -> Result<printer_profiles::Profiles, InformationRequestError> {
+        crate::Printer::get_printer_profiles(self).await
+    }
+
+    async fn get_files(
+        &self,
+        files_descriptor: FilesFetchDescriptor,
+    ) -> Result<printer_files::Files, FileRequestError> {
+        crate::Printer::get_files(self, files_descriptor).await
+    }
+
+    async fn print_statistics(
+        &self,
+    ) -> Result<printer_files::PrintStatisticsReport, FileRequestError> {
+        crate::Printer::print_statistics(self).await
+    }
+
+    async fn upload_file(
+        &self,
+        upload: FileUploadDescriptor,
+    ) -> Result<UploadResponse, FileUploadError> {
+        crate::Printer::upload_file(self, upload).await
+    }
+
+    async fn get_file(
+        &self,
+        file_descriptor: FileFetchDescriptor,
+    ) -> Result<printer_files::Entry, FileRequestError> {
+        crate::Printer::get_file(self, file_descriptor).await
+    }
+
+    async fn issue_file_command(
+        &self,
+        command: FileCommandDescriptor,
+    ) -> Result<(), FileCommandError> {
+        crate::Printer::issue_file_command(self, command).await
+    }
+
+    async fn delete_file(&self, path: PathDescriptor) -> Result<(), FileDeletionError> {
+        crate::Printer::delete_file(self, path).await
+    }
+
+    async fn issue_job_command(&self, command: JobCommand) -> Result<(), JobCommandError> {
+        crate::Printer::issue_job_command(self, command).await
+    }
+
+    async fn get_job(&self) -> Result<JobInformation, InformationRequestError> {
+        crate::Printer::get_job(self).await
+    }
+
+    async fn get_printer_telemetry(&self) -> Result<RawPrinter, DeviceStateError> {
+        crate::Printer::get_printer_telemetry(self).await
+    }
+
+    async fn send_gcode(&self, commands: Vec<String>) -> Result<(), PrinterCommandError> {
+        crate::Printer::send_gcode(self, commands).await
+    }
+
+    async fn move_printhead(
+        &self,
+        command: PrintheadMoveDescriptor,
+    ) -> Result<(), ToolCommandError> {
+        crate::Printer::move_printhead(self, command).await
+    }
+
+    async fn change_printhead_feedrate(&self, factor: f32) -> Result<(), ToolCommandError> {
+        crate::Printer::change_printhead_feedrate(self, factor).await
+    }
+
+    async fn tool_temperature(&self, command: ToolTempDescriptor) -> Result<(), ToolCommandError> {
+        crate::Printer::tool_temperature(self, command).await
+    }
+
+    async fn get_tool_state(&self, history: Option<u32>) -> Result<ToolState, DeviceStateError> {
+        crate::Printer::get_tool_state(self, history).await
+    }
+
+    async fn select_tool(&self, tool: ToolId) -> Result<(), ToolCommandError> {
+        crate::Printer::select_tool(self, tool).await
+    }
+
+    async fn extrude(&self, amount: f32) -> Result<(), ToolCommandError> {
+        crate::Printer::extrude(self, amount).await
+    }
+
+    async fn change_tool_flowrate(&self, factor: f32) -> Result<(), ToolCommandError> {
+        crate::Printer::change_tool_flowrate(self, factor).await
+    }
+
+    async fn change_bed_temp(&self, command: BedTempDescriptor) -> Result<(), ToolCommandError> {
+        crate::Printer::change_bed_temp(self, command).await
+    }
+
+    async fn get_bed_state(&self, history: Option<u32>) -> Result<BedState, DeviceStateError> {
+        crate::Printer::get_bed_state(self, history).await
+    }
+
+    async fn get_temperature_profiles(
+        &self,
+    ) -> Result<Vec<TemperatureProfile>, TemperatureProfileError> {
+        crate::Printer::get_temperature_profiles(self).await
+    }
+
+    async fn apply_temperature_profile(&self, name: &str) -> Result<(), TemperatureProfileError> {
+        crate::Printer::apply_temperature_profile(self, name).await
+    }
+
+    async fn cool_down(&self) -> Result<(), TemperatureProfileError> {
+        crate::Printer::cool_down(self).await
+    }
+
+    async fn get_pi_support(&self) -> Result<PiSupport, InformationRequestError> {
+        crate::Printer::get_pi_support(self).await
+    }
+
+    async fn get_action_prompt(&self) -> Result<Option<ActionPrompt>, InformationRequestError> {
+        crate::Printer::get_action_prompt(self).await
+    }
+
+    async fn select_prompt_choice(&self, choice: usize) -> Result<(), PromptCommandError> {
+        crate::Printer::select_prompt_choice(self, choice).await
+    }
+
+    async fn get_webcams(&self) -> Result<Webcams, InformationRequestError> {
+        crate::Printer::get_webcams(self).await
+    }
+
+    async fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        crate::Printer::snapshot(self).await
+    }
+}
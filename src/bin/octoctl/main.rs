@@ -0,0 +1,537 @@
+//! `octoctl`, a command line client for OctoPrint.
+//!
+//! The printer is either given directly with `--url` and `--api-key`, or picked by name with
+//! `--printer` from a configuration file given with `--config`, see
+//! [`FleetConfig`](octoprint_rs::config::FleetConfig). Every option can also be set from the
+//! environment.
+//!
+//! ```text
+//! octoctl --config printers.toml --printer prusa-1 status
+//! octoctl files upload benchy.gcode parts/benchy.gcode --print
+//! octoctl temp tool0 210
+//! octoctl --json job
+//! ```
+
+mod output;
+
+use std::{error::Error, path::PathBuf, process::ExitCode};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use octoprint_rs::{
+    config::FleetConfig,
+    errors::{DeviceStateError, OctoPrintError},
+    format,
+    types::{printer_files::Entry, *},
+    Printer, PrinterBuilder,
+};
+
+type CliResult<T = ()> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "octoctl",
+    version,
+    about = "Control OctoPrint printers from the command line"
+)]
+struct Cli {
+    #[command(flatten)]
+    target: Target,
+
+    /// Print the output as JSON instead of tables.
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct Target {
+    /// A TOML or JSON file with printer configurations.
+    #[arg(long, global = true, env = "OCTOCTL_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// The name of the printer in the configuration file. Can be left out if there is only one.
+    #[arg(short, long, global = true, env = "OCTOCTL_PRINTER")]
+    printer: Option<String>,
+
+    /// The base url of the printer, instead of a configuration file.
+    #[arg(long, global = true, env = "OCTOPRINT_URL", conflicts_with = "config")]
+    url: Option<String>,
+
+    /// The api key to use with `--url`.
+    #[arg(long, global = true, env = "OCTOPRINT_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the connection state, temperatures and the current job.
+    Status,
+    /// Manage the files on the printer.
+    #[command(subcommand)]
+    Files(FilesCommand),
+    /// Show or control the current job.
+    Job {
+        #[command(subcommand)]
+        command: Option<JobAction>,
+    },
+    /// Set the target temperature of a tool.
+    Temp {
+        /// The tool, like `tool0`.
+        tool: ToolId,
+        /// The temperature in °C, `0` turns the heater off.
+        temperature: f32,
+        /// Set the temperature offset instead of the target.
+        #[arg(long)]
+        offset: bool,
+    },
+    /// Set the target temperature of the bed.
+    Bed {
+        /// The temperature in °C, `0` turns the heater off.
+        temperature: f32,
+        /// Set the temperature offset instead of the target.
+        #[arg(long)]
+        offset: bool,
+    },
+    /// Move the printhead relative to its current position, in mm.
+    Jog {
+        #[arg(short, default_value_t = 0.0, allow_negative_numbers = true)]
+        x: f32,
+        #[arg(short, default_value_t = 0.0, allow_negative_numbers = true)]
+        y: f32,
+        #[arg(short, default_value_t = 0.0, allow_negative_numbers = true)]
+        z: f32,
+    },
+    /// Home the given axes, or all of them.
+    Home {
+        #[arg(value_enum)]
+        axes: Vec<Axis>,
+    },
+    /// Send G-code commands to the printer.
+    Gcode {
+        #[arg(required = true)]
+        commands: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FilesCommand {
+    /// List all files and folders.
+    Ls {
+        /// Only list the files of one storage.
+        #[arg(long, value_enum)]
+        origin: Option<Origin>,
+    },
+    /// Upload a file.
+    Upload {
+        /// The file to upload.
+        file: PathBuf,
+        /// The path on the printer. Defaults to the name of the file.
+        destination: Option<String>,
+        #[command(flatten)]
+        origin: OriginArg,
+        /// Select the file after uploading it.
+        #[arg(long)]
+        select: bool,
+        /// Start printing the file after uploading it.
+        #[arg(long)]
+        print: bool,
+    },
+    /// Delete a file or folder.
+    Rm {
+        path: String,
+        #[command(flatten)]
+        origin: OriginArg,
+    },
+    /// Move a file or folder.
+    Mv {
+        source: String,
+        destination: String,
+        #[command(flatten)]
+        origin: OriginArg,
+    },
+    /// Copy a file or folder.
+    Cp {
+        source: String,
+        destination: String,
+        #[command(flatten)]
+        origin: OriginArg,
+    },
+}
+
+#[derive(Subcommand)]
+enum JobAction {
+    /// Start printing the selected file, or select and print `file`.
+    Start {
+        file: Option<String>,
+        #[command(flatten)]
+        origin: OriginArg,
+    },
+    Pause,
+    Resume,
+    Cancel,
+}
+
+#[derive(Args)]
+struct OriginArg {
+    /// Use the SD card of the printer instead of the local storage.
+    #[arg(long)]
+    sdcard: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Origin {
+    Local,
+    Sdcard,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// Converts the error of an operation into an [`OctoPrintError`], which can be displayed.
+fn api<E>(error: E) -> Box<dyn Error>
+where
+    OctoPrintError: From<E>,
+{
+    Box::new(OctoPrintError::from(error))
+}
+
+impl Target {
+    fn printer(&self) -> CliResult<Printer> {
+        if let Some(url) = &self.url {
+            let api_key = self
+                .api_key
+                .as_ref()
+                .ok_or("--url requires --api-key or OCTOPRINT_API_KEY")?;
+            return Ok(PrinterBuilder::from_base_url(url, api_key)?.build());
+        }
+
+        let path = self
+            .config
+            .as_ref()
+            .ok_or("either --url or --config is required, or OCTOPRINT_URL or OCTOCTL_CONFIG")?;
+        let config = FleetConfig::load(path)?;
+        let printer = match &self.printer {
+            Some(name) => config
+                .printers
+                .get(name)
+                .ok_or_else(|| format!("no printer named {} in {}", name, path.display()))?,
+            None if config.printers.len() == 1 => config.printers.values().next().unwrap(),
+            None => {
+                let names: Vec<_> = config.printers.keys().map(String::as_str).collect();
+                return Err(
+                    format!("choose a printer with --printer: {}", names.join(", ")).into(),
+                );
+            }
+        };
+        Ok(PrinterBuilder::from_config(printer)?.build())
+    }
+}
+
+impl OriginArg {
+    fn path(&self, path: String) -> PathDescriptor {
+        PathDescriptor {
+            location: if self.sdcard {
+                FileLocation::Sdcard
+            } else {
+                FileLocation::Local
+            },
+            path,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("octoctl: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> CliResult {
+    let printer = cli.target.printer()?;
+
+    match cli.command {
+        Command::Status => status(&printer, cli.json).await,
+        Command::Files(command) => files(&printer, command, cli.json).await,
+        Command::Job { command: None } => job(&printer, cli.json).await,
+        Command::Job {
+            command: Some(action),
+        } => job_action(&printer, action).await,
+        Command::Temp {
+            tool,
+            temperature,
+            offset,
+        } => {
+            let command = if offset {
+                ToolTempDescriptor::Offset {
+                    tool,
+                    amount: temperature,
+                }
+            } else {
+                ToolTempDescriptor::Target { tool, temperature }
+            };
+            printer.tool_temperature(command).await.map_err(api)
+        }
+        Command::Bed {
+            temperature,
+            offset,
+        } => {
+            let command = if offset {
+                BedTempDescriptor::Offset {
+                    amount: temperature,
+                }
+            } else {
+                BedTempDescriptor::Target {
+                    target: temperature,
+                }
+            };
+            printer.change_bed_temp(command).await.map_err(api)
+        }
+        Command::Jog { x, y, z } => printer
+            .move_printhead(PrintheadMoveDescriptor::Relative { x, y, z })
+            .await
+            .map_err(api),
+        Command::Home { axes } => {
+            let command = if axes.is_empty() {
+                PrintheadMoveDescriptor::HOME_ALL
+            } else {
+                PrintheadMoveDescriptor::Home {
+                    x: axes.contains(&Axis::X),
+                    y: axes.contains(&Axis::Y),
+                    z: axes.contains(&Axis::Z),
+                }
+            };
+            printer.move_printhead(command).await.map_err(api)
+        }
+        Command::Gcode { commands } => printer.send_gcode(commands).await.map_err(api),
+    }
+}
+
+async fn status(printer: &Printer, json: bool) -> CliResult {
+    let connection = printer.get_connection().await.map_err(api)?;
+    // A printer that is not connected to OctoPrint has no telemetry
+    let telemetry = match printer.get_printer_telemetry().await {
+        Ok(telemetry) => Some(telemetry),
+        Err(DeviceStateError::Conflict(_)) => None,
+        Err(e) => return Err(api(e)),
+    };
+    let job = printer.get_job().await.map_err(api)?;
+
+    if json {
+        return Ok(output::json(&serde_json::json!({
+            "connection": connection.current,
+            "printer": telemetry,
+            "job": job,
+        }))?);
+    }
+
+    let current = &connection.current;
+    let state = telemetry.as_ref().map_or(&current.state, |t| &t.state.text);
+    output::field("State", state);
+    if let (Some(port), Some(baudrate)) = (&current.port, current.baudrate) {
+        output::field(
+            "Port",
+            format!("{} @ {} ({})", port, baudrate, current.printer_profile),
+        );
+    }
+    print_job(&job);
+
+    if let Some(telemetry) = telemetry {
+        let mut heaters: Vec<_> = telemetry.temperature.tools.iter().collect();
+        heaters.sort_by_key(|(name, _)| name.as_str());
+        let rows: Vec<_> = heaters
+            .into_iter()
+            .map(|(name, tool)| {
+                [
+                    name.clone(),
+                    format!("{:.1}", tool.actual),
+                    output::temperature(tool.target),
+                ]
+            })
+            .collect();
+        println!();
+        output::table(["HEATER", "ACTUAL", "TARGET"], &rows);
+    }
+    Ok(())
+}
+
+async fn job(printer: &Printer, json: bool) -> CliResult {
+    let job = printer.get_job().await.map_err(api)?;
+    if json {
+        return Ok(output::json(&job)?);
+    }
+
+    output::field("State", &job.state);
+    print_job(&job);
+    Ok(())
+}
+
+fn print_job(job: &JobInformation) {
+    let Some(path) = job.job.file.path.as_ref().or(job.job.file.name.as_ref()) else {
+        return;
+    };
+    output::field("File", path);
+
+    let progress = &job.progress;
+    if let Some(completion) = progress.completion {
+        let mut line = format!("{:.1}%", completion);
+        if let Some(elapsed) = progress.print_time {
+            line += &format!(", {} elapsed", format::duration(elapsed));
+        }
+        if let Some(left) = progress.print_time_left {
+            line += &format!(", {} left", format::duration(left));
+        }
+        output::field("Progress", line);
+    }
+}
+
+async fn job_action(printer: &Printer, action: JobAction) -> CliResult {
+    let command = match action {
+        JobAction::Start {
+            file: Some(file),
+            origin,
+        } => {
+            return printer
+                .issue_file_command(FileCommandDescriptor {
+                    command: FileCommand::Select { print: true },
+                    path: origin.path(file),
+                })
+                .await
+                .map_err(api);
+        }
+        JobAction::Start { file: None, .. } => JobCommand::Start,
+        JobAction::Pause => JobCommand::Pause,
+        JobAction::Resume => JobCommand::Resume,
+        JobAction::Cancel => JobCommand::Cancel,
+    };
+    printer.issue_job_command(command).await.map_err(api)
+}
+
+async fn files(printer: &Printer, command: FilesCommand, json: bool) -> CliResult {
+    match command {
+        FilesCommand::Ls { origin } => {
+            let location = match origin {
+                None => FilesLocation::Root,
+                Some(Origin::Local) => FilesLocation::Local,
+                Some(Origin::Sdcard) => FilesLocation::Sdcard,
+            };
+            let files = printer
+                .get_files(FilesFetchDescriptor {
+                    location,
+                    recursive: true,
+                    force: false,
+                })
+                .await
+                .map_err(api)?;
+            if json {
+                return Ok(output::json(&files)?);
+            }
+
+            let mut rows = vec![];
+            list(&files.files, &mut rows);
+            rows.sort();
+            output::table(["ORIGIN", "PATH", "SIZE"], &rows);
+            println!();
+            output::field(
+                "Free",
+                format!(
+                    "{} of {}",
+                    format::size(files.free),
+                    format::size(files.total)
+                ),
+            );
+            Ok(())
+        }
+        FilesCommand::Upload {
+            file,
+            destination,
+            origin,
+            select,
+            print,
+        } => {
+            let destination = match destination {
+                Some(destination) => destination,
+                None => file
+                    .file_name()
+                    .ok_or_else(|| format!("{} is not a file", file.display()))?
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            let contents = std::fs::read(&file)
+                .map_err(|e| format!("could not read {}: {}", file.display(), e))?;
+            let response = printer
+                .upload_file(FileUploadDescriptor {
+                    path: origin.path(destination),
+                    contents,
+                    select,
+                    print,
+                })
+                .await
+                .map_err(api)?;
+            if json {
+                output::json(&response)?;
+            }
+            Ok(())
+        }
+        FilesCommand::Rm { path, origin } => {
+            printer.delete_file(origin.path(path)).await.map_err(api)
+        }
+        FilesCommand::Mv {
+            source,
+            destination,
+            origin,
+        } => printer
+            .issue_file_command(FileCommandDescriptor {
+                command: FileCommand::Move { destination },
+                path: origin.path(source),
+            })
+            .await
+            .map_err(api),
+        FilesCommand::Cp {
+            source,
+            destination,
+            origin,
+        } => printer
+            .issue_file_command(FileCommandDescriptor {
+                command: FileCommand::Copy { destination },
+                path: origin.path(source),
+            })
+            .await
+            .map_err(api),
+    }
+}
+
+/// Flattens the entries into rows, marking folders with a trailing `/`.
+fn list(entries: &[Entry], rows: &mut Vec<[String; 3]>) {
+    for entry in entries {
+        match entry {
+            Entry::File {
+                origin, path, size, ..
+            } => rows.push([
+                origin.clone(),
+                path.clone(),
+                size.map_or("-".to_string(), format::size),
+            ]),
+            Entry::Folder {
+                origin,
+                path,
+                children,
+                ..
+            } => {
+                rows.push([origin.clone(), format!("{}/", path), "-".to_string()]);
+                list(children, rows);
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// Prints `value` as pretty JSON.
+pub fn json<T: Serialize>(value: &T) -> Result<(), serde_json::Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints rows as a table with a header, every column padded to its widest cell.
+pub fn table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    print_row(&widths, header);
+    for row in rows {
+        print_row(&widths, row);
+    }
+}
+
+fn print_row<const N: usize, T: Display>(widths: &[usize; N], cells: impl IntoIterator<Item = T>) {
+    let line = cells
+        .into_iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+        .collect::<Vec<_>>()
+        .join("  ");
+    println!("{}", line.trim_end());
+}
+
+/// Prints `label: value` with the values of consecutive calls aligned.
+pub fn field(label: &str, value: impl Display) {
+    println!("{:<10} {}", format!("{}:", label), value);
+}
+
+/// Formats an optional temperature, `-` if there is none.
+pub fn temperature(value: Option<f32>) -> String {
+    value.map_or("-".to_string(), |t| format!("{:.1}", t))
+}
@@ -0,0 +1,540 @@
+use std::{collections::BTreeMap, fmt};
+
+use octoprint_rs::{
+    errors::OctoPrintError,
+    fleet::FleetResults,
+    types::{BedState, JobInformation, ToolState},
+};
+use ratatui::{
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    widgets::{ListState, TableState},
+};
+
+/// The distances in mm the printhead can be jogged by.
+pub const JOG_STEPS: [f32; 4] = [0.1, 1.0, 10.0, 100.0];
+
+/// What was last polled from a printer. The temperatures are `None` while the printer is not
+/// connected to OctoPrint.
+pub struct PrinterStatus {
+    pub job: JobInformation,
+    pub tools: Option<ToolState>,
+    pub bed: Option<BedState>,
+}
+
+#[derive(Clone)]
+pub struct FileEntry {
+    pub origin: String,
+    pub path: String,
+    pub size: Option<u64>,
+}
+
+/// A command for a printer, run in the background.
+pub enum Action {
+    Pause,
+    Resume,
+    Cancel,
+    Print(FileEntry),
+    Jog { x: f32, y: f32, z: f32 },
+    Home,
+}
+
+/// Work for the event loop, which the dashboard can not do on its own.
+pub enum Request {
+    Files { printer: String },
+    Run { printer: String, action: Action },
+}
+
+pub enum Message {
+    Statuses(FleetResults<PrinterStatus, OctoPrintError>),
+    Terminal(Event),
+    Files {
+        printer: String,
+        result: Result<Vec<FileEntry>, String>,
+    },
+    Done {
+        printer: String,
+        action: String,
+        result: Result<(), String>,
+    },
+}
+
+pub enum Mode {
+    Normal,
+    Jog,
+    Files {
+        printer: String,
+        files: Option<Result<Vec<FileEntry>, String>>,
+        list: ListState,
+    },
+    Confirm {
+        printer: String,
+        action: Action,
+    },
+}
+
+pub struct Notice {
+    pub text: String,
+    pub error: bool,
+}
+
+/// The state of the dashboard.
+pub struct App {
+    pub printers: Vec<String>,
+    pub statuses: BTreeMap<String, Result<PrinterStatus, String>>,
+    pub table: TableState,
+    pub mode: Mode,
+    pub jog_step: usize,
+    pub notice: Option<Notice>,
+    pub quit: bool,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Pause => write!(f, "pause"),
+            Action::Resume => write!(f, "resume"),
+            Action::Cancel => write!(f, "cancel the print"),
+            Action::Print(file) => write!(f, "print {}", file.path),
+            Action::Jog { x, y, z } => write!(f, "jog by ({}, {}, {}) mm", x, y, z),
+            Action::Home => write!(f, "home"),
+        }
+    }
+}
+
+impl App {
+    pub fn new(printers: Vec<String>) -> Self {
+        App {
+            printers,
+            statuses: BTreeMap::new(),
+            table: TableState::default().with_selected(0),
+            mode: Mode::Normal,
+            jog_step: 2,
+            notice: None,
+            quit: false,
+        }
+    }
+
+    /// The name of the selected printer.
+    pub fn selected(&self) -> Option<&str> {
+        self.table
+            .selected()
+            .and_then(|i| self.printers.get(i))
+            .map(String::as_str)
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Request> {
+        match message {
+            Message::Statuses(results) => {
+                self.statuses = results
+                    .into_iter()
+                    .map(|(name, result)| (name, result.map_err(|e| e.to_string())))
+                    .collect();
+                None
+            }
+            Message::Files { printer, result } => {
+                if let Mode::Files {
+                    printer: shown,
+                    files,
+                    list,
+                } = &mut self.mode
+                {
+                    if *shown == printer {
+                        list.select(result.as_ref().ok().filter(|f| !f.is_empty()).map(|_| 0));
+                        *files = Some(result);
+                    }
+                }
+                None
+            }
+            Message::Done {
+                printer,
+                action,
+                result,
+            } => {
+                self.notice = Some(match result {
+                    Ok(()) => Notice {
+                        text: format!("{}: {}", printer, action),
+                        error: false,
+                    },
+                    Err(e) => Notice {
+                        text: format!("{}: failed to {}: {}", printer, action, e),
+                        error: true,
+                    },
+                });
+                None
+            }
+            Message::Terminal(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                self.on_key(key)
+            }
+            Message::Terminal(_) => None,
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Option<Request> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return None;
+        }
+
+        match &mut self.mode {
+            Mode::Normal => self.on_normal_key(key.code),
+            Mode::Jog => self.on_jog_key(key.code),
+            Mode::Files {
+                printer,
+                files,
+                list,
+            } => match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.mode = Mode::Normal;
+                    None
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    list.select_previous();
+                    None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let count = files
+                        .as_ref()
+                        .and_then(|f| f.as_ref().ok())
+                        .map_or(0, Vec::len);
+                    list.select(next(list.selected(), count));
+                    None
+                }
+                KeyCode::Char('r') => {
+                    *files = None;
+                    Some(Request::Files {
+                        printer: printer.clone(),
+                    })
+                }
+                KeyCode::Enter => {
+                    let file = match (files, list.selected()) {
+                        (Some(Ok(files)), Some(i)) => files.get(i)?.clone(),
+                        _ => return None,
+                    };
+                    self.mode = Mode::Confirm {
+                        printer: printer.clone(),
+                        action: Action::Print(file),
+                    };
+                    None
+                }
+                _ => None,
+            },
+            Mode::Confirm { .. } => {
+                let Mode::Confirm { printer, action } =
+                    std::mem::replace(&mut self.mode, Mode::Normal)
+                else {
+                    unreachable!()
+                };
+                matches!(key.code, KeyCode::Char('y') | KeyCode::Enter)
+                    .then_some(Request::Run { printer, action })
+            }
+        }
+    }
+
+    fn on_normal_key(&mut self, code: KeyCode) -> Option<Request> {
+        if matches!(code, KeyCode::Char('q') | KeyCode::Esc) {
+            self.quit = true;
+            return None;
+        }
+
+        let printer = self.selected()?.to_string();
+        let action = match code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.table.select_previous();
+                return None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.table
+                    .select(next(self.table.selected(), self.printers.len()));
+                return None;
+            }
+            KeyCode::Char('m') => {
+                self.mode = Mode::Jog;
+                return None;
+            }
+            KeyCode::Char('f') => {
+                self.mode = Mode::Files {
+                    printer: printer.clone(),
+                    files: None,
+                    list: ListState::default(),
+                };
+                return Some(Request::Files { printer });
+            }
+            KeyCode::Char('c') => {
+                self.mode = Mode::Confirm {
+                    printer,
+                    action: Action::Cancel,
+                };
+                return None;
+            }
+            KeyCode::Char('p') => Action::Pause,
+            KeyCode::Char('r') => Action::Resume,
+            _ => return None,
+        };
+        Some(Request::Run { printer, action })
+    }
+
+    fn on_jog_key(&mut self, code: KeyCode) -> Option<Request> {
+        let step = JOG_STEPS[self.jog_step];
+        let action = match code {
+            KeyCode::Esc | KeyCode::Char('m') | KeyCode::Char('q') => {
+                self.mode = Mode::Normal;
+                return None;
+            }
+            KeyCode::Char('-') | KeyCode::Char('[') => {
+                self.jog_step = self.jog_step.saturating_sub(1);
+                return None;
+            }
+            KeyCode::Char('+') | KeyCode::Char(']') => {
+                self.jog_step = (self.jog_step + 1).min(JOG_STEPS.len() - 1);
+                return None;
+            }
+            KeyCode::Left => jog(-step, 0.0, 0.0),
+            KeyCode::Right => jog(step, 0.0, 0.0),
+            KeyCode::Up => jog(0.0, step, 0.0),
+            KeyCode::Down => jog(0.0, -step, 0.0),
+            KeyCode::PageUp => jog(0.0, 0.0, step),
+            KeyCode::PageDown => jog(0.0, 0.0, -step),
+            KeyCode::Char('h') => Action::Home,
+            _ => return None,
+        };
+        Some(Request::Run {
+            printer: self.selected()?.to_string(),
+            action,
+        })
+    }
+}
+
+/// The row after `selected`, staying on the last one of `count` rows.
+fn next(selected: Option<usize>, count: usize) -> Option<usize> {
+    match selected {
+        _ if count == 0 => None,
+        Some(i) => Some((i + 1).min(count - 1)),
+        None => Some(0),
+    }
+}
+
+fn jog(x: f32, y: f32, z: f32) -> Action {
+    Action::Jog { x, y, z }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        App::new(vec!["prusa-1".to_string(), "prusa-2".to_string()])
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Request> {
+        app.update(Message::Terminal(Event::Key(KeyEvent::new(
+            code,
+            KeyModifiers::NONE,
+        ))))
+    }
+
+    fn benchy() -> FileEntry {
+        FileEntry {
+            origin: "local".to_string(),
+            path: "benchy.gcode".to_string(),
+            size: Some(1_200_000),
+        }
+    }
+
+    #[test]
+    fn next_stays_on_the_last_row() {
+        assert_eq!(next(None, 0), None);
+        assert_eq!(next(Some(1), 0), None);
+        assert_eq!(next(None, 3), Some(0));
+        assert_eq!(next(Some(0), 3), Some(1));
+        assert_eq!(next(Some(2), 3), Some(2));
+    }
+
+    #[test]
+    fn selects_printers() {
+        let mut app = app();
+        assert_eq!(app.selected(), Some("prusa-1"));
+
+        assert!(press(&mut app, KeyCode::Char('j')).is_none());
+        assert_eq!(app.selected(), Some("prusa-2"));
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected(), Some("prusa-2"));
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.selected(), Some("prusa-1"));
+
+        let release = KeyEvent {
+            kind: KeyEventKind::Release,
+            ..KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE)
+        };
+        app.update(Message::Terminal(Event::Key(release)));
+        assert_eq!(app.selected(), Some("prusa-1"));
+    }
+
+    #[test]
+    fn runs_pause_and_resume_right_away() {
+        let mut app = app();
+
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('p')),
+            Some(Request::Run { printer, action: Action::Pause }) if printer == "prusa-1"
+        ));
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('r')),
+            Some(Request::Run {
+                action: Action::Resume,
+                ..
+            })
+        ));
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn confirms_cancel() {
+        let mut app = app();
+
+        assert!(press(&mut app, KeyCode::Char('c')).is_none());
+        assert!(matches!(
+            app.mode,
+            Mode::Confirm {
+                action: Action::Cancel,
+                ..
+            }
+        ));
+        assert!(press(&mut app, KeyCode::Char('n')).is_none());
+        assert!(matches!(app.mode, Mode::Normal));
+
+        press(&mut app, KeyCode::Char('c'));
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('y')),
+            Some(Request::Run {
+                action: Action::Cancel,
+                ..
+            })
+        ));
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn prints_a_file_from_the_list() {
+        let mut app = app();
+
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('f')),
+            Some(Request::Files { printer }) if printer == "prusa-1"
+        ));
+        assert!(press(&mut app, KeyCode::Enter).is_none());
+        assert!(matches!(app.mode, Mode::Files { files: None, .. }));
+
+        app.update(Message::Files {
+            printer: "prusa-2".to_string(),
+            result: Ok(vec![benchy()]),
+        });
+        assert!(matches!(app.mode, Mode::Files { files: None, .. }));
+        app.update(Message::Files {
+            printer: "prusa-1".to_string(),
+            result: Ok(vec![benchy()]),
+        });
+        let Mode::Files { list, .. } = &app.mode else {
+            panic!("expected the file list");
+        };
+        assert_eq!(list.selected(), Some(0));
+
+        assert!(press(&mut app, KeyCode::Enter).is_none());
+        assert!(matches!(
+            &app.mode,
+            Mode::Confirm { action: Action::Print(file), .. } if file.path == "benchy.gcode"
+        ));
+        assert!(matches!(
+            press(&mut app, KeyCode::Enter),
+            Some(Request::Run {
+                action: Action::Print(_),
+                ..
+            })
+        ));
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn leaves_and_reloads_the_file_list() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('f'));
+        app.update(Message::Files {
+            printer: "prusa-1".to_string(),
+            result: Err("offline".to_string()),
+        });
+
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('r')),
+            Some(Request::Files { .. })
+        ));
+        assert!(matches!(app.mode, Mode::Files { files: None, .. }));
+        assert!(press(&mut app, KeyCode::Esc).is_none());
+        assert!(matches!(app.mode, Mode::Normal));
+        assert!(!app.quit);
+    }
+
+    #[test]
+    fn jogs_by_the_selected_step() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('m'));
+        assert!(matches!(app.mode, Mode::Jog));
+
+        assert!(matches!(
+            press(&mut app, KeyCode::Right),
+            Some(Request::Run { action: Action::Jog { x, y, z }, .. })
+                if x == 10.0 && y == 0.0 && z == 0.0
+        ));
+        press(&mut app, KeyCode::Char('+'));
+        press(&mut app, KeyCode::Char('+'));
+        assert_eq!(app.jog_step, JOG_STEPS.len() - 1);
+        assert!(matches!(
+            press(&mut app, KeyCode::PageDown),
+            Some(Request::Run { action: Action::Jog { z, .. }, .. }) if z == -100.0
+        ));
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('h')),
+            Some(Request::Run {
+                action: Action::Home,
+                ..
+            })
+        ));
+
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn quits() {
+        let mut quit = app();
+        press(&mut quit, KeyCode::Char('q'));
+        assert!(quit.quit);
+
+        let mut app = app();
+        press(&mut app, KeyCode::Char('f'));
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        app.update(Message::Terminal(Event::Key(ctrl_c)));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn keeps_statuses_and_notices() {
+        let mut app = app();
+
+        let results = [(
+            "prusa-1".to_string(),
+            Err(OctoPrintError::InvalidArgument("offline".to_string())),
+        )]
+        .into_iter()
+        .collect();
+        app.update(Message::Statuses(results));
+        assert!(matches!(&app.statuses["prusa-1"], Err(e) if e.contains("offline")));
+
+        app.update(Message::Done {
+            printer: "prusa-1".to_string(),
+            action: "pause".to_string(),
+            result: Err("busy".to_string()),
+        });
+        let notice = app.notice.as_ref().unwrap();
+        assert!(notice.error);
+        assert_eq!(notice.text, "prusa-1: failed to pause: busy");
+    }
+}
@@ -0,0 +1,283 @@
+//! `octodash`, a terminal dashboard for a farm of OctoPrint printers.
+//!
+//! Every printer of a configuration file (see
+//! [`FleetConfig`](octoprint_rs::config::FleetConfig)) is polled for its job and temperatures.
+//! The selected printer can be paused, resumed, cancelled, jogged, and given a file to print
+//! from its file browser. The keys are shown at the bottom of the screen.
+//!
+//! ```text
+//! octodash --config printers.toml --interval 5
+//! ```
+
+mod app;
+mod ui;
+
+use std::{error::Error, path::PathBuf, process::ExitCode, sync::Arc, time::Duration};
+
+use clap::Parser;
+use octoprint_rs::{
+    config::FleetConfig,
+    errors::{DeviceStateError, OctoPrintError},
+    fleet::Fleet,
+    types::{printer_files::Entry, *},
+    Printer, PrinterBuilder,
+};
+use ratatui::{crossterm::event, DefaultTerminal};
+use tokio::sync::{mpsc, Notify};
+
+use app::{Action, App, FileEntry, Message, PrinterStatus, Request};
+
+#[derive(Parser)]
+#[command(
+    name = "octodash",
+    version,
+    about = "A terminal dashboard for OctoPrint printers"
+)]
+struct Cli {
+    /// A TOML or JSON file with printer configurations.
+    #[arg(long, env = "OCTOCTL_CONFIG", required_unless_present = "url")]
+    config: Option<PathBuf>,
+
+    /// The base url of a single printer, instead of a configuration file.
+    #[arg(
+        long,
+        env = "OCTOPRINT_URL",
+        conflicts_with = "config",
+        requires = "api_key"
+    )]
+    url: Option<String>,
+
+    /// The api key to use with `--url`.
+    #[arg(long, env = "OCTOPRINT_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// Seconds between two polls of every printer.
+    #[arg(long, default_value_t = 2.0)]
+    interval: f64,
+}
+
+impl Cli {
+    fn fleet(&self) -> Result<Fleet, Box<dyn Error>> {
+        if let (Some(url), Some(api_key)) = (&self.url, &self.api_key) {
+            let mut fleet = Fleet::new();
+            fleet.add(url, PrinterBuilder::from_base_url(url, api_key)?.build());
+            return Ok(fleet);
+        }
+
+        let path = self
+            .config
+            .as_ref()
+            .ok_or("--config or --url is required")?;
+        Ok(FleetConfig::load(path)?.fleet()?)
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let setup = cli.fleet().and_then(|fleet| {
+        let interval = Duration::try_from_secs_f64(cli.interval)
+            .map_err(|e| format!("invalid interval {}: {}", cli.interval, e))?;
+        Ok((fleet, interval))
+    });
+    let (fleet, interval) = match setup {
+        Ok(setup) => setup,
+        Err(e) => {
+            eprintln!("octodash: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, Arc::new(fleet), interval).await;
+    ratatui::restore();
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("octodash: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(
+    terminal: &mut DefaultTerminal,
+    fleet: Arc<Fleet>,
+    interval: Duration,
+) -> Result<(), Box<dyn Error>> {
+    let (messages, mut inbox) = mpsc::unbounded_channel();
+    let refresh = Arc::new(Notify::new());
+    tokio::spawn(poll_fleet(
+        fleet.clone(),
+        interval,
+        messages.clone(),
+        refresh.clone(),
+    ));
+
+    // Reading the terminal blocks, so it gets a thread of its own
+    let events = messages.clone();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if events.send(Message::Terminal(event)).is_err() {
+                return;
+            }
+        }
+    });
+
+    let mut app = App::new(fleet.names().map(str::to_string).collect());
+    loop {
+        terminal.draw(|frame| ui::draw(frame, &mut app))?;
+
+        let Some(message) = inbox.recv().await else {
+            return Ok(());
+        };
+        if let Some(request) = app.update(message) {
+            execute(&fleet, request, &messages, &refresh);
+        }
+        if app.quit {
+            return Ok(());
+        }
+    }
+}
+
+/// Polls every printer, every `interval` or right after `refresh` is notified.
+async fn poll_fleet(
+    fleet: Arc<Fleet>,
+    interval: Duration,
+    messages: mpsc::UnboundedSender<Message>,
+    refresh: Arc<Notify>,
+) {
+    loop {
+        let statuses = fleet.run(poll).await;
+        if messages.send(Message::Statuses(statuses)).is_err() {
+            return;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = refresh.notified() => {}
+        }
+    }
+}
+
+async fn poll(printer: Arc<Printer>) -> Result<PrinterStatus, OctoPrintError> {
+    let (job, tools, bed) = tokio::join!(
+        printer.get_job(),
+        printer.get_tool_state(None),
+        printer.get_bed_state(None)
+    );
+    Ok(PrinterStatus {
+        job: job?,
+        tools: operational(tools)?,
+        bed: operational(bed)?,
+    })
+}
+
+/// `None` if the printer is not operational, which OctoPrint answers with `409 Conflict`.
+fn operational<T>(result: Result<T, DeviceStateError>) -> Result<Option<T>, OctoPrintError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(DeviceStateError::Conflict(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Runs a request of the dashboard in the background and reports back with a message.
+fn execute(
+    fleet: &Fleet,
+    request: Request,
+    messages: &mpsc::UnboundedSender<Message>,
+    refresh: &Arc<Notify>,
+) {
+    let name = match &request {
+        Request::Files { printer } | Request::Run { printer, .. } => printer,
+    };
+    let Some(printer) = fleet.get(name).cloned() else {
+        return;
+    };
+    let (messages, refresh) = (messages.clone(), refresh.clone());
+
+    tokio::spawn(async move {
+        let message = match request {
+            Request::Files { printer: name } => Message::Files {
+                printer: name,
+                result: files(&printer).await.map_err(|e| e.to_string()),
+            },
+            Request::Run {
+                printer: name,
+                action,
+            } => {
+                let description = action.to_string();
+                let result = action.run(&printer).await.map_err(|e| e.to_string());
+                refresh.notify_one();
+                Message::Done {
+                    printer: name,
+                    action: description,
+                    result,
+                }
+            }
+        };
+        let _ = messages.send(message);
+    });
+}
+
+impl Action {
+    async fn run(self, printer: &Printer) -> Result<(), OctoPrintError> {
+        match self {
+            Action::Pause => printer.issue_job_command(JobCommand::Pause).await?,
+            Action::Resume => printer.issue_job_command(JobCommand::Resume).await?,
+            Action::Cancel => printer.issue_job_command(JobCommand::Cancel).await?,
+            Action::Print(file) => {
+                let location = if file.origin == "sdcard" {
+                    FileLocation::Sdcard
+                } else {
+                    FileLocation::Local
+                };
+                printer
+                    .issue_file_command(FileCommandDescriptor {
+                        command: FileCommand::Select { print: true },
+                        path: PathDescriptor {
+                            location,
+                            path: file.path,
+                        },
+                    })
+                    .await?
+            }
+            Action::Jog { x, y, z } => {
+                printer
+                    .move_printhead(PrintheadMoveDescriptor::Relative { x, y, z })
+                    .await?
+            }
+            Action::Home => {
+                printer
+                    .move_printhead(PrintheadMoveDescriptor::HOME_ALL)
+                    .await?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every file of both storages, sorted by path.
+async fn files(printer: &Printer) -> Result<Vec<FileEntry>, OctoPrintError> {
+    let files = printer
+        .get_files(FilesFetchDescriptor {
+            location: FilesLocation::Root,
+            recursive: true,
+            force: false,
+        })
+        .await?;
+
+    let mut entries = vec![];
+    let mut pending: Vec<Entry> = files.files;
+    while let Some(entry) = pending.pop() {
+        match entry {
+            Entry::File {
+                origin, path, size, ..
+            } => entries.push(FileEntry { origin, path, size }),
+            Entry::Folder { children, .. } => pending.extend(children),
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
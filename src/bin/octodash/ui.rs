@@ -0,0 +1,262 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Cell, Clear, Gauge, List, ListItem, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+
+use octoprint_rs::format::{self, duration};
+
+use crate::app::{App, Mode, PrinterStatus, JOG_STEPS};
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let rows = app.printers.len() as u16;
+    let [printers, details, footer] = Layout::vertical([
+        Constraint::Length(rows + 3),
+        Constraint::Min(8),
+        Constraint::Length(2),
+    ])
+    .areas(frame.area());
+
+    draw_printers(frame, app, printers);
+    draw_details(frame, app, details);
+    draw_footer(frame, app, footer);
+
+    match &mut app.mode {
+        Mode::Files {
+            printer,
+            files,
+            list,
+        } => {
+            let area = popup(frame.area(), 70, 20);
+            let block = Block::bordered().title(format!(" Files on {} ", printer));
+            frame.render_widget(Clear, area);
+            match files {
+                None => frame.render_widget(Paragraph::new("Loading…").block(block), area),
+                Some(Err(e)) => {
+                    frame.render_widget(Paragraph::new(e.as_str()).red().block(block), area)
+                }
+                Some(Ok(files)) => {
+                    let items = files.iter().map(|file| {
+                        let size = file.size.map_or("".to_string(), format::size);
+                        ListItem::new(Line::from(vec![
+                            Span::raw(format!("{:<7} ", file.origin)).dark_gray(),
+                            Span::raw(file.path.as_str()),
+                            Span::raw(format!("  {}", size)).dark_gray(),
+                        ]))
+                    });
+                    let list_widget = List::new(items)
+                        .block(block)
+                        .highlight_style(Style::new().reversed());
+                    frame.render_stateful_widget(list_widget, area, list);
+                }
+            }
+        }
+        Mode::Confirm { printer, action } => {
+            let area = popup(frame.area(), 50, 5);
+            let text = format!("{} on {}?\n\ny: yes, any other key: no", action, printer);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(text)
+                    .wrap(Wrap { trim: true })
+                    .block(Block::bordered().title(" Confirm ").yellow()),
+                area,
+            );
+        }
+        Mode::Normal | Mode::Jog => {}
+    }
+}
+
+fn draw_printers(frame: &mut Frame, app: &mut App, area: Rect) {
+    let rows = app.printers.iter().map(|name| {
+        let cells = match app.statuses.get(name) {
+            None => vec![Cell::from(name.as_str()), Cell::from("…")],
+            Some(Err(e)) => vec![Cell::from(name.as_str()), Cell::from(e.as_str()).red()],
+            Some(Ok(status)) => {
+                let progress = &status.job.progress;
+                let tool0 = status
+                    .tools
+                    .as_ref()
+                    .and_then(|t| t.tools.get("tool0"))
+                    .map(|t| temperature(t.actual, t.target));
+                let bed = status
+                    .bed
+                    .as_ref()
+                    .map(|b| temperature(b.bed.actual, b.bed.target));
+                vec![
+                    Cell::from(name.as_str()),
+                    Cell::from(status.job.state.as_str()).style(state_style(&status.job.state)),
+                    Cell::from(
+                        progress
+                            .completion
+                            .map_or("-".to_string(), |c| format!("{:.1}%", c)),
+                    ),
+                    Cell::from(progress.print_time_left.map_or("-".to_string(), duration)),
+                    Cell::from(tool0.unwrap_or_else(|| "-".to_string())),
+                    Cell::from(bed.unwrap_or_else(|| "-".to_string())),
+                ]
+            }
+        };
+        Row::new(cells)
+    });
+
+    let header = Row::new(["PRINTER", "STATE", "PROGRESS", "LEFT", "TOOL0", "BED"]).bold();
+    let widths = [
+        Constraint::Fill(2),
+        Constraint::Fill(2),
+        Constraint::Length(9),
+        Constraint::Length(8),
+        Constraint::Length(14),
+        Constraint::Length(14),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::bordered().title(" Printers "))
+        .row_highlight_style(Style::new().reversed());
+    frame.render_stateful_widget(table, area, &mut app.table);
+}
+
+fn draw_details(frame: &mut Frame, app: &App, area: Rect) {
+    let [temperatures, job] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(area);
+    let name = app.selected().unwrap_or("");
+    let status = app.statuses.get(name).and_then(|s| s.as_ref().ok());
+
+    let temperature_block = Block::bordered().title(" Temperatures ");
+    match status.filter(|s| s.tools.is_some() || s.bed.is_some()) {
+        Some(status) => frame.render_widget(
+            Table::new(heaters(status), [Constraint::Fill(1); 3])
+                .header(Row::new(["HEATER", "ACTUAL", "TARGET"]).bold())
+                .block(temperature_block),
+            temperatures,
+        ),
+        None => frame.render_widget(
+            Paragraph::new("Not connected").block(temperature_block),
+            temperatures,
+        ),
+    }
+
+    let job_block = Block::bordered().title(format!(" Job on {} ", name));
+    let inner = job_block.inner(job);
+    frame.render_widget(job_block, job);
+    let Some(status) = status else {
+        return;
+    };
+
+    let [text, gauge] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+    let file = &status.job.job.file;
+    let progress = &status.job.progress;
+    let lines = vec![
+        field("State", status.job.state.clone()),
+        field(
+            "File",
+            file.path
+                .clone()
+                .or(file.name.clone())
+                .unwrap_or("-".to_string()),
+        ),
+        field(
+            "Elapsed",
+            progress.print_time.map_or("-".to_string(), duration),
+        ),
+        field(
+            "Left",
+            progress.print_time_left.map_or("-".to_string(), duration),
+        ),
+    ];
+    frame.render_widget(Paragraph::new(lines), text);
+
+    let completion = progress.completion.unwrap_or(0.0).clamp(0.0, 100.0);
+    frame.render_widget(
+        Gauge::default()
+            .ratio(completion as f64 / 100.0)
+            .label(format!("{:.1}%", completion))
+            .gauge_style(Color::Green),
+        gauge,
+    );
+}
+
+fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let [notice, keys] = Layout::vertical([Constraint::Length(1); 2]).areas(area);
+    if let Some(message) = &app.notice {
+        let style = if message.error {
+            Style::new().red()
+        } else {
+            Style::new().green()
+        };
+        frame.render_widget(Paragraph::new(message.text.as_str()).style(style), notice);
+    }
+
+    let help = match app.mode {
+        Mode::Normal => {
+            "↑/↓ select  p pause  r resume  c cancel  f files  m move  q quit".to_string()
+        }
+        Mode::Jog => format!(
+            "←/→ X  ↑/↓ Y  PgUp/PgDn Z  -/+ step ({} mm)  h home  Esc back",
+            JOG_STEPS[app.jog_step]
+        ),
+        Mode::Files { .. } => "↑/↓ select  Enter print  r reload  Esc back".to_string(),
+        Mode::Confirm { .. } => "y confirm  any other key cancels".to_string(),
+    };
+    frame.render_widget(Paragraph::new(help).dark_gray(), keys);
+}
+
+/// The actual and target temperature of every tool and the bed.
+fn heaters(status: &PrinterStatus) -> Vec<Row<'static>> {
+    let mut tools: Vec<_> = status
+        .tools
+        .iter()
+        .flat_map(|t| &t.tools)
+        .map(|(name, tool)| (name.clone(), tool.actual, tool.target))
+        .collect();
+    tools.sort_by(|a, b| a.0.cmp(&b.0));
+    tools.extend(
+        status
+            .bed
+            .iter()
+            .map(|b| ("bed".to_string(), b.bed.actual, b.bed.target)),
+    );
+
+    tools
+        .into_iter()
+        .map(|(name, actual, target)| {
+            let target = target.map_or("-".to_string(), |t| format!("{:.1}", t));
+            Row::new([name, format!("{:.1}", actual), target])
+        })
+        .collect()
+}
+
+fn field(label: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::raw(format!("{:<9}", label)).bold(),
+        Span::raw(value),
+    ])
+}
+
+fn state_style(state: &str) -> Style {
+    match state {
+        "Printing" => Style::new().green(),
+        "Paused" | "Pausing" => Style::new().yellow(),
+        s if s.starts_with("Error") || s.starts_with("Offline") => Style::new().red(),
+        _ => Style::new(),
+    }
+}
+
+fn temperature(actual: f32, target: Option<f32>) -> String {
+    match target {
+        Some(target) if target > 0.0 => format!("{:.0}/{:.0}°C", actual, target),
+        _ => format!("{:.0}°C", actual),
+    }
+}
+
+/// A centered area of `percent` of the width and `height` rows.
+fn popup(area: Rect, percent: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Percentage(percent)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
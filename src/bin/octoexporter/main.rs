@@ -0,0 +1,57 @@
+//! `octoexporter`, a Prometheus exporter for the printers of a configuration file.
+//!
+//! See [`octoprint_rs::exporter`] for the metrics it serves.
+//!
+//! ```text
+//! octoexporter --config printers.toml --listen 0.0.0.0:9469 --interval 15
+//! ```
+
+use std::{error::Error, net::TcpListener, path::PathBuf, process::ExitCode, time::Duration};
+
+use clap::Parser;
+use octoprint_rs::{config::FleetConfig, exporter::Exporter};
+
+#[derive(Parser)]
+#[command(
+    name = "octoexporter",
+    version,
+    about = "Export the metrics of OctoPrint printers to Prometheus"
+)]
+struct Cli {
+    /// A TOML or JSON file with printer configurations.
+    #[arg(long, env = "OCTOCTL_CONFIG")]
+    config: PathBuf,
+
+    /// The address to serve the metrics at.
+    #[arg(long, default_value = "0.0.0.0:9469")]
+    listen: String,
+
+    /// Seconds between two polls of every printer.
+    #[arg(long, default_value_t = 15.0)]
+    interval: f64,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("octoexporter: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let fleet = FleetConfig::load(&cli.config)?.fleet()?;
+    let interval = Duration::try_from_secs_f64(cli.interval)
+        .map_err(|e| format!("invalid interval {}: {}", cli.interval, e))?;
+    let listener = TcpListener::bind(&cli.listen)
+        .map_err(|e| format!("could not listen on {}: {}", cli.listen, e))?;
+
+    Exporter::new(fleet)
+        .interval(interval)
+        .serve(listener)
+        .await?;
+    Ok(())
+}
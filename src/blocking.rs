@@ -0,0 +1,216 @@
+//! A blocking client for OctoPrint, for programs that don't want to deal with async.
+//!
+//! [`Printer`] wraps the async [`crate::Printer`] and drives every request on its own
+//! single-threaded runtime, so it shares all the `types` and `errors` with the async client.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use octoprint_rs::PrinterBuilder;
+//!
+//! let printer = PrinterBuilder::new("octopi.local", "API_KEY").build_blocking();
+//! let job = printer.get_job().unwrap();
+//! ```
+
+use std::time::Duration;
+
+use tokio::runtime::Runtime;
+
+use crate::errors::*;
+use crate::types::*;
+
+/// Defines blocking methods that run the async method of the same name to completion.
+macro_rules! blocking {
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident$(<$($generic:ident: $bound:path),*>)?(&self $(, $arg:ident: $ty:ty)*)
+            -> $ret:ty;
+    )*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name$(<$($generic: $bound),*>)?(&self $(, $arg: $ty)*) -> $ret {
+                self.runtime.block_on(self.inner.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+/// A blocking printer, created by
+/// [`PrinterBuilder::build_blocking`](crate::PrinterBuilder::build_blocking).
+///
+/// Every method mirrors the method of the same name on the async [`crate::Printer`].
+///
+/// # Panics
+///
+/// The methods panic if they are called from within an async runtime, use the async
+/// [`crate::Printer`] there instead.
+pub struct Printer {
+    inner: crate::Printer,
+    runtime: Runtime,
+}
+
+impl Printer {
+    pub(crate) fn new(inner: crate::Printer) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime of the blocking printer");
+
+        Printer { inner, runtime }
+    }
+
+    blocking! {
+        /// See [`Printer::get_api_version`](crate::Printer::get_api_version).
+        fn get_api_version(&self) -> Result<ApiVersion, InformationRequestError>;
+
+        /// See [`Printer::get_connection`](crate::Printer::get_connection).
+        fn get_connection(&self) -> Result<PrinterConnection, InformationRequestError>;
+
+        /// See [`Printer::set_connection`](crate::Printer::set_connection).
+        fn set_connection(
+            &self,
+            connection: ConnectionCommandDescriptor
+        ) -> Result<(), SetConnectionError>;
+
+        /// See [`Printer::get_printer_profiles`](crate::Printer::get_printer_profiles).
+        fn get_printer_profiles(
+            &self
+        ) -> Result<printer_profiles::Profiles, InformationRequestError>;
+
+        /// See [`Printer::get_files`](crate::Printer::get_files).
+        fn get_files(
+            &self,
+            files_descriptor: FilesFetchDescriptor
+        ) -> Result<printer_files::Files, FileRequestError>;
+
+        /// See [`Printer::print_statistics`](crate::Printer::print_statistics).
+        fn print_statistics(
+            &self
+        ) -> Result<printer_files::PrintStatisticsReport, FileRequestError>;
+
+        /// See [`Printer::upload_file`](crate::Printer::upload_file).
+        fn upload_file(&self, upload: FileUploadDescriptor) -> Result<UploadResponse, FileUploadError>;
+
+        /// See [`Printer::get_file`](crate::Printer::get_file).
+        fn get_file(
+            &self,
+            file_descriptor: FileFetchDescriptor
+        ) -> Result<printer_files::Entry, FileRequestError>;
+
+        /// See [`Printer::issue_file_command`](crate::Printer::issue_file_command).
+        fn issue_file_command(
+            &self,
+            command: FileCommandDescriptor
+        ) -> Result<(), FileCommandError>;
+
+        /// See [`Printer::delete_file`](crate::Printer::delete_file).
+        fn delete_file(&self, path: PathDescriptor) -> Result<(), FileDeletionError>;
+
+        /// See [`Printer::issue_job_command`](crate::Printer::issue_job_command).
+        fn issue_job_command(&self, command: JobCommand) -> Result<(), JobCommandError>;
+
+        /// See [`Printer::get_job`](crate::Printer::get_job).
+        fn get_job(&self) -> Result<JobInformation, InformationRequestError>;
+
+        /// See [`Printer::get_printer_telemetry`](crate::Printer::get_printer_telemetry).
+        fn get_printer_telemetry(&self) -> Result<RawPrinter, DeviceStateError>;
+
+        /// See [`Printer::send_gcode`](crate::Printer::send_gcode).
+        fn send_gcode(&self, commands: Vec<String>) -> Result<(), PrinterCommandError>;
+
+        /// See [`Printer::move_printhead`](crate::Printer::move_printhead).
+        fn move_printhead(
+            &self,
+            command: PrintheadMoveDescriptor
+        ) -> Result<(), ToolCommandError>;
+
+        /// See [`Printer::change_printhead_feedrate`](crate::Printer::change_printhead_feedrate).
+        fn change_printhead_feedrate(&self, factor: f32) -> Result<(), ToolCommandError>;
+
+        /// See [`Printer::tool_temperature`](crate::Printer::tool_temperature).
+        fn tool_temperature(&self, command: ToolTempDescriptor) -> Result<(), ToolCommandError>;
+
+        /// See [`Printer::get_tool_state`](crate::Printer::get_tool_state).
+        fn get_tool_state(&self, history: Option<u32>) -> Result<ToolState, DeviceStateError>;
+
+        /// See [`Printer::heat_and_wait`](crate::Printer::heat_and_wait).
+        fn heat_and_wait(
+            &self,
+            tool: ToolId,
+            target: f32,
+            tolerance: f32,
+            stable_for: Duration,
+            timeout: Duration
+        ) -> Result<f32, HeatError>;
+
+        /// See [`Printer::select_tool`](crate::Printer::select_tool).
+        fn select_tool(&self, tool: ToolId) -> Result<(), ToolCommandError>;
+
+        /// See [`Printer::extrude`](crate::Printer::extrude).
+        fn extrude(&self, amount: f32) -> Result<(), ToolCommandError>;
+
+        /// See [`Printer::retract`](crate::Printer::retract).
+        fn retract(&self, amount: f32) -> Result<(), ToolCommandError>;
+
+        /// See [`Printer::change_tool_flowrate`](crate::Printer::change_tool_flowrate).
+        fn change_tool_flowrate(&self, factor: f32) -> Result<(), ToolCommandError>;
+
+        /// See [`Printer::change_bed_temp`](crate::Printer::change_bed_temp).
+        fn change_bed_temp(&self, command: BedTempDescriptor) -> Result<(), ToolCommandError>;
+
+        /// See [`Printer::get_bed_state`](crate::Printer::get_bed_state).
+        fn get_bed_state(&self, history: Option<u32>) -> Result<BedState, DeviceStateError>;
+
+        /// See [`Printer::heat_bed_and_wait`](crate::Printer::heat_bed_and_wait).
+        fn heat_bed_and_wait(
+            &self,
+            target: f32,
+            tolerance: f32,
+            stable_for: Duration,
+            timeout: Duration
+        ) -> Result<f32, HeatError>;
+
+        /// See [`Printer::get_temperature_profiles`](crate::Printer::get_temperature_profiles).
+        fn get_temperature_profiles(
+            &self
+        ) -> Result<Vec<TemperatureProfile>, TemperatureProfileError>;
+
+        /// See [`Printer::apply_temperature_profile`](crate::Printer::apply_temperature_profile).
+        fn apply_temperature_profile(&self, name: &str) -> Result<(), TemperatureProfileError>;
+
+        /// See [`Printer::cool_down`](crate::Printer::cool_down).
+        fn cool_down(&self) -> Result<(), TemperatureProfileError>;
+
+        /// See [`Printer::get_pi_support`](crate::Printer::get_pi_support).
+        fn get_pi_support(&self) -> Result<PiSupport, InformationRequestError>;
+
+        /// See [`Printer::get_action_prompt`](crate::Printer::get_action_prompt).
+        fn get_action_prompt(&self) -> Result<Option<ActionPrompt>, InformationRequestError>;
+
+        /// See [`Printer::select_prompt_choice`](crate::Printer::select_prompt_choice).
+        fn select_prompt_choice(&self, choice: usize) -> Result<(), PromptCommandError>;
+
+        /// See [`Printer::get_language_packs`](crate::Printer::get_language_packs).
+        fn get_language_packs(&self) -> Result<LanguagePacks, InformationRequestError>;
+
+        /// See [`Printer::upload_language_pack`](crate::Printer::upload_language_pack).
+        fn upload_language_pack<S: ToString>(
+            &self,
+            file_name: S,
+            contents: Vec<u8>
+        ) -> Result<LanguagePacks, LanguagePackError>;
+
+        /// See [`Printer::delete_language_pack`](crate::Printer::delete_language_pack).
+        fn delete_language_pack(
+            &self,
+            locale: &str,
+            pack: &str
+        ) -> Result<LanguagePacks, LanguagePackError>;
+
+        /// See [`Printer::get_webcams`](crate::Printer::get_webcams).
+        fn get_webcams(&self) -> Result<Webcams, InformationRequestError>;
+
+        /// See [`Printer::snapshot`](crate::Printer::snapshot).
+        fn snapshot(&self) -> Result<Snapshot, SnapshotError>;
+    }
+}
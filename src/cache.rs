@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use reqwest::{
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+    },
+    RequestBuilder,
+};
+
+/// Caches the bodies of `GET` responses by url, together with their `ETag` and
+/// `Last-Modified` validators.
+///
+/// A cached body younger than the ttl is used without asking the printer. An older one is
+/// revalidated with a conditional request and used again if the printer answers
+/// `304 Not Modified`.
+pub(crate) struct ResponseCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+}
+
+struct CachedResponse {
+    body: String,
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    fetched: Instant,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        ResponseCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<String, CachedResponse>> {
+        // Entries are only ever replaced as a whole, so a poisoned lock is still usable
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The cached body of `url` if it is younger than the ttl.
+    pub(crate) fn fresh(&self, url: &str) -> Option<String> {
+        self.entries()
+            .get(url)
+            .filter(|entry| entry.fetched.elapsed() < self.ttl)
+            .map(|entry| entry.body.clone())
+    }
+
+    /// Adds the validators of the cached response of `url` to `request`.
+    pub(crate) fn conditional(&self, url: &str, request: RequestBuilder) -> RequestBuilder {
+        let entries = self.entries();
+        let Some(entry) = entries.get(url) else {
+            return request;
+        };
+
+        let validators: [(HeaderName, &Option<HeaderValue>); 2] = [
+            (IF_NONE_MATCH, &entry.etag),
+            (IF_MODIFIED_SINCE, &entry.last_modified),
+        ];
+        validators
+            .into_iter()
+            .fold(request, |request, (name, value)| match value {
+                Some(value) => request.header(name, value.clone()),
+                None => request,
+            })
+    }
+
+    /// The cached body of `url` after the printer answered `304 Not Modified`. The body counts
+    /// as fresh again.
+    pub(crate) fn revalidated(&self, url: &str) -> Option<String> {
+        let mut entries = self.entries();
+        let entry = entries.get_mut(url)?;
+        entry.fetched = Instant::now();
+        Some(entry.body.clone())
+    }
+
+    /// Caches a response, if it carries a validator or the ttl is not zero.
+    pub(crate) fn store(&self, url: &str, headers: &HeaderMap, body: &str) {
+        let etag = headers.get(ETAG).cloned();
+        let last_modified = headers.get(LAST_MODIFIED).cloned();
+        if etag.is_none() && last_modified.is_none() && self.ttl.is_zero() {
+            return;
+        }
+
+        self.entries().insert(
+            url.to_string(),
+            CachedResponse {
+                body: body.to_string(),
+                etag,
+                last_modified,
+                fetched: Instant::now(),
+            },
+        );
+    }
+
+    /// Drops every cached response whose url starts with `prefix`.
+    pub(crate) fn invalidate(&self, prefix: &str) {
+        self.entries().retain(|url, _| !url.starts_with(prefix));
+    }
+}
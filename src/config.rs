@@ -0,0 +1,338 @@
+//! Printer configurations read from TOML or JSON files, so credentials don't have to be
+//! hard-coded.
+//!
+//! A [`FleetConfig`] names any number of printers, each configured by a [`PrinterConfig`].
+//! The api key can be given inline, but is better read from an environment variable with
+//! `api_key_env` or from a file with `api_key_file`. Relative paths in a configuration file are
+//! resolved relative to the directory of the file.
+//!
+//! ```toml
+//! [printers.prusa-1]
+//! address = "10.0.0.11"
+//! port = 5000
+//! api_key_env = "PRUSA_1_API_KEY"
+//! connect_timeout_secs = 5
+//!
+//! [printers.ender]
+//! base_url = "https://farm.example/octoprint-3/"
+//! api_key_file = "secrets/ender.key"
+//!
+//! [printers.ender.tls]
+//! root_certificates = ["farm-ca.pem"]
+//! ```
+//!
+//! Reading TOML requires the `toml` feature, JSON is always supported.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use octoprint_rs::config::FleetConfig;
+//!
+//! # fn main() -> Result<(), octoprint_rs::errors::ConfigError> {
+//! let fleet = FleetConfig::load("printers.json")?.fleet()?;
+//! for name in fleet.names() {
+//!     println!("{}", name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::ConfigError, fleet::Fleet, PrinterBuilder};
+
+/// The configuration of a single printer, see [`PrinterBuilder::from_config`].
+///
+/// Exactly one of `address` and `base_url` has to be set, as well as exactly one of `api_key`,
+/// `api_key_env` and `api_key_file`. `port`, `https` and `base_path` only apply to `address`.
+///
+/// An inline `api_key` is redacted from the `Debug` output.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PrinterConfig {
+    /// The host name or IP address of the printer.
+    pub address: Option<String>,
+    /// A full base url like `https://farm.example/octoprint-3/`, instead of `address`, `port`,
+    /// `https` and `base_path`.
+    pub base_url: Option<String>,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub https: bool,
+    /// The path OctoPrint is served at, if it is behind a reverse proxy.
+    pub base_path: Option<String>,
+    pub api_key: Option<String>,
+    /// The name of an environment variable holding the api key.
+    pub api_key_env: Option<String>,
+    /// A file holding the api key. Surrounding whitespace is ignored.
+    pub api_key_file: Option<PathBuf>,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    pub connect_timeout_secs: Option<f64>,
+    /// The timeout of a whole request, including uploading a file, see
+    /// [`PrinterBuilder::timeout`]. Keep it well above the time the largest G-code file takes to
+    /// upload.
+    pub request_timeout_secs: Option<f64>,
+}
+
+impl fmt::Debug for PrinterConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrinterConfig")
+            .field("address", &self.address)
+            .field("base_url", &self.base_url)
+            .field("port", &self.port)
+            .field("https", &self.https)
+            .field("base_path", &self.base_path)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("api_key_env", &self.api_key_env)
+            .field("api_key_file", &self.api_key_file)
+            .field("tls", &self.tls)
+            .field("connect_timeout_secs", &self.connect_timeout_secs)
+            .field("request_timeout_secs", &self.request_timeout_secs)
+            .finish()
+    }
+}
+
+/// The TLS settings of a printer. Certificates and keys are read from PEM files.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// Certificates to trust in addition to the default root certificates.
+    #[serde(default)]
+    pub root_certificates: Vec<PathBuf>,
+    /// A client certificate for mutual TLS. Requires `client_key`.
+    pub client_certificate: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    /// The SHA-256 fingerprint of the only certificate to accept.
+    pub pin_sha256: Option<String>,
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+}
+
+/// Named printer configurations, read from a file with [`FleetConfig::load`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FleetConfig {
+    #[serde(default)]
+    pub printers: BTreeMap<String, PrinterConfig>,
+}
+
+impl PrinterBuilder {
+    /// Creates a `PrinterBuilder` from a [`PrinterConfig`], reading the api key and TLS files
+    /// it references. Relative paths are resolved relative to the current directory.
+    ///
+    /// # Errors
+    ///
+    /// * `MissingAddress` - If not exactly one of `address` and `base_url` is set.
+    /// * `MissingApiKey` - If not exactly one of `api_key`, `api_key_env` and `api_key_file` is
+    ///   set.
+    /// * `MissingEnvVar` - If the environment variable of `api_key_env` is not set.
+    /// * `Parse` - If `base_url` is combined with `port`, `https` or `base_path`, or only one of
+    ///   `client_certificate` and `client_key` is set.
+    /// * `Io` - If a referenced file can not be read.
+    /// * `Builder` - If the base url, a certificate, the client key or the fingerprint is
+    ///   invalid.
+    pub fn from_config(config: &PrinterConfig) -> Result<Self, ConfigError> {
+        let api_key = config.api_key()?;
+
+        let mut builder = match (&config.address, &config.base_url) {
+            (Some(address), None) => {
+                let mut builder = PrinterBuilder::new(address, api_key).https(config.https);
+                if let Some(port) = config.port {
+                    builder = builder.port(port);
+                }
+                if let Some(base_path) = &config.base_path {
+                    builder = builder.path_prefix(base_path);
+                }
+                builder
+            }
+            (None, Some(_))
+                if config.port.is_some() || config.https || config.base_path.is_some() =>
+            {
+                return Err(ConfigError::Parse(
+                    "`base_url` can not be combined with `port`, `https` or `base_path`"
+                        .to_string(),
+                ))
+            }
+            (None, Some(base_url)) => PrinterBuilder::from_base_url(base_url, api_key)?,
+            _ => return Err(ConfigError::MissingAddress),
+        };
+
+        let tls = &config.tls;
+        for certificate in &tls.root_certificates {
+            builder = builder.add_root_certificate_pem(read(certificate)?)?;
+        }
+        match (&tls.client_certificate, &tls.client_key) {
+            (Some(certificate), Some(key)) => {
+                builder = builder.client_identity_pem(read(certificate)?, read(key)?)?;
+            }
+            (None, None) => {}
+            _ => {
+                return Err(ConfigError::Parse(
+                    "`client_certificate` and `client_key` have to be set together".to_string(),
+                ))
+            }
+        }
+        if let Some(fingerprint) = &tls.pin_sha256 {
+            builder = builder.pin_certificate_sha256(fingerprint)?;
+        }
+        builder = builder.danger_accept_invalid_certs(tls.danger_accept_invalid_certs);
+
+        if let Some(timeout) = config.connect_timeout_secs {
+            builder = builder.connect_timeout(seconds(timeout)?);
+        }
+        if let Some(timeout) = config.request_timeout_secs {
+            builder = builder.timeout(seconds(timeout)?);
+        }
+        Ok(builder)
+    }
+}
+
+impl PrinterConfig {
+    fn api_key(&self) -> Result<String, ConfigError> {
+        match (&self.api_key, &self.api_key_env, &self.api_key_file) {
+            (Some(key), None, None) => Ok(key.clone()),
+            (None, Some(name), None) => {
+                std::env::var(name).map_err(|_| ConfigError::MissingEnvVar(name.clone()))
+            }
+            (None, None, Some(path)) => {
+                let key = read(path)?;
+                Ok(String::from_utf8_lossy(&key).trim().to_string())
+            }
+            _ => Err(ConfigError::MissingApiKey),
+        }
+    }
+
+    /// Resolves the relative paths of this configuration relative to `directory`.
+    fn resolve_paths(&mut self, directory: &Path) {
+        let paths = self
+            .api_key_file
+            .iter_mut()
+            .chain(self.tls.root_certificates.iter_mut())
+            .chain(self.tls.client_certificate.iter_mut())
+            .chain(self.tls.client_key.iter_mut());
+        for path in paths {
+            if path.is_relative() {
+                *path = directory.join(&*path);
+            }
+        }
+    }
+}
+
+impl FleetConfig {
+    /// Reads a configuration from a `.toml` or `.json` file.
+    ///
+    /// # Errors
+    ///
+    /// * `Io` - If the file can not be read.
+    /// * `UnsupportedFormat` - If the file is neither TOML nor JSON, or is TOML and the `toml`
+    ///   feature is disabled.
+    /// * `Parse` - If the file can not be parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let mut config = match extension.to_ascii_lowercase().as_str() {
+            "json" => Self::from_json_str(&data)?,
+            #[cfg(feature = "toml")]
+            "toml" => Self::from_toml_str(&data)?,
+            #[cfg(not(feature = "toml"))]
+            "toml" => {
+                return Err(ConfigError::UnsupportedFormat(
+                    "reading TOML requires the `toml` feature".to_string(),
+                ))
+            }
+            _ => {
+                return Err(ConfigError::UnsupportedFormat(format!(
+                    "expected a .toml or .json file, got {}",
+                    path.display()
+                )))
+            }
+        };
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        for printer in config.printers.values_mut() {
+            printer.resolve_paths(directory);
+        }
+        Ok(config)
+    }
+
+    /// Parses a configuration from JSON.
+    ///
+    /// # Errors
+    ///
+    /// * `Parse` - If the configuration can not be parsed.
+    pub fn from_json_str(json: &str) -> Result<Self, ConfigError> {
+        let deserializer = &mut serde_json::Deserializer::from_str(json);
+        serde_path_to_error::deserialize(deserializer)
+            .map_err(|e| ConfigError::Parse(format!("{}: {}", e.path(), e.inner())))
+    }
+
+    /// Parses a configuration from TOML.
+    ///
+    /// # Errors
+    ///
+    /// * `Parse` - If the configuration can not be parsed.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        toml::from_str(toml).map_err(|e| ConfigError::Parse(e.message().to_string()))
+    }
+
+    /// A `PrinterBuilder` for every printer, see [`PrinterBuilder::from_config`].
+    ///
+    /// # Errors
+    ///
+    /// The error of the first printer that can not be configured, wrapped in `Printer`.
+    pub fn builders(&self) -> Result<BTreeMap<String, PrinterBuilder>, ConfigError> {
+        self.printers
+            .iter()
+            .map(|(name, config)| {
+                let builder =
+                    PrinterBuilder::from_config(config).map_err(|e| ConfigError::Printer {
+                        name: name.clone(),
+                        source: Box::new(e),
+                    })?;
+                Ok((name.clone(), builder))
+            })
+            .collect()
+    }
+
+    /// Builds a [`Fleet`] of every printer.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`builders()`](#method.builders).
+    ///
+    /// # Panics
+    ///
+    /// Like [`PrinterBuilder::build`].
+    pub fn fleet(&self) -> Result<Fleet, ConfigError> {
+        let mut fleet = Fleet::new();
+        for (name, builder) in self.builders()? {
+            fleet.add(name, builder.build());
+        }
+        Ok(fleet)
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, ConfigError> {
+    fs::read(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn seconds(seconds: f64) -> Result<Duration, ConfigError> {
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|e| ConfigError::Parse(format!("invalid timeout {}: {}", seconds, e)))
+}
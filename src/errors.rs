@@ -1,70 +1,800 @@
+use std::fmt;
+use std::path::PathBuf;
+
 use reqwest::Error as ReqwestError;
+use reqwest::StatusCode;
+
+/// The error every request to the printer can fail with.
+///
+/// The methods on `Printer` return an error enum per operation, which can all be converted into
+/// this with `?` or [`From`]. Errors that did not fit any of the variants of the operation enum
+/// are kept in its `Unexpected` variant.
+///
+/// * `Request` occurs when the request could not be sent or the response could not be read.
+/// * `Status` occurs when the server responds with an error status code.
+/// * `Parse` occurs when the response can not be parsed. `path` points to the field that failed
+///   to parse. This usually means the wrapper is outdated.
+/// * `InvalidArgument` occurs when a request is rejected before it is sent.
+/// * `Unavailable` occurs when the server does not offer what was requested, for example when
+///   no webcam is configured.
+/// * `Builder` occurs when the `PrinterBuilder` is misconfigured.
+///
+/// `endpoint` is the path of the url that was requested. It is `None` if the error was converted
+/// from an operation enum that does not keep track of it.
+#[derive(Debug)]
+pub enum OctoPrintError {
+    Request {
+        endpoint: Option<String>,
+        source: ReqwestError,
+    },
+    Status {
+        endpoint: Option<String>,
+        status: StatusCode,
+        body: String,
+    },
+    Parse {
+        endpoint: Option<String>,
+        path: String,
+        message: String,
+    },
+    InvalidArgument(String),
+    Unavailable(String),
+    Builder(PrinterBuilderError),
+}
+
+impl OctoPrintError {
+    /// The path of the url that was requested, if known.
+    pub fn endpoint(&self) -> Option<&str> {
+        match self {
+            OctoPrintError::Request { endpoint, .. }
+            | OctoPrintError::Status { endpoint, .. }
+            | OctoPrintError::Parse { endpoint, .. } => endpoint.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The status code the server responded with, if any.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            OctoPrintError::Status { status, .. } => Some(*status),
+            OctoPrintError::Request { source, .. } => source.status(),
+            _ => None,
+        }
+    }
+
+    /// The body the server responded with, if the error was caused by an error status code.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            OctoPrintError::Status { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+
+    fn status_error(status: StatusCode, body: String) -> OctoPrintError {
+        OctoPrintError::Status {
+            endpoint: None,
+            status,
+            body,
+        }
+    }
+
+    /// Formats a parse error the same way `serde_path_to_error` does.
+    fn parse_message(path: &str, message: &str) -> String {
+        if path == "." || path.is_empty() {
+            message.to_string()
+        } else {
+            format!("{}: {}", path, message)
+        }
+    }
+}
+
+impl fmt::Display for OctoPrintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let endpoint = self.endpoint().unwrap_or("the printer");
+
+        match self {
+            OctoPrintError::Request { source, .. } => {
+                write!(f, "request to {} failed: {}", endpoint, source)
+            }
+            OctoPrintError::Status { status, body, .. } if body.is_empty() => {
+                write!(f, "{} responded with {}", endpoint, status)
+            }
+            OctoPrintError::Status { status, body, .. } => {
+                write!(f, "{} responded with {}: {}", endpoint, status, body)
+            }
+            OctoPrintError::Parse { path, message, .. } => write!(
+                f,
+                "failed to parse the response of {}: {}",
+                endpoint,
+                OctoPrintError::parse_message(path, message)
+            ),
+            OctoPrintError::InvalidArgument(message) => write!(f, "invalid argument: {}", message),
+            OctoPrintError::Unavailable(message) => write!(f, "{}", message),
+            OctoPrintError::Builder(e) => write!(f, "invalid printer configuration: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for OctoPrintError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OctoPrintError::Request { source, .. } => Some(source),
+            OctoPrintError::Builder(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ReqwestError> for OctoPrintError {
+    fn from(source: ReqwestError) -> Self {
+        OctoPrintError::Request {
+            endpoint: source.url().map(|url| url.path().to_string()),
+            source,
+        }
+    }
+}
+
+impl From<PrinterBuilderError> for OctoPrintError {
+    fn from(e: PrinterBuilderError) -> Self {
+        OctoPrintError::Builder(e)
+    }
+}
+
+/// Implements the conversions between an operation error enum and [`OctoPrintError`].
+///
+/// Every enum has the `ServerError`, `ReqwestError` and `Unexpected` variants. `statuses` maps
+/// the status codes the operation expects to the variant holding the response body, and
+/// `parse` names the variant holding parse errors if the operation returns data. `ServerError`,
+/// the parse variant and `Unexpected` keep the `OctoPrintError` they were converted from, so
+/// converting them back returns it unchanged.
+macro_rules! impl_octoprint_error {
+    (
+        $error:ident,
+        $(parse: $parse:ident,)?
+        statuses: { $($status:ident => $variant:ident),* $(,)? }
+        $(, also: { $($also_status:ident => $also_variant:ident),* $(,)? })?
+    ) => {
+        impl From<OctoPrintError> for $error {
+            fn from(e: OctoPrintError) -> Self {
+                match e {
+                    OctoPrintError::Request { source, .. } => $error::ReqwestError(source),
+                    $(
+                        OctoPrintError::Status {
+                            status: StatusCode::$status,
+                            body,
+                            ..
+                        } => $error::$variant(body),
+                    )*
+                    $($(
+                        OctoPrintError::Status {
+                            status: StatusCode::$also_status,
+                            body,
+                            ..
+                        } => $error::$also_variant(body),
+                    )*)?
+                    e @ OctoPrintError::Status { status, .. } if status.is_server_error() => {
+                        $error::ServerError(e)
+                    }
+                    $(
+                        e @ OctoPrintError::Parse { .. } => $error::$parse(e),
+                    )?
+                    e => $error::Unexpected(e),
+                }
+            }
+        }
+
+        impl From<$error> for OctoPrintError {
+            fn from(e: $error) -> Self {
+                match e {
+                    $error::ServerError(e) => e,
+                    $error::ReqwestError(source) => OctoPrintError::from(source),
+                    $(
+                        $error::$parse(e) => e,
+                    )?
+                    $(
+                        $error::$variant(body) => {
+                            OctoPrintError::status_error(StatusCode::$status, body)
+                        }
+                    )*
+                    $error::Unexpected(e) => e,
+                }
+            }
+        }
+    };
+}
+
+/// Errors that can occur when configuring a `PrinterBuilder`.
+///
+/// * `InvalidUrl` occurs when the base url can not be parsed.
+/// * `UnsupportedScheme` occurs when the base url is not `http` or `https`.
+/// * `MissingHost` occurs when the base url does not contain a host.
+/// * `InvalidCertificate` occurs when a certificate PEM can not be parsed.
+/// * `InvalidPrivateKey` occurs when a private key PEM can not be parsed or the key type is not
+///   supported.
+/// * `InvalidFingerprint` occurs when a pinned fingerprint is not a hex encoded SHA-256 hash.
+#[derive(Debug)]
+pub enum PrinterBuilderError {
+    InvalidUrl(String),
+    UnsupportedScheme(String),
+    MissingHost,
+    InvalidCertificate(String),
+    InvalidPrivateKey(String),
+    InvalidFingerprint(String),
+}
+
+impl fmt::Display for PrinterBuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrinterBuilderError::InvalidUrl(e) => write!(f, "invalid url: {}", e),
+            PrinterBuilderError::UnsupportedScheme(scheme) => {
+                write!(f, "unsupported scheme `{}`, expected http or https", scheme)
+            }
+            PrinterBuilderError::MissingHost => write!(f, "the url does not contain a host"),
+            PrinterBuilderError::InvalidCertificate(e) => write!(f, "invalid certificate: {}", e),
+            PrinterBuilderError::InvalidPrivateKey(e) => write!(f, "invalid private key: {}", e),
+            PrinterBuilderError::InvalidFingerprint(fingerprint) => {
+                write!(f, "invalid SHA-256 fingerprint `{}`", fingerprint)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrinterBuilderError {}
+
+/// Errors that can occur when loading a printer configuration, see [`crate::config`].
+///
+/// * `Io` occurs when the configuration or a file it references can not be read.
+/// * `Parse` occurs when the configuration is not valid TOML or JSON, or has unknown fields.
+/// * `UnsupportedFormat` occurs when the file extension is neither `.toml` nor `.json`, or when
+///   reading TOML without the `toml` feature.
+/// * `MissingAddress` occurs when neither an `address` nor a `base_url` is configured, or both.
+/// * `MissingApiKey` occurs when not exactly one of `api_key`, `api_key_env` and `api_key_file`
+///   is configured.
+/// * `MissingEnvVar` occurs when the environment variable holding the api key is not set.
+/// * `Builder` occurs when the `PrinterBuilder` rejects a setting, like an invalid certificate.
+/// * `Printer` wraps the error of a single printer of a multi-printer configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse(String),
+    UnsupportedFormat(String),
+    MissingAddress,
+    MissingApiKey,
+    MissingEnvVar(String),
+    Builder(PrinterBuilderError),
+    Printer {
+        name: String,
+        source: Box<ConfigError>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "failed to read {}: {}", path.display(), source)
+            }
+            ConfigError::Parse(e) => write!(f, "invalid configuration: {}", e),
+            ConfigError::UnsupportedFormat(e) => write!(f, "unsupported configuration: {}", e),
+            ConfigError::MissingAddress => {
+                write!(f, "exactly one of `address` and `base_url` has to be set")
+            }
+            ConfigError::MissingApiKey => write!(
+                f,
+                "exactly one of `api_key`, `api_key_env` and `api_key_file` has to be set"
+            ),
+            ConfigError::MissingEnvVar(name) => {
+                write!(f, "the environment variable `{}` is not set", name)
+            }
+            ConfigError::Builder(e) => write!(f, "invalid printer configuration: {}", e),
+            ConfigError::Printer { name, source } => write!(f, "printer `{}`: {}", name, source),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            ConfigError::Builder(e) => Some(e),
+            ConfigError::Printer { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<PrinterBuilderError> for ConfigError {
+    fn from(e: PrinterBuilderError) -> Self {
+        ConfigError::Builder(e)
+    }
+}
+
+/// Errors that can occur when loading, saving or changing a
+/// [`PrintQueue`](crate::queue::PrintQueue).
+///
+/// * `Io` occurs when the queue directory can not be read or written.
+/// * `Corrupt` occurs when the saved state of the queue can not be parsed.
+/// * `UnknownJob` occurs when no job with the given id is in the queue.
+/// * `InvalidState` occurs when a job can not be changed in its current state, for example when
+///   removing a job that is being printed.
+#[derive(Debug)]
+pub enum QueueError {
+    Io(std::io::Error),
+    Corrupt(String),
+    UnknownJob(u64),
+    InvalidState(String),
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::Io(e) => write!(f, "failed to access the queue: {}", e),
+            QueueError::Corrupt(e) => write!(f, "the saved queue is corrupt: {}", e),
+            QueueError::UnknownJob(id) => write!(f, "there is no job {} in the queue", id),
+            QueueError::InvalidState(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for QueueError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QueueError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for QueueError {
+    fn from(e: std::io::Error) -> Self {
+        QueueError::Io(e)
+    }
+}
+
+/// Errors that can occur when recording the temperature history of a printer with a
+/// [`HistoryRecorder`](crate::history::HistoryRecorder).
+///
+/// * `Request` occurs when the history can not be requested from OctoPrint.
+/// * `Io` occurs when the history file at `path` can not be read or written.
+#[derive(Debug)]
+pub enum HistoryError {
+    Request(OctoPrintError),
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Request(e) => write!(f, "failed to request the history: {}", e),
+            HistoryError::Io { path, source } => {
+                write!(f, "failed to write {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HistoryError::Request(e) => Some(e),
+            HistoryError::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<OctoPrintError> for HistoryError {
+    fn from(e: OctoPrintError) -> Self {
+        HistoryError::Request(e)
+    }
+}
+
+/// Errors that can occur when waiting for a heater with
+/// [`Printer::heat_and_wait`](crate::Printer::heat_and_wait) or
+/// [`Printer::heat_bed_and_wait`](crate::Printer::heat_bed_and_wait).
+///
+/// * `Request` occurs when the target can not be set or the temperature can not be read, or with
+///   an `InvalidArgument` when the target is 0°C or below.
+/// * `Timeout` occurs when the temperature did not settle within the band before the timeout.
+/// * `ThermalRunawaySuspected` occurs when the temperature moved away from the target instead of
+///   towards it. The target of the heater is set to 0 before the error is returned.
+///
+/// `actual` is the last temperature measured.
+#[derive(Debug)]
+pub enum HeatError {
+    Request(OctoPrintError),
+    Timeout {
+        heater: String,
+        target: f32,
+        actual: f32,
+    },
+    ThermalRunawaySuspected {
+        heater: String,
+        target: f32,
+        actual: f32,
+    },
+}
+
+impl fmt::Display for HeatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeatError::Request(e) => write!(f, "{}", e),
+            HeatError::Timeout {
+                heater,
+                target,
+                actual,
+            } => write!(
+                f,
+                "{} did not settle at {}°C in time, it is at {}°C",
+                heater, target, actual
+            ),
+            HeatError::ThermalRunawaySuspected {
+                heater,
+                target,
+                actual,
+            } => write!(
+                f,
+                "suspected thermal runaway of {}: it is at {}°C and moving away from {}°C",
+                heater, actual, target
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HeatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HeatError::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<OctoPrintError> for HeatError {
+    fn from(e: OctoPrintError) -> Self {
+        HeatError::Request(e)
+    }
+}
 
 /// If you receive an InvalidResponse error, it means that this wrapper is outdated.
 #[derive(Debug)]
 pub enum InformationRequestError {
-    ServerError,
+    ServerError(OctoPrintError),
     ReqwestError(ReqwestError),
-    ParseError(String),
+    ParseError(OctoPrintError),
+    NotFound(String),
+    Unexpected(OctoPrintError),
 }
 
+impl_octoprint_error!(InformationRequestError, parse: ParseError, statuses: {
+    NOT_FOUND => NotFound,
+});
+
 #[derive(Debug)]
 pub enum SetConnectionError {
-    ServerError,
+    ServerError(OctoPrintError),
     ReqwestError(ReqwestError),
     BadRequest(String),
+    Unexpected(OctoPrintError),
 }
 
+impl_octoprint_error!(SetConnectionError, statuses: {
+    BAD_REQUEST => BadRequest,
+});
+
 /// If you receive an InvalidResponse error, it means that this wrapper is outdated.
 #[derive(Debug)]
 pub enum FileRequestError {
-    ServerError,
+    ServerError(OctoPrintError),
     ReqwestError(ReqwestError),
-    ParseError(String),
-    NotFound(String)
+    ParseError(OctoPrintError),
+    NotFound(String),
+    Unexpected(OctoPrintError),
 }
 
+impl_octoprint_error!(FileRequestError, parse: ParseError, statuses: {
+    NOT_FOUND => NotFound,
+});
+
 /// Errors that can occur when sending a file command to the server.
 ///
 /// * `ReqwestError` occurs when the request to the server fails.
 /// * `Conflict` occurs when the server responds with a `409` status code.
 /// * `BadRequest` occurs when the server responds with a `400` or `500` status code, Make sure to check
-///     your destination path. 
+///   your destination path.
 #[derive(Debug)]
 pub enum FileCommandError {
-    ServerError,
+    ServerError(OctoPrintError),
     ReqwestError(ReqwestError),
     Conflict(String),
     BadRequest(String),
+    Unexpected(OctoPrintError),
+}
+
+impl_octoprint_error!(FileCommandError, statuses: {
+    CONFLICT => Conflict,
+    BAD_REQUEST => BadRequest,
+}, also: {
+    INTERNAL_SERVER_ERROR => BadRequest,
+});
+
+/// Errors that can occur when uploading a file.
+///
+/// * `ReqwestError` occurs when the request to the server fails.
+/// * `ParseError` occurs when the response can not be parsed.
+/// * `BadRequest` occurs when the server responds with a `400` status code. This usually means
+///   the path is invalid.
+/// * `NotFound` occurs when the server responds with a `404` status code. This means the
+///   location does not exist, for example when the printer has no SD card.
+/// * `Conflict` occurs when the server responds with a `409` status code. This means the file is
+///   currently being printed or the printer is not operational.
+/// * `UnsupportedFileType` occurs when the server responds with a `415` status code. OctoPrint
+///   only accepts G-code and model files.
+#[derive(Debug)]
+pub enum FileUploadError {
+    ServerError(OctoPrintError),
+    ReqwestError(ReqwestError),
+    ParseError(OctoPrintError),
+    BadRequest(String),
+    NotFound(String),
+    Conflict(String),
+    UnsupportedFileType(String),
+    Unexpected(OctoPrintError),
 }
 
+impl_octoprint_error!(FileUploadError, parse: ParseError, statuses: {
+    BAD_REQUEST => BadRequest,
+    NOT_FOUND => NotFound,
+    CONFLICT => Conflict,
+    UNSUPPORTED_MEDIA_TYPE => UnsupportedFileType,
+});
+
 #[derive(Debug)]
 pub enum FileDeletionError {
-    ServerError,
+    ServerError(OctoPrintError),
     ReqwestError(ReqwestError),
     NotFound(String),
     Conflict(String),
+    Unexpected(OctoPrintError),
 }
 
+impl_octoprint_error!(FileDeletionError, statuses: {
+    NOT_FOUND => NotFound,
+    CONFLICT => Conflict,
+});
+
 #[derive(Debug)]
 pub enum JobCommandError {
-    ServerError,
+    ServerError(OctoPrintError),
     ReqwestError(ReqwestError),
     Conflict(String),
+    Unexpected(OctoPrintError),
 }
 
+impl_octoprint_error!(JobCommandError, statuses: {
+    CONFLICT => Conflict,
+});
+
+/// Errors that can occur when sending G-code commands to the printer.
+///
+/// * `ReqwestError` occurs when the request to the server fails.
+/// * `BadRequest` occurs when no command is given.
+/// * `Conflict` occurs when the printer is not operational.
+#[derive(Debug)]
+pub enum PrinterCommandError {
+    ServerError(OctoPrintError),
+    ReqwestError(ReqwestError),
+    BadRequest(String),
+    Conflict(String),
+    Unexpected(OctoPrintError),
+}
+
+impl_octoprint_error!(PrinterCommandError, statuses: {
+    BAD_REQUEST => BadRequest,
+    CONFLICT => Conflict,
+});
+
 #[derive(Debug)]
 pub enum DeviceStateError {
-    ServerError,
+    ServerError(OctoPrintError),
     ReqwestError(ReqwestError),
-    ParseError(String),
+    ParseError(OctoPrintError),
     Conflict(String),
+    Unexpected(OctoPrintError),
 }
 
+impl_octoprint_error!(DeviceStateError, parse: ParseError, statuses: {
+    CONFLICT => Conflict,
+});
+
 #[derive(Debug)]
 pub enum ToolCommandError {
-    ServerError,
+    ServerError(OctoPrintError),
+    ReqwestError(ReqwestError),
+    BadRequest(String),
+    Conflict(String),
+    Unexpected(OctoPrintError),
+}
+
+impl_octoprint_error!(ToolCommandError, statuses: {
+    BAD_REQUEST => BadRequest,
+    CONFLICT => Conflict,
+});
+
+/// The error of parsing a [`ToolId`](crate::types::ToolId) from a name that is not `tool`
+/// followed by a number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidToolId(pub String);
+
+impl fmt::Display for InvalidToolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid tool {:?}, expected a name like `tool0`", self.0)
+    }
+}
+
+impl std::error::Error for InvalidToolId {}
+
+/// Errors that can occur when applying a temperature profile or cooling down the printer.
+///
+/// * `ReqwestError` occurs when a request fails.
+/// * `ParseError` occurs when the settings or printer profiles can not be parsed.
+/// * `UnknownProfile` occurs when there is no temperature profile with the given name. It is not
+///   sent by the server.
+/// * `NotFound` occurs when the server responds with a `404` status code.
+/// * `BadRequest` occurs when the server responds with a `400` status code.
+/// * `Conflict` occurs when the server responds with a `409` status code. This usually means the
+///   printer is not operational.
+#[derive(Debug)]
+pub enum TemperatureProfileError {
+    ServerError(OctoPrintError),
+    ReqwestError(ReqwestError),
+    ParseError(OctoPrintError),
+    UnknownProfile(String),
+    NotFound(String),
+    BadRequest(String),
+    Conflict(String),
+    Unexpected(OctoPrintError),
+}
+
+impl From<OctoPrintError> for TemperatureProfileError {
+    fn from(e: OctoPrintError) -> Self {
+        match e {
+            OctoPrintError::Request { source, .. } => TemperatureProfileError::ReqwestError(source),
+            OctoPrintError::Status {
+                status: StatusCode::NOT_FOUND,
+                body,
+                ..
+            } => TemperatureProfileError::NotFound(body),
+            OctoPrintError::Status {
+                status: StatusCode::BAD_REQUEST,
+                body,
+                ..
+            } => TemperatureProfileError::BadRequest(body),
+            OctoPrintError::Status {
+                status: StatusCode::CONFLICT,
+                body,
+                ..
+            } => TemperatureProfileError::Conflict(body),
+            e @ OctoPrintError::Status { status, .. } if status.is_server_error() => {
+                TemperatureProfileError::ServerError(e)
+            }
+            e @ OctoPrintError::Parse { .. } => TemperatureProfileError::ParseError(e),
+            e => TemperatureProfileError::Unexpected(e),
+        }
+    }
+}
+
+impl From<TemperatureProfileError> for OctoPrintError {
+    fn from(e: TemperatureProfileError) -> Self {
+        match e {
+            TemperatureProfileError::ServerError(e) => e,
+            TemperatureProfileError::ReqwestError(source) => OctoPrintError::from(source),
+            TemperatureProfileError::ParseError(e) => e,
+            TemperatureProfileError::UnknownProfile(name) => {
+                OctoPrintError::InvalidArgument(format!("no temperature profile called {}", name))
+            }
+            TemperatureProfileError::NotFound(body) => {
+                OctoPrintError::status_error(StatusCode::NOT_FOUND, body)
+            }
+            TemperatureProfileError::BadRequest(body) => {
+                OctoPrintError::status_error(StatusCode::BAD_REQUEST, body)
+            }
+            TemperatureProfileError::Conflict(body) => {
+                OctoPrintError::status_error(StatusCode::CONFLICT, body)
+            }
+            TemperatureProfileError::Unexpected(e) => e,
+        }
+    }
+}
+
+/// Errors that can occur when answering an action command prompt.
+///
+/// * `ReqwestError` occurs when the request to the server fails.
+/// * `BadRequest` occurs when the server responds with a `400` status code. This means the choice
+///   is not valid for the current prompt.
+/// * `Conflict` occurs when the server responds with a `409` status code. This means there is no
+///   active prompt.
+#[derive(Debug)]
+pub enum PromptCommandError {
+    ServerError(OctoPrintError),
     ReqwestError(ReqwestError),
     BadRequest(String),
     Conflict(String),
+    Unexpected(OctoPrintError),
+}
+
+impl_octoprint_error!(PromptCommandError, statuses: {
+    BAD_REQUEST => BadRequest,
+    CONFLICT => Conflict,
+});
+
+/// Errors that can occur when uploading or deleting a language pack.
+///
+/// * `ReqwestError` occurs when the request to the server fails.
+/// * `ParseError` occurs when the returned list of language packs can not be parsed.
+/// * `BadRequest` occurs when the server responds with a `400` status code. This usually means
+///   the uploaded file is not a valid language pack archive.
+#[derive(Debug)]
+pub enum LanguagePackError {
+    ServerError(OctoPrintError),
+    ReqwestError(ReqwestError),
+    ParseError(OctoPrintError),
+    BadRequest(String),
+    Unexpected(OctoPrintError),
+}
+
+impl_octoprint_error!(LanguagePackError, parse: ParseError, statuses: {
+    BAD_REQUEST => BadRequest,
+});
+
+/// Errors that can occur when taking a webcam snapshot.
+///
+/// * `ReqwestError` occurs when a request fails.
+/// * `ParseError` occurs when the webcam settings can not be parsed.
+/// * `NoSnapshotUrl` occurs when no webcam with snapshot support is configured.
+/// * `SnapshotFailed` occurs when the snapshot url responds with an error status code.
+#[derive(Debug)]
+pub enum SnapshotError {
+    ServerError(OctoPrintError),
+    ReqwestError(ReqwestError),
+    ParseError(OctoPrintError),
+    NoSnapshotUrl,
+    SnapshotFailed(String),
+    Unexpected(OctoPrintError),
+}
+
+impl From<OctoPrintError> for SnapshotError {
+    fn from(e: OctoPrintError) -> Self {
+        match e {
+            OctoPrintError::Request { source, .. } => SnapshotError::ReqwestError(source),
+            e @ OctoPrintError::Status { status, .. } if status.is_server_error() => {
+                SnapshotError::ServerError(e)
+            }
+            e @ OctoPrintError::Parse { .. } => SnapshotError::ParseError(e),
+            e => SnapshotError::Unexpected(e),
+        }
+    }
+}
+
+impl From<SnapshotError> for OctoPrintError {
+    fn from(e: SnapshotError) -> Self {
+        match e {
+            SnapshotError::ServerError(e) => e,
+            SnapshotError::ReqwestError(source) => OctoPrintError::from(source),
+            SnapshotError::ParseError(e) => e,
+            SnapshotError::NoSnapshotUrl => OctoPrintError::Unavailable(
+                "no webcam with snapshot support is configured".to_string(),
+            ),
+            SnapshotError::SnapshotFailed(body) => OctoPrintError::Unavailable(format!(
+                "the webcam failed to take a snapshot: {}",
+                body
+            )),
+            SnapshotError::Unexpected(e) => e,
+        }
+    }
 }
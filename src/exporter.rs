@@ -0,0 +1,410 @@
+//! A Prometheus exporter for a [`Fleet`], enabled by the `exporter` feature.
+//!
+//! An [`Exporter`] polls every printer of a fleet periodically and serves the last values in
+//! the Prometheus text format at `/metrics`. Every metric is labeled with the name of its
+//! printer:
+//!
+//! * `octoprint_up` - Whether the last poll of the job succeeded.
+//! * `octoprint_temperature_actual_celsius` and `octoprint_temperature_target_celsius` - The
+//!   temperatures of every tool and the bed, labeled with `heater`.
+//! * `octoprint_job_completion_ratio`, `octoprint_job_print_time_seconds` and
+//!   `octoprint_job_print_time_left_seconds` - The progress of the current job.
+//! * `octoprint_printer_state` - The [state flags](crate::types::PrinterStateFlags) of the
+//!   printer as `0` or `1`, labeled with `flag`.
+//! * `octoprint_storage_free_bytes` and `octoprint_storage_total_bytes` - The local storage.
+//! * `octoprint_request_duration_seconds` - A summary of the duration of the requests of the
+//!   exporter, and `octoprint_request_errors_total` the number of failed ones, both labeled with
+//!   `endpoint`.
+//!
+//! Temperatures and state flags are missing while a printer is not connected to OctoPrint.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::{net::TcpListener, time::Duration};
+//!
+//! use octoprint_rs::config::FleetConfig;
+//! use octoprint_rs::exporter::Exporter;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let fleet = FleetConfig::load("printers.json")?.fleet()?;
+//! let listener = TcpListener::bind("0.0.0.0:9469")?;
+//! Exporter::new(fleet)
+//!     .interval(Duration::from_secs(15))
+//!     .serve(listener)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt::Write,
+    future::Future,
+    io,
+    net::TcpListener,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+
+use crate::{
+    api::OctoPrintApi,
+    errors::DeviceStateError,
+    fleet::Fleet,
+    types::{FilesFetchDescriptor, FilesLocation, JobInformation, RawPrinter},
+    Printer,
+};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+const CONTENT_TYPE_TEXT: &str = "text/plain; version=0.0.4";
+
+/// Polls the printers of a [`Fleet`] and serves their metrics. See the [module docs](self).
+pub struct Exporter<P = Printer> {
+    fleet: Arc<Fleet<P>>,
+    interval: Duration,
+    metrics: Arc<Mutex<Metrics>>,
+}
+
+#[derive(Default)]
+struct Metrics {
+    printers: BTreeMap<String, Sample>,
+    requests: BTreeMap<(String, &'static str), RequestStats>,
+}
+
+/// What the last poll of a printer returned. `None` where a request failed.
+#[derive(Default)]
+struct Sample {
+    telemetry: Option<RawPrinter>,
+    job: Option<JobInformation>,
+    storage: Option<(u64, u64)>,
+    requests: Vec<(&'static str, Duration, bool)>,
+}
+
+#[derive(Default)]
+struct RequestStats {
+    count: u64,
+    seconds: f64,
+    errors: u64,
+}
+
+impl<P> Exporter<P> {
+    /// Creates an exporter that polls every printer every 10 seconds.
+    pub fn new(fleet: Fleet<P>) -> Self {
+        Exporter {
+            fleet: Arc::new(fleet),
+            interval: DEFAULT_INTERVAL,
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Set how long to wait between two polls.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    fn metrics(&self) -> MutexGuard<'_, Metrics> {
+        // Samples are only ever replaced as a whole, so a poisoned lock is still usable
+        self.metrics.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The metrics of the last poll in the Prometheus text format.
+    pub fn render(&self) -> String {
+        self.metrics().render()
+    }
+}
+
+impl<P: OctoPrintApi + 'static> Exporter<P> {
+    /// Polls every printer once and updates the metrics.
+    pub async fn poll(&self) {
+        let samples = self
+            .fleet
+            .run(|printer| async move { Ok::<_, Infallible>(sample(&*printer).await) })
+            .await;
+
+        let mut metrics = self.metrics();
+        for (name, sample) in samples {
+            let Ok(sample) = sample;
+            for (endpoint, duration, failed) in &sample.requests {
+                let stats = metrics
+                    .requests
+                    .entry((name.clone(), endpoint))
+                    .or_default();
+                stats.count += 1;
+                stats.seconds += duration.as_secs_f64();
+                stats.errors += *failed as u64;
+            }
+            metrics.printers.insert(name, sample);
+        }
+    }
+
+    /// Polls every printer every [`interval`](#method.interval), forever.
+    pub async fn run(&self) {
+        loop {
+            self.poll().await;
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    /// Polls the printers and serves the metrics at `/metrics` on `listener`, until the server
+    /// fails.
+    ///
+    /// # Errors
+    ///
+    /// If the listener can not be used or the server fails.
+    pub async fn serve(self, listener: TcpListener) -> io::Result<()> {
+        listener.set_nonblocking(true)?;
+        let exporter = Arc::new(self);
+
+        let poller = exporter.clone();
+        let polling = tokio::spawn(async move { poller.run().await });
+
+        let make_service = make_service_fn(move |_| {
+            let exporter = exporter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let exporter = exporter.clone();
+                    async move { Ok::<_, Infallible>(exporter.respond(&request)) }
+                }))
+            }
+        });
+
+        let result = Server::from_tcp(listener)
+            .map_err(io::Error::other)?
+            .serve(make_service)
+            .await
+            .map_err(io::Error::other);
+        polling.abort();
+        result
+    }
+
+    fn respond(&self, request: &Request<Body>) -> Response<Body> {
+        let response = Response::builder();
+        let response = match (request.method(), request.uri().path()) {
+            (&Method::GET, "/metrics") => response
+                .header(CONTENT_TYPE, CONTENT_TYPE_TEXT)
+                .body(Body::from(self.render())),
+            _ => response
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from("Not found, metrics are at /metrics")),
+        };
+        response.expect("the response is valid")
+    }
+}
+
+/// Polls a printer. A printer that is not connected to OctoPrint has no telemetry, which is
+/// not counted as an error.
+async fn sample<P: OctoPrintApi>(printer: &P) -> Sample {
+    let files = FilesFetchDescriptor {
+        location: FilesLocation::Local,
+        recursive: false,
+        force: false,
+    };
+    let ((telemetry, telemetry_time), (job, job_time), (files, files_time)) = tokio::join!(
+        timed(printer.get_printer_telemetry()),
+        timed(printer.get_job()),
+        timed(printer.get_files(files))
+    );
+
+    let telemetry_failed =
+        telemetry.is_err() && !matches!(telemetry, Err(DeviceStateError::Conflict(_)));
+    Sample {
+        requests: vec![
+            ("api/printer", telemetry_time, telemetry_failed),
+            ("api/job", job_time, job.is_err()),
+            ("api/files/local", files_time, files.is_err()),
+        ],
+        telemetry: telemetry.ok(),
+        job: job.ok(),
+        storage: files.ok().map(|files| (files.free, files.total)),
+    }
+}
+
+async fn timed<F: Future>(future: F) -> (F::Output, Duration) {
+    let start = Instant::now();
+    let output = future.await;
+    (output, start.elapsed())
+}
+
+impl Metrics {
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let printers = || self.printers.iter();
+
+        family(
+            &mut out,
+            "octoprint_up",
+            "gauge",
+            "Whether the last poll of the job of the printer succeeded.",
+            printers().map(|(name, s)| (labels(name, &[]), s.job.is_some() as u8 as f64)),
+        );
+
+        // The heaters are kept in a map, sorting them keeps the output stable
+        let mut heaters: Vec<_> = printers()
+            .flat_map(|(name, sample)| {
+                sample
+                    .telemetry
+                    .iter()
+                    .flat_map(|t| &t.temperature.tools)
+                    .map(move |(heater, tool)| (labels(name, &[("heater", heater)]), tool))
+            })
+            .collect();
+        heaters.sort_by(|a, b| a.0.cmp(&b.0));
+        family(
+            &mut out,
+            "octoprint_temperature_actual_celsius",
+            "gauge",
+            "The measured temperature of a heater.",
+            heaters
+                .iter()
+                .map(|(labels, tool)| (labels.clone(), tool.actual as f64)),
+        );
+        family(
+            &mut out,
+            "octoprint_temperature_target_celsius",
+            "gauge",
+            "The target temperature of a heater, 0 if it is off.",
+            heaters
+                .iter()
+                .map(|(labels, tool)| (labels.clone(), tool.target.unwrap_or(0.0) as f64)),
+        );
+
+        let jobs = || printers().filter_map(|(name, s)| Some((labels(name, &[]), s.job.as_ref()?)));
+        family(
+            &mut out,
+            "octoprint_job_completion_ratio",
+            "gauge",
+            "The completion of the current job from 0 to 1.",
+            jobs().filter_map(|(l, job)| Some((l, job.progress.completion? as f64 / 100.0))),
+        );
+        family(
+            &mut out,
+            "octoprint_job_print_time_seconds",
+            "gauge",
+            "The time the current job has been printing.",
+            jobs().filter_map(|(l, job)| Some((l, job.progress.print_time? as f64))),
+        );
+        family(
+            &mut out,
+            "octoprint_job_print_time_left_seconds",
+            "gauge",
+            "The estimated time until the current job is done.",
+            jobs().filter_map(|(l, job)| Some((l, job.progress.print_time_left? as f64))),
+        );
+
+        family(
+            &mut out,
+            "octoprint_printer_state",
+            "gauge",
+            "The state flags of the printer.",
+            printers().flat_map(|(name, sample)| {
+                sample.telemetry.iter().flat_map(move |t| {
+                    let flags = &t.state.flags;
+                    [
+                        ("operational", flags.operational),
+                        ("paused", flags.paused),
+                        ("pausing", flags.pausing),
+                        ("printing", flags.printing),
+                        ("cancelling", flags.cancelling),
+                        ("sd_ready", flags.sd_ready),
+                        ("error", flags.error),
+                        ("ready", flags.ready),
+                        ("closed_or_error", flags.closed_or_error),
+                    ]
+                    .map(|(flag, set)| (labels(name, &[("flag", flag)]), set as u8 as f64))
+                })
+            }),
+        );
+
+        let storage = || printers().filter_map(|(name, s)| Some((labels(name, &[]), s.storage?)));
+        family(
+            &mut out,
+            "octoprint_storage_free_bytes",
+            "gauge",
+            "The free space of the local storage.",
+            storage().map(|(labels, (free, _))| (labels, free as f64)),
+        );
+        family(
+            &mut out,
+            "octoprint_storage_total_bytes",
+            "gauge",
+            "The size of the local storage.",
+            storage().map(|(labels, (_, total))| (labels, total as f64)),
+        );
+
+        let requests = || {
+            self.requests
+                .iter()
+                .map(|((name, endpoint), stats)| (labels(name, &[("endpoint", endpoint)]), stats))
+        };
+        writeln!(
+            out,
+            "# HELP octoprint_request_duration_seconds The duration of the requests to the printer."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE octoprint_request_duration_seconds summary").unwrap();
+        for (labels, stats) in requests() {
+            writeln!(
+                out,
+                "octoprint_request_duration_seconds_sum{} {}",
+                labels, stats.seconds
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "octoprint_request_duration_seconds_count{} {}",
+                labels, stats.count
+            )
+            .unwrap();
+        }
+        family(
+            &mut out,
+            "octoprint_request_errors_total",
+            "counter",
+            "The number of failed requests to the printer.",
+            requests().map(|(labels, stats)| (labels, stats.errors as f64)),
+        );
+
+        out
+    }
+}
+
+/// Writes a metric family with its samples.
+fn family(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: impl Iterator<Item = (String, f64)>,
+) {
+    // Writing to a `String` can not fail
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+    for (labels, value) in samples {
+        writeln!(out, "{}{} {}", name, labels, value).unwrap();
+    }
+}
+
+/// Formats the labels of a sample, starting with the name of the printer.
+fn labels(printer: &str, extra: &[(&str, &str)]) -> String {
+    let pairs: Vec<_> = std::iter::once(("printer", printer))
+        .chain(extra.iter().copied())
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
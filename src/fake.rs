@@ -0,0 +1,1156 @@
+//! An in-memory printer for deterministic tests of code that uses [`OctoPrintApi`].
+//!
+//! [`FakePrinter`] keeps a file list, the selected job and the temperatures of its tools and
+//! bed, and answers every call like OctoPrint would, including the `400`, `404` and `409`
+//! errors for invalid tools, missing files or commands that conflict with a running print.
+//! Nothing happens on its own: temperatures only change when a test sets them with
+//! [`FakePrinter::set_tool_actual`] or [`FakePrinter::set_bed_actual`] and a print only
+//! progresses with [`FakePrinter::set_progress`].
+//!
+//! # Example
+//!
+//! ```
+//! use octoprint_rs::api::OctoPrintApi;
+//! use octoprint_rs::fake::FakePrinter;
+//! use octoprint_rs::types::*;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let printer = FakePrinter::new();
+//! printer.add_file(FileLocation::Local, "parts/benchy.gcode", 1024);
+//!
+//! printer
+//!     .issue_file_command(FileCommandDescriptor {
+//!         command: FileCommand::Select { print: true },
+//!         path: PathDescriptor {
+//!             location: FileLocation::Local,
+//!             path: "parts/benchy.gcode".to_string(),
+//!         },
+//!     })
+//!     .await
+//!     .unwrap();
+//!
+//! printer.set_progress(50.0);
+//! let job = printer.get_job().await.unwrap();
+//! assert_eq!(job.state, "Printing");
+//! assert_eq!(job.progress.filepos, Some(512));
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Mutex, MutexGuard},
+};
+
+use reqwest::StatusCode;
+
+use crate::api::OctoPrintApi;
+use crate::errors::*;
+use crate::types::printer_files::{Entry, Files};
+use crate::types::*;
+
+const PORTS: [&str; 1] = ["VIRTUAL"];
+const BAUDRATES: [u32; 1] = [115200];
+const ROOM_TEMPERATURE: f32 = 21.0;
+
+/// An in-memory printer implementing [`OctoPrintApi`]. See the [module docs](self).
+pub struct FakePrinter {
+    state: Mutex<FakeState>,
+}
+
+struct FakeState {
+    connected: bool,
+    files: Vec<FakeFile>,
+    free: u64,
+    total: u64,
+    selected: Option<FakeFile>,
+    job: JobState,
+    completion: f32,
+    tools: BTreeMap<String, FakeTemperature>,
+    bed: FakeTemperature,
+    selected_tool: ToolId,
+    feedrate: f32,
+    flowrate: f32,
+    profile: printer_profiles::Profile,
+    gcode: Vec<String>,
+    temperature_profiles: Vec<TemperatureProfile>,
+    pi_support: Option<PiSupport>,
+    prompt: Option<ActionPrompt>,
+    prompt_answers: Vec<usize>,
+    snapshot: Option<Snapshot>,
+}
+
+#[derive(Clone, PartialEq)]
+struct FakeFile {
+    origin: &'static str,
+    path: String,
+    size: u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum JobState {
+    Operational,
+    Printing,
+    Paused,
+}
+
+struct FakeTemperature {
+    actual: f32,
+    target: f32,
+    offset: f32,
+}
+
+impl Default for FakePrinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakePrinter {
+    /// Creates a connected, idle printer with one tool `tool0`, no files and everything at
+    /// room temperature.
+    pub fn new() -> Self {
+        Self::with_tools(1)
+    }
+
+    /// Creates a connected, idle printer with the tools `tool0` to `tool{count - 1}`.
+    pub fn with_tools(count: usize) -> Self {
+        let tools = (0..count)
+            .map(|i| (format!("tool{}", i), FakeTemperature::room()))
+            .collect();
+
+        FakePrinter {
+            state: Mutex::new(FakeState {
+                connected: true,
+                files: vec![],
+                free: 8_000_000_000,
+                total: 16_000_000_000,
+                selected: None,
+                job: JobState::Operational,
+                completion: 0.0,
+                tools,
+                bed: FakeTemperature::room(),
+                selected_tool: ToolId::new(0),
+                feedrate: 1.0,
+                flowrate: 1.0,
+                profile: default_profile(count),
+                gcode: vec![],
+                temperature_profiles: default_temperature_profiles(),
+                pi_support: None,
+                prompt: None,
+                prompt_answers: vec![],
+                snapshot: None,
+            }),
+        }
+    }
+
+    /// Adds a file of `size` bytes at `path`. Folders are created implicitly by the path.
+    pub fn add_file(&self, location: FileLocation, path: &str, size: u64) {
+        self.state().store(origin(&location), path, size);
+    }
+
+    /// Sets the actual temperature of a tool, like the printer heating up. Unknown tools are
+    /// ignored.
+    pub fn set_tool_actual(&self, tool: ToolId, actual: f32) {
+        if let Some(temperature) = self.state().tools.get_mut(&tool.to_string()) {
+            temperature.actual = actual;
+        }
+    }
+
+    /// Sets the actual temperature of the bed.
+    pub fn set_bed_actual(&self, actual: f32) {
+        self.state().bed.actual = actual;
+    }
+
+    /// Sets the completion of the running print in percent. At `100` the print is done and the
+    /// printer is operational again.
+    pub fn set_progress(&self, completion: f32) {
+        let mut state = self.state();
+        if state.job == JobState::Operational {
+            return;
+        }
+
+        state.completion = completion.clamp(0.0, 100.0);
+        if state.completion >= 100.0 {
+            state.job = JobState::Operational;
+        }
+    }
+
+    /// Replaces the printer profile the printer is connected with. By default this is a
+    /// `_default` profile with a 200 mm cube build volume, a 0.4 mm nozzle and one extruder per
+    /// tool.
+    pub fn set_printer_profile(&self, profile: printer_profiles::Profile) {
+        self.state().profile = profile;
+    }
+
+    /// The current feedrate and flowrate factors.
+    pub fn rates(&self) -> (f32, f32) {
+        let state = self.state();
+        (state.feedrate, state.flowrate)
+    }
+
+    /// The currently selected tool.
+    pub fn selected_tool(&self) -> ToolId {
+        self.state().selected_tool
+    }
+
+    /// Every G-code command sent with `send_gcode`, in order. The fake does not execute them.
+    pub fn sent_gcode(&self) -> Vec<String> {
+        self.state().gcode.clone()
+    }
+
+    /// Replaces the temperature profiles. By default these are OctoPrint's `ABS` and `PLA`
+    /// profiles. The fake has no chamber, so chamber temperatures are ignored.
+    pub fn set_temperature_profiles(&self, profiles: Vec<TemperatureProfile>) {
+        self.state().temperature_profiles = profiles;
+    }
+
+    /// Makes the printer run on a Raspberry Pi reporting `pi_support`. Without it, the
+    /// `pi_support` plugin is not found, like on any other host.
+    pub fn set_pi_support(&self, pi_support: PiSupport) {
+        self.state().pi_support = Some(pi_support);
+    }
+
+    /// Shows an action command prompt, like the printer asking whether the filament was changed.
+    /// The prompt stays until it is answered with `select_prompt_choice`.
+    pub fn show_prompt(&self, prompt: ActionPrompt) {
+        self.state().prompt = Some(prompt);
+    }
+
+    /// The index of every choice selected to answer a prompt, in order.
+    pub fn prompt_answers(&self) -> Vec<usize> {
+        self.state().prompt_answers.clone()
+    }
+
+    /// Adds a webcam taking `snapshot`. Without it, no webcam is configured.
+    pub fn set_snapshot(&self, snapshot: Snapshot) {
+        self.state().snapshot = Some(snapshot);
+    }
+
+    /// Sets the targets of every extruder and the bed, if the printer profile has it heated.
+    async fn set_all_temperatures(
+        &self,
+        extruder: f32,
+        bed: f32,
+    ) -> Result<(), TemperatureProfileError> {
+        let (tools, heated_bed) = {
+            let state = self.state();
+            (state.profile.tools(), state.profile.heated_bed)
+        };
+
+        let targets = tools.into_iter().map(|tool| (tool, extruder)).collect();
+        self.tool_temperature(ToolTempDescriptor::Targets(targets))
+            .await
+            .map_err(OctoPrintError::from)?;
+        if heated_bed {
+            self.change_bed_temp(BedTempDescriptor::Target { target: bed })
+                .await
+                .map_err(OctoPrintError::from)?;
+        }
+        Ok(())
+    }
+
+    fn state(&self) -> MutexGuard<'_, FakeState> {
+        // The state is never left half updated, so a poisoned lock is still usable
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl FakeState {
+    fn state_text(&self) -> &'static str {
+        match (self.connected, self.job) {
+            (false, _) => "Closed",
+            (true, JobState::Operational) => "Operational",
+            (true, JobState::Printing) => "Printing",
+            (true, JobState::Paused) => "Paused",
+        }
+    }
+
+    fn store(&mut self, origin: &'static str, path: &str, size: u64) {
+        let file = FakeFile {
+            origin,
+            path: path.trim_matches('/').to_string(),
+            size,
+        };
+
+        // An overwritten file gives its space back before the new one takes it
+        if let Some(index) = self
+            .files
+            .iter()
+            .position(|f| f.origin == file.origin && f.path == file.path)
+        {
+            self.free += self.files.remove(index).size;
+        }
+        self.free = self.free.saturating_sub(size);
+        self.files.push(file);
+    }
+
+    fn is_busy(&self) -> bool {
+        self.job != JobState::Operational
+    }
+
+    fn find(&self, path: &PathDescriptor) -> Option<usize> {
+        let wanted = path.path.trim_matches('/');
+        self.files
+            .iter()
+            .position(|f| f.origin == origin(&path.location) && f.path == wanted)
+    }
+
+    fn is_folder(&self, path: &PathDescriptor) -> bool {
+        let prefix = format!("{}/", path.path.trim_matches('/'));
+        self.files
+            .iter()
+            .any(|f| f.origin == origin(&path.location) && f.path.starts_with(&prefix))
+    }
+
+    fn is_selected_for_print(&self, file: &FakeFile) -> bool {
+        self.is_busy() && self.selected.as_ref() == Some(file)
+    }
+
+    fn entries(&self, origin: &'static str, folder: &str, recursive: bool) -> Vec<Entry> {
+        let prefix = if folder.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", folder)
+        };
+
+        let mut folders = BTreeSet::new();
+        let mut entries = vec![];
+        for file in self.files.iter().filter(|f| f.origin == origin) {
+            let Some(rest) = file.path.strip_prefix(&prefix) else {
+                continue;
+            };
+            match rest.split_once('/') {
+                Some((child, _)) => {
+                    folders.insert(child.to_string());
+                }
+                None => entries.push(file.entry()),
+            }
+        }
+
+        for name in folders {
+            let path = format!("{}{}", prefix, name);
+            let children = if recursive {
+                self.entries(origin, &path, true)
+            } else {
+                vec![]
+            };
+
+            entries.push(Entry::Folder {
+                children,
+                display: name.clone(),
+                name,
+                origin: origin.to_string(),
+                refs: None,
+                type_path: vec!["folder".to_string()],
+                size: None,
+                path,
+            });
+        }
+        entries
+    }
+
+    fn flags(&self) -> PrinterStateFlags {
+        PrinterStateFlags {
+            operational: self.connected,
+            paused: self.connected && self.job == JobState::Paused,
+            pausing: false,
+            printing: self.connected && self.job == JobState::Printing,
+            cancelling: false,
+            sd_ready: self.connected,
+            error: false,
+            ready: self.connected && !self.is_busy(),
+            closed_or_error: !self.connected,
+        }
+    }
+
+    fn tool_state(&self, history: Option<u32>) -> ToolState {
+        ToolState {
+            history: history.map(|_| vec![]),
+            tools: self
+                .tools
+                .iter()
+                .map(|(name, temperature)| (name.clone(), temperature.to_tool()))
+                .collect(),
+        }
+    }
+}
+
+impl FakeFile {
+    fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    fn entry(&self) -> Entry {
+        Entry::File {
+            display: self.name().to_string(),
+            name: self.name().to_string(),
+            path: self.path.clone(),
+            type_path: vec!["machinecode".to_string(), "gcode".to_string()],
+            origin: self.origin.to_string(),
+            date: None,
+            hash: None,
+            size: Some(self.size),
+            refs: None,
+            gcode_analysis: None,
+            print: None,
+            statistics: None,
+        }
+    }
+}
+
+impl FakeTemperature {
+    fn room() -> Self {
+        FakeTemperature {
+            actual: ROOM_TEMPERATURE,
+            target: 0.0,
+            offset: 0.0,
+        }
+    }
+
+    fn to_tool(&self) -> PrinterTool {
+        PrinterTool {
+            actual: self.actual,
+            target: Some(self.target),
+            offset: Some(self.offset),
+        }
+    }
+}
+
+fn default_profile(tools: usize) -> printer_profiles::Profile {
+    printer_profiles::Profile {
+        id: "_default".to_string(),
+        name: "Default".to_string(),
+        model: Some("Generic RepRap Printer".to_string()),
+        current: true,
+        default: true,
+        heated_bed: true,
+        heated_chamber: false,
+        volume: printer_profiles::Volume {
+            form_factor: "rectangular".to_string(),
+            origin: "lowerleft".to_string(),
+            width: 200.0,
+            depth: 200.0,
+            height: 200.0,
+        },
+        extruder: printer_profiles::Extruder {
+            count: tools as u32,
+            nozzle_diameter: 0.4,
+            shared_nozzle: false,
+            offsets: vec![[0.0, 0.0]; tools],
+        },
+    }
+}
+
+/// The temperature profiles OctoPrint comes with.
+fn default_temperature_profiles() -> Vec<TemperatureProfile> {
+    vec![
+        TemperatureProfile {
+            name: "ABS".to_string(),
+            extruder: 210.0,
+            bed: 100.0,
+            chamber: None,
+        },
+        TemperatureProfile {
+            name: "PLA".to_string(),
+            extruder: 180.0,
+            bed: 60.0,
+            chamber: None,
+        },
+    ]
+}
+
+fn origin(location: &FileLocation) -> &'static str {
+    match location {
+        FileLocation::Local => "local",
+        FileLocation::Sdcard => "sdcard",
+    }
+}
+
+/// The error the real printer returns for `status`, mapped like the [`Printer`](crate::Printer)
+/// maps it.
+fn status<E: From<OctoPrintError>>(endpoint: &str, status: StatusCode, body: &str) -> E {
+    E::from(OctoPrintError::Status {
+        endpoint: Some(endpoint.to_string()),
+        status,
+        body: body.to_string(),
+    })
+}
+
+/// Sanitizes an uploaded path like OctoPrint does: spaces become underscores and every other
+/// character that is not ASCII alphanumeric or one of `-_.()[]` is dropped. OctoPrint
+/// transliterates non-ASCII characters first, this simply drops them.
+fn sanitize(path: &str) -> String {
+    path.trim_matches('/')
+        .split('/')
+        .map(|segment| {
+            segment
+                .chars()
+                .map(|c| if c == ' ' { '_' } else { c })
+                .filter(|c| c.is_ascii_alphanumeric() || "-_.()[]".contains(*c))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn files_endpoint(path: &PathDescriptor) -> String {
+    format!(
+        "/api/files/{}/{}",
+        origin(&path.location),
+        path.path.trim_matches('/')
+    )
+}
+
+impl OctoPrintApi for FakePrinter {
+    async fn get_api_version(&self) -> Result<ApiVersion, InformationRequestError> {
+        Ok(ApiVersion {
+            api: "0.1".to_string(),
+            server: "1.9.3".to_string(),
+            text: "OctoPrint 1.9.3".to_string(),
+        })
+    }
+
+    async fn get_connection(&self) -> Result<PrinterConnection, InformationRequestError> {
+        let state = self.state();
+
+        Ok(PrinterConnection {
+            current: PrinterConnectionStateCurrent {
+                state: state.state_text().to_string(),
+                port: state.connected.then(|| PORTS[0].to_string()),
+                baudrate: state.connected.then_some(BAUDRATES[0]),
+                printer_profile: state.profile.id.clone(),
+            },
+            options: PrinterConnectionStateOptions {
+                ports: PORTS.iter().map(|p| p.to_string()).collect(),
+                baudrates: BAUDRATES.to_vec(),
+                printer_profiles: vec![PrinterProfile {
+                    name: state.profile.name.clone(),
+                    id: state.profile.id.clone(),
+                }],
+            },
+        })
+    }
+
+    async fn set_connection(
+        &self,
+        connection: ConnectionCommandDescriptor,
+    ) -> Result<(), SetConnectionError> {
+        let mut state = self.state();
+
+        match connection {
+            ConnectionCommandDescriptor::Connect { port, baudrate, .. } => {
+                if !PORTS.contains(&port.as_str()) || !BAUDRATES.contains(&baudrate) {
+                    return Err(status(
+                        "/api/connection",
+                        StatusCode::BAD_REQUEST,
+                        "Invalid port or baudrate",
+                    ));
+                }
+                state.connected = true;
+            }
+            ConnectionCommandDescriptor::Disconnect => {
+                state.connected = false;
+                state.job = JobState::Operational;
+            }
+            ConnectionCommandDescriptor::FakeAck => {}
+        }
+        Ok(())
+    }
+
+    async fn get_printer_profiles(
+        &self,
+    ) -> Result<printer_profiles::Profiles, InformationRequestError> {
+        let profile = self.state().profile.clone();
+
+        Ok(printer_profiles::Profiles {
+            profiles: [(profile.id.clone(), profile)].into_iter().collect(),
+        })
+    }
+
+    async fn get_files(
+        &self,
+        files_descriptor: FilesFetchDescriptor,
+    ) -> Result<Files, FileRequestError> {
+        let state = self.state();
+        let recursive = files_descriptor.recursive;
+
+        let files = match files_descriptor.location {
+            FilesLocation::Root => {
+                let mut files = state.entries("local", "", recursive);
+                files.extend(state.entries("sdcard", "", recursive));
+                files
+            }
+            FilesLocation::Local => state.entries("local", "", recursive),
+            FilesLocation::Sdcard => state.entries("sdcard", "", recursive),
+        };
+
+        Ok(Files {
+            files,
+            free: state.free,
+            total: state.total,
+        })
+    }
+
+    async fn upload_file(
+        &self,
+        mut upload: FileUploadDescriptor,
+    ) -> Result<UploadResponse, FileUploadError> {
+        upload.path.path = sanitize(&upload.path.path);
+        let path = upload.path.path.clone();
+        let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+        let origin = origin(&upload.path.location);
+        let endpoint = format!("/api/files/{}", origin);
+        {
+            let mut state = self.state();
+            if name.is_empty() {
+                return Err(status(
+                    &endpoint,
+                    StatusCode::BAD_REQUEST,
+                    "No file included",
+                ));
+            }
+            if matches!(upload.path.location, FileLocation::Sdcard) && !state.connected {
+                return Err(status(
+                    &endpoint,
+                    StatusCode::CONFLICT,
+                    "Can not upload to SD card, printer is not operational",
+                ));
+            }
+            if let Some(index) = state.find(&upload.path) {
+                if state.is_selected_for_print(&state.files[index]) {
+                    return Err(status(
+                        &endpoint,
+                        StatusCode::CONFLICT,
+                        "Trying to overwrite a file that is currently in use",
+                    ));
+                }
+            }
+            state.store(origin, &path, upload.contents.len() as u64);
+        }
+
+        let done = if upload.select || upload.print {
+            self.issue_file_command(FileCommandDescriptor {
+                command: FileCommand::Select {
+                    print: upload.print,
+                },
+                path: upload.path,
+            })
+            .await
+            .is_ok()
+        } else {
+            true
+        };
+
+        let file = UploadedFile {
+            name,
+            origin: origin.to_string(),
+            path: Some(path),
+            refs: None,
+        };
+        Ok(UploadResponse {
+            files: [(origin.to_string(), file)].into_iter().collect(),
+            done,
+        })
+    }
+
+    async fn get_file(
+        &self,
+        file_descriptor: FileFetchDescriptor,
+    ) -> Result<Entry, FileRequestError> {
+        let state = self.state();
+        let path = &file_descriptor.path;
+
+        if let Some(index) = state.find(path) {
+            return Ok(state.files[index].entry());
+        }
+        if !state.is_folder(path) {
+            return Err(status(
+                &files_endpoint(path),
+                StatusCode::NOT_FOUND,
+                "File not found",
+            ));
+        }
+
+        let folder = path.path.trim_matches('/');
+        let name = folder.rsplit('/').next().unwrap_or(folder).to_string();
+        Ok(Entry::Folder {
+            children: state.entries(origin(&path.location), folder, file_descriptor.recursive),
+            display: name.clone(),
+            name,
+            origin: origin(&path.location).to_string(),
+            path: folder.to_string(),
+            refs: None,
+            type_path: vec!["folder".to_string()],
+            size: None,
+        })
+    }
+
+    async fn issue_file_command(
+        &self,
+        command: FileCommandDescriptor,
+    ) -> Result<(), FileCommandError> {
+        let mut state = self.state();
+        let endpoint = files_endpoint(&command.path);
+
+        let Some(index) = state.find(&command.path) else {
+            return Err(status(&endpoint, StatusCode::NOT_FOUND, "File not found"));
+        };
+        let file = state.files[index].clone();
+
+        match command.command {
+            FileCommand::Select { print } => {
+                if state.is_busy() {
+                    return Err(status(&endpoint, StatusCode::CONFLICT, "Printer is busy"));
+                }
+                if print && !state.connected {
+                    return Err(status(
+                        &endpoint,
+                        StatusCode::CONFLICT,
+                        "Printer is not operational",
+                    ));
+                }
+
+                state.selected = Some(file);
+                state.completion = 0.0;
+                if print {
+                    state.job = JobState::Printing;
+                }
+            }
+            FileCommand::Unselect => {
+                if state.is_busy() {
+                    return Err(status(&endpoint, StatusCode::CONFLICT, "Printer is busy"));
+                }
+                state.selected = None;
+            }
+            FileCommand::Copy { ref destination } | FileCommand::Move { ref destination } => {
+                let moving = matches!(command.command, FileCommand::Move { .. });
+                if moving && state.is_selected_for_print(&file) {
+                    return Err(status(
+                        &endpoint,
+                        StatusCode::CONFLICT,
+                        "Trying to move a file which is currently in use",
+                    ));
+                }
+
+                let destination = destination.trim_matches('/');
+                let path = if destination.is_empty() {
+                    file.name().to_string()
+                } else {
+                    format!("{}/{}", destination, file.name())
+                };
+                if state
+                    .files
+                    .iter()
+                    .any(|f| f.origin == file.origin && f.path == path)
+                {
+                    return Err(status(
+                        &endpoint,
+                        StatusCode::CONFLICT,
+                        "File already exists",
+                    ));
+                }
+
+                if moving {
+                    state.files[index].path = path;
+                } else {
+                    state.free = state.free.saturating_sub(file.size);
+                    state.files.push(FakeFile { path, ..file });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_file(&self, path: PathDescriptor) -> Result<(), FileDeletionError> {
+        let mut state = self.state();
+        let endpoint = files_endpoint(&path);
+
+        let Some(index) = state.find(&path) else {
+            return Err(status(&endpoint, StatusCode::NOT_FOUND, "File not found"));
+        };
+        if state.is_selected_for_print(&state.files[index]) {
+            return Err(status(
+                &endpoint,
+                StatusCode::CONFLICT,
+                "Trying to delete a file that is currently being printed",
+            ));
+        }
+
+        let file = state.files.remove(index);
+        state.free += file.size;
+        if state.selected.as_ref() == Some(&file) {
+            state.selected = None;
+        }
+        Ok(())
+    }
+
+    async fn issue_job_command(&self, command: JobCommand) -> Result<(), JobCommandError> {
+        let mut state = self.state();
+        let conflict = |body| Err(status("/api/job", StatusCode::CONFLICT, body));
+
+        if !state.connected {
+            return conflict("Printer is not operational");
+        }
+
+        match (command, state.job) {
+            (JobCommand::Start, JobState::Operational) => {
+                if state.selected.is_none() {
+                    return conflict("No file selected");
+                }
+                state.job = JobState::Printing;
+                state.completion = 0.0;
+            }
+            (JobCommand::Cancel, JobState::Printing | JobState::Paused) => {
+                state.job = JobState::Operational;
+            }
+            (JobCommand::Pause | JobCommand::Toggle, JobState::Printing) => {
+                state.job = JobState::Paused;
+            }
+            (JobCommand::Resume | JobCommand::Toggle, JobState::Paused) => {
+                state.job = JobState::Printing;
+            }
+            (JobCommand::Start, _) => return conflict("Printer is already printing"),
+            _ => return conflict("Printer is not printing"),
+        }
+        Ok(())
+    }
+
+    async fn get_job(&self) -> Result<JobInformation, InformationRequestError> {
+        let state = self.state();
+        let selected = state.selected.as_ref();
+
+        let completion = selected.map(|_| state.completion);
+        Ok(JobInformation {
+            job: Job {
+                estimated_print_time: None,
+                filament: FilamentToolOpt {
+                    length: None,
+                    volume: None,
+                },
+                file: JobFile {
+                    date: None,
+                    name: selected.map(|f| f.name().to_string()),
+                    origin: selected.map(|f| f.origin.to_string()),
+                    path: selected.map(|f| f.path.clone()),
+                    size: selected.map(|f| f.size),
+                },
+                last_print_time: None,
+                user: None,
+            },
+            progress: JobProgress {
+                completion,
+                filepos: selected.map(|f| (f.size as f64 * state.completion as f64 / 100.0) as u64),
+                print_time: None,
+                print_time_left: None,
+                print_time_origin: None,
+            },
+            state: state.state_text().to_string(),
+        })
+    }
+
+    async fn get_printer_telemetry(&self) -> Result<RawPrinter, DeviceStateError> {
+        let state = self.state();
+        if !state.connected {
+            return Err(status(
+                "/api/printer",
+                StatusCode::CONFLICT,
+                "Printer is not operational",
+            ));
+        }
+
+        let mut temperature = state.tool_state(None);
+        temperature
+            .tools
+            .insert("bed".to_string(), state.bed.to_tool());
+
+        Ok(RawPrinter {
+            temperature,
+            sd: SdState { ready: true },
+            state: PrinterState {
+                text: state.state_text().to_string(),
+                flags: state.flags(),
+            },
+        })
+    }
+
+    async fn send_gcode(&self, commands: Vec<String>) -> Result<(), PrinterCommandError> {
+        if commands.is_empty() {
+            return Err(PrinterCommandError::BadRequest(
+                "At least one command is required".to_string(),
+            ));
+        }
+
+        let mut state = self.state();
+        if !state.connected {
+            return Err(status(
+                "/api/printer/command",
+                StatusCode::CONFLICT,
+                "Printer is not operational",
+            ));
+        }
+        state.gcode.extend(commands);
+        Ok(())
+    }
+
+    async fn move_printhead(
+        &self,
+        _command: PrintheadMoveDescriptor,
+    ) -> Result<(), ToolCommandError> {
+        let state = self.state();
+        if !state.connected || state.job == JobState::Printing {
+            return Err(status(
+                "/api/printer/printhead",
+                StatusCode::CONFLICT,
+                "Printer is not operational or currently printing",
+            ));
+        }
+        Ok(())
+    }
+
+    async fn change_printhead_feedrate(&self, factor: f32) -> Result<(), ToolCommandError> {
+        if !(0.5..=2.0).contains(&factor) {
+            return Err(ToolCommandError::BadRequest(
+                "Feedrate factor must be between 0.5 and 2.0".to_string(),
+            ));
+        }
+
+        let mut state = self.state();
+        if !state.connected {
+            return Err(status(
+                "/api/printer/printhead",
+                StatusCode::CONFLICT,
+                "Printer is not operational",
+            ));
+        }
+        state.feedrate = factor;
+        Ok(())
+    }
+
+    async fn tool_temperature(&self, command: ToolTempDescriptor) -> Result<(), ToolCommandError> {
+        let mut state = self.state();
+        if !state.connected {
+            return Err(status(
+                "/api/printer/tool",
+                StatusCode::CONFLICT,
+                "Printer is not operational",
+            ));
+        }
+
+        let (values, target) = match command {
+            ToolTempDescriptor::Target { tool, temperature } => {
+                (BTreeMap::from([(tool, temperature)]), true)
+            }
+            ToolTempDescriptor::Offset { tool, amount } => {
+                (BTreeMap::from([(tool, amount)]), false)
+            }
+            ToolTempDescriptor::Targets(targets) => (targets, true),
+            ToolTempDescriptor::Offsets(offsets) => (offsets, false),
+        };
+
+        // Like OctoPrint, nothing is changed if one of the tools is invalid
+        let unknown = |tool: &ToolId| !state.tools.contains_key(&tool.to_string());
+        if values.is_empty() || values.keys().any(unknown) {
+            return Err(status(
+                "/api/printer/tool",
+                StatusCode::BAD_REQUEST,
+                "Invalid tool",
+            ));
+        }
+        for (tool, value) in values {
+            let temperature = state
+                .tools
+                .get_mut(&tool.to_string())
+                .expect("validated above");
+            if target {
+                temperature.target = value;
+            } else {
+                temperature.offset = value;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_tool_state(&self, history: Option<u32>) -> Result<ToolState, DeviceStateError> {
+        let state = self.state();
+        if !state.connected {
+            return Err(status(
+                "/api/printer",
+                StatusCode::CONFLICT,
+                "Printer is not operational",
+            ));
+        }
+        Ok(state.tool_state(history))
+    }
+
+    async fn select_tool(&self, tool: ToolId) -> Result<(), ToolCommandError> {
+        let mut state = self.state();
+        if !state.connected || state.job == JobState::Printing {
+            return Err(status(
+                "/api/printer/tool",
+                StatusCode::CONFLICT,
+                "Printer is not operational or currently printing",
+            ));
+        }
+        if !state.tools.contains_key(&tool.to_string()) {
+            return Err(status(
+                "/api/printer/tool",
+                StatusCode::BAD_REQUEST,
+                "Invalid tool",
+            ));
+        }
+        state.selected_tool = tool;
+        Ok(())
+    }
+
+    async fn extrude(&self, _amount: f32) -> Result<(), ToolCommandError> {
+        let state = self.state();
+        if !state.connected || state.job == JobState::Printing {
+            return Err(status(
+                "/api/printer/tool",
+                StatusCode::CONFLICT,
+                "Printer is not operational or currently printing",
+            ));
+        }
+        Ok(())
+    }
+
+    async fn change_tool_flowrate(&self, factor: f32) -> Result<(), ToolCommandError> {
+        let mut state = self.state();
+        if !state.connected {
+            return Err(status(
+                "/api/printer/tool",
+                StatusCode::CONFLICT,
+                "Printer is not operational",
+            ));
+        }
+        if !(0.75..=1.25).contains(&factor) {
+            return Err(status(
+                "/api/printer/tool",
+                StatusCode::BAD_REQUEST,
+                "Factor must be between 0.75 and 1.25",
+            ));
+        }
+        state.flowrate = factor;
+        Ok(())
+    }
+
+    async fn change_bed_temp(&self, command: BedTempDescriptor) -> Result<(), ToolCommandError> {
+        let mut state = self.state();
+        if !state.connected {
+            return Err(status(
+                "/api/printer/bed",
+                StatusCode::CONFLICT,
+                "Printer is not operational",
+            ));
+        }
+
+        match command {
+            BedTempDescriptor::Target { target } => state.bed.target = target,
+            BedTempDescriptor::Offset { amount } => state.bed.offset = amount,
+        }
+        Ok(())
+    }
+
+    async fn get_bed_state(&self, history: Option<u32>) -> Result<BedState, DeviceStateError> {
+        let state = self.state();
+        if !state.connected {
+            return Err(status(
+                "/api/printer",
+                StatusCode::CONFLICT,
+                "Printer is not operational",
+            ));
+        }
+
+        Ok(BedState {
+            history: history.map(|_| vec![]),
+            bed: state.bed.to_tool(),
+        })
+    }
+
+    async fn get_temperature_profiles(
+        &self,
+    ) -> Result<Vec<TemperatureProfile>, TemperatureProfileError> {
+        Ok(self.state().temperature_profiles.clone())
+    }
+
+    async fn apply_temperature_profile(&self, name: &str) -> Result<(), TemperatureProfileError> {
+        let profile = self
+            .state()
+            .temperature_profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+            .ok_or_else(|| TemperatureProfileError::UnknownProfile(name.to_string()))?;
+
+        self.set_all_temperatures(profile.extruder, profile.bed)
+            .await
+    }
+
+    async fn cool_down(&self) -> Result<(), TemperatureProfileError> {
+        self.set_all_temperatures(0.0, 0.0).await
+    }
+
+    async fn get_pi_support(&self) -> Result<PiSupport, InformationRequestError> {
+        self.state().pi_support.clone().ok_or_else(|| {
+            status(
+                "/plugin/pi_support",
+                StatusCode::NOT_FOUND,
+                "Unknown plugin",
+            )
+        })
+    }
+
+    async fn get_action_prompt(&self) -> Result<Option<ActionPrompt>, InformationRequestError> {
+        Ok(self.state().prompt.clone())
+    }
+
+    async fn select_prompt_choice(&self, choice: usize) -> Result<(), PromptCommandError> {
+        let mut state = self.state();
+        let Some(prompt) = &state.prompt else {
+            return Err(status(
+                "/plugin/action_command_prompt",
+                StatusCode::CONFLICT,
+                "No active prompt",
+            ));
+        };
+        if choice >= prompt.choices.len() {
+            return Err(status(
+                "/plugin/action_command_prompt",
+                StatusCode::BAD_REQUEST,
+                "Invalid choice",
+            ));
+        }
+
+        state.prompt = None;
+        state.prompt_answers.push(choice);
+        Ok(())
+    }
+
+    async fn get_webcams(&self) -> Result<Webcams, InformationRequestError> {
+        let state = self.state();
+
+        Ok(Webcams {
+            webcams: state
+                .snapshot
+                .iter()
+                .map(|snapshot| Webcam {
+                    name: "classic".to_string(),
+                    display_name: Some("Classic Webcam".to_string()),
+                    can_snapshot: true,
+                    flip_h: snapshot.orientation.flip_h,
+                    flip_v: snapshot.orientation.flip_v,
+                    rotate90: snapshot.orientation.rotate_90,
+                    compat: None,
+                })
+                .collect(),
+        })
+    }
+
+    async fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        self.state()
+            .snapshot
+            .clone()
+            .ok_or(SnapshotError::NoSnapshotUrl)
+    }
+}
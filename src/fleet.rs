@@ -0,0 +1,249 @@
+//! Runs operations on many printers at once.
+//!
+//! A [`Fleet`] owns named printers and runs an operation on all of them concurrently, with at
+//! most [`concurrency`](Fleet::concurrency) operations in flight. The result of every printer
+//! is collected in [`FleetResults`], so one unreachable printer does not hide the others.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use octoprint_rs::fleet::Fleet;
+//! use octoprint_rs::PrinterBuilder;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let mut fleet = Fleet::new().concurrency(4);
+//! fleet.add("prusa-1", PrinterBuilder::new("10.0.0.11", "API_KEY").build());
+//! fleet.add("prusa-2", PrinterBuilder::new("10.0.0.12", "API_KEY").build());
+//!
+//! let jobs = fleet.run(|printer| async move { printer.get_job().await }).await;
+//! for (name, job) in jobs.ok() {
+//!     println!("{}: {}", name, job.state);
+//! }
+//! for (name, error) in jobs.errors() {
+//!     eprintln!("{}: {:?}", name, error);
+//! }
+//! # }
+//! ```
+
+use std::{
+    collections::{btree_map, BTreeMap},
+    future::Future,
+    sync::Arc,
+};
+
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::{
+    api::OctoPrintApi,
+    errors::OctoPrintError,
+    types::{JobInformation, RawPrinter},
+    Printer,
+};
+
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Named printers that operations are run on concurrently.
+///
+/// Printers are kept in the order of their names. They are generic over [`OctoPrintApi`], so a
+/// fleet of [`FakePrinter`](crate::fake::FakePrinter)s can stand in for real printers in tests.
+pub struct Fleet<P = Printer> {
+    printers: BTreeMap<String, Arc<P>>,
+    concurrency: usize,
+}
+
+/// The current state and job of a printer, see [`Fleet::snapshot`].
+#[derive(Debug)]
+pub struct PrinterSnapshot {
+    pub printer: RawPrinter,
+    pub job: JobInformation,
+}
+
+/// The result of an operation for every printer of a [`Fleet`], in the order of their names.
+#[derive(Debug)]
+pub struct FleetResults<T, E> {
+    results: BTreeMap<String, Result<T, E>>,
+}
+
+impl<P> Default for Fleet<P> {
+    fn default() -> Self {
+        Fleet {
+            printers: BTreeMap::new(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl<P> Fleet<P> {
+    /// Creates an empty fleet that runs up to 8 operations at once.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how many operations run at once. A `limit` of `0` is treated as `1`.
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Adds a printer under `name`, returning the printer that had this name before.
+    pub fn add<S: ToString>(&mut self, name: S, printer: P) -> Option<Arc<P>> {
+        self.printers.insert(name.to_string(), Arc::new(printer))
+    }
+
+    /// Removes the printer named `name` from the fleet.
+    pub fn remove(&mut self, name: &str) -> Option<Arc<P>> {
+        self.printers.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<P>> {
+        self.printers.get(name)
+    }
+
+    /// The names of all printers, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.printers.keys().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<P>)> {
+        self.printers
+            .iter()
+            .map(|(name, printer)| (name.as_str(), printer))
+    }
+
+    pub fn len(&self) -> usize {
+        self.printers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.printers.is_empty()
+    }
+}
+
+impl<P: OctoPrintApi + 'static> Fleet<P> {
+    /// Runs `operation` on every printer, with at most [`concurrency`](#method.concurrency)
+    /// operations at once.
+    ///
+    /// Every operation runs as a task of its own, so it gets a shared handle to its printer.
+    ///
+    /// # Returns
+    ///
+    /// The result of every printer, including the failed ones.
+    ///
+    /// # Panics
+    ///
+    /// If an operation panics, the panic is resumed once every other operation has finished.
+    pub async fn run<F, Fut, T, E>(&self, operation: F) -> FleetResults<T, E>
+    where
+        F: Fn(Arc<P>) -> Fut,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        T: Send + 'static,
+        E: Send + 'static,
+    {
+        let permits = Arc::new(Semaphore::new(self.concurrency));
+        let mut tasks = JoinSet::new();
+        for (name, printer) in &self.printers {
+            let name = name.clone();
+            let permits = permits.clone();
+            let future = operation(printer.clone());
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                (name, future.await)
+            });
+        }
+
+        let mut results = BTreeMap::new();
+        let mut panic = None;
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((name, result)) => {
+                    results.insert(name, result);
+                }
+                Err(e) if e.is_panic() => panic = Some(e.into_panic()),
+                // Tasks are only cancelled when the set is dropped
+                Err(_) => {}
+            }
+        }
+
+        if let Some(panic) = panic {
+            std::panic::resume_unwind(panic);
+        }
+        FleetResults { results }
+    }
+
+    /// Gets the state and the current job of every printer.
+    ///
+    /// Both are requested at the same time, see
+    /// [`get_printer_telemetry()`](OctoPrintApi::get_printer_telemetry) and
+    /// [`get_job()`](OctoPrintApi::get_job).
+    ///
+    /// # Errors
+    ///
+    /// The result of a printer is the first error of both requests. A printer that is not
+    /// connected to OctoPrint fails with a `409 Conflict` status.
+    pub async fn snapshot(&self) -> FleetResults<PrinterSnapshot, OctoPrintError> {
+        self.run(|printer| async move {
+            let (state, job) = tokio::join!(printer.get_printer_telemetry(), printer.get_job());
+            Ok::<_, OctoPrintError>(PrinterSnapshot {
+                printer: state?,
+                job: job?,
+            })
+        })
+        .await
+    }
+}
+
+impl<T, E> FleetResults<T, E> {
+    /// The result of the printer named `name`.
+    pub fn get(&self, name: &str) -> Option<&Result<T, E>> {
+        self.results.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Result<T, E>)> {
+        self.results
+            .iter()
+            .map(|(name, result)| (name.as_str(), result))
+    }
+
+    /// The values of the printers the operation succeeded on.
+    pub fn ok(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.iter()
+            .filter_map(|(name, result)| result.as_ref().ok().map(|value| (name, value)))
+    }
+
+    /// The errors of the printers the operation failed on.
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &E)> {
+        self.iter()
+            .filter_map(|(name, result)| result.as_ref().err().map(|error| (name, error)))
+    }
+
+    /// Whether the operation succeeded on every printer.
+    pub fn all_ok(&self) -> bool {
+        self.results.values().all(Result::is_ok)
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+impl<T, E> IntoIterator for FleetResults<T, E> {
+    type Item = (String, Result<T, E>);
+    type IntoIter = btree_map::IntoIter<String, Result<T, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+impl<T, E> FromIterator<(String, Result<T, E>)> for FleetResults<T, E> {
+    fn from_iter<I: IntoIterator<Item = (String, Result<T, E>)>>(iter: I) -> Self {
+        FleetResults {
+            results: iter.into_iter().collect(),
+        }
+    }
+}
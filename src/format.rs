@@ -0,0 +1,46 @@
+//! Human readable sizes and durations, as shown by the command line tools.
+
+/// Formats a byte count like `1.4 MB`.
+pub fn size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Formats seconds like `1h 05m` or `3m 20s`.
+pub fn duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m {:02}s", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(size(999), "999 B");
+        assert_eq!(size(1_200), "1.2 KB");
+        assert_eq!(size(801_365), "801.4 KB");
+        assert_eq!(size(423_737_376_768), "423.7 GB");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration(200), "3m 20s");
+        assert_eq!(duration(3900), "1h 05m");
+    }
+}
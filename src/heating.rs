@@ -0,0 +1,135 @@
+//! Waiting for a heater to reach its target, shared by [`Printer`](crate::Printer) and the
+//! default methods of [`OctoPrintApi`].
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::{
+    api::OctoPrintApi,
+    errors::{HeatError, OctoPrintError},
+    types::{BedTempDescriptor, ToolId, ToolTempDescriptor},
+};
+
+/// How far in °C the temperature may drift away from the closest it got to the target, before
+/// the heater is suspected to run away.
+const RUNAWAY_HYSTERESIS: f32 = 10.0;
+
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(crate) enum Heater {
+    Tool(ToolId),
+    Bed,
+}
+
+/// How close the temperature has to get to its target, for how long.
+pub(crate) struct Band {
+    pub target: f32,
+    pub tolerance: f32,
+    pub stable_for: Duration,
+    pub timeout: Duration,
+}
+
+impl Heater {
+    fn name(&self) -> String {
+        match self {
+            Heater::Tool(tool) => tool.to_string(),
+            Heater::Bed => "bed".to_string(),
+        }
+    }
+
+    async fn set_target<P: OctoPrintApi + ?Sized>(
+        &self,
+        printer: &P,
+        target: f32,
+    ) -> Result<(), OctoPrintError> {
+        match self {
+            Heater::Tool(tool) => {
+                printer
+                    .tool_temperature(ToolTempDescriptor::Target {
+                        tool: *tool,
+                        temperature: target,
+                    })
+                    .await?
+            }
+            Heater::Bed => {
+                printer
+                    .change_bed_temp(BedTempDescriptor::Target { target })
+                    .await?
+            }
+        }
+        Ok(())
+    }
+
+    async fn actual<P: OctoPrintApi + ?Sized>(&self, printer: &P) -> Result<f32, OctoPrintError> {
+        match self {
+            Heater::Tool(tool) => printer
+                .get_tool_state(None)
+                .await?
+                .tools
+                .get(&tool.to_string())
+                .map(|state| state.actual)
+                .ok_or_else(|| OctoPrintError::InvalidArgument(format!("unknown tool {}", tool))),
+            Heater::Bed => Ok(printer.get_bed_state(None).await?.bed.actual),
+        }
+    }
+}
+
+/// Sets the target of `heater` and polls its temperature until it stayed within the band for
+/// `stable_for`. Returns the last temperature measured.
+///
+/// A target of 0 turns the heater off, which only cools down to the ambient temperature, so it is
+/// rejected. On a suspected thermal runaway the heater is turned off before returning.
+pub(crate) async fn heat_and_wait<P: OctoPrintApi + ?Sized>(
+    printer: &P,
+    heater: Heater,
+    band: Band,
+) -> Result<f32, HeatError> {
+    if band.target <= 0.0 {
+        let message = format!(
+            "{} can not settle at {}°C, it only cools down to the ambient temperature",
+            heater.name(),
+            band.target
+        );
+        return Err(OctoPrintError::InvalidArgument(message).into());
+    }
+    heater.set_target(printer, band.target).await?;
+
+    let poll_interval = (band.stable_for / 4).clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL);
+    let deadline = Instant::now() + band.timeout;
+    let mut closest = f32::INFINITY;
+    let mut stable_since = None;
+    loop {
+        let actual = heater.actual(printer).await?;
+        let now = Instant::now();
+        let distance = (actual - band.target).abs();
+
+        if distance <= band.tolerance {
+            let since = *stable_since.get_or_insert(now);
+            if now - since >= band.stable_for {
+                return Ok(actual);
+            }
+        } else {
+            stable_since = None;
+            if distance > closest.max(band.tolerance) + RUNAWAY_HYSTERESIS {
+                heater.set_target(printer, 0.0).await?;
+                return Err(HeatError::ThermalRunawaySuspected {
+                    heater: heater.name(),
+                    target: band.target,
+                    actual,
+                });
+            }
+        }
+        closest = closest.min(distance);
+
+        if now >= deadline {
+            return Err(HeatError::Timeout {
+                heater: heater.name(),
+                target: band.target,
+                actual,
+            });
+        }
+        tokio::time::sleep(poll_interval.min(deadline - now)).await;
+    }
+}
@@ -0,0 +1,427 @@
+//! Records the temperature history of printers to CSV or JSON Lines files.
+//!
+//! OctoPrint keeps the temperatures of the last minutes and returns them as the history of
+//! [`get_tool_state`](crate::Printer::get_tool_state) and
+//! [`get_bed_state`](crate::Printer::get_bed_state). Polling these windows often enough that
+//! they overlap gives a continuous log: [`merge_history`] combines the tool and bed samples of
+//! a window into one [`TemperatureRecord`] per timestamp, and a [`HistoryWriter`] only writes
+//! the records newer than the ones it already wrote. As the tool and bed windows are requested
+//! separately, the newer end of one window is held back until the other one covers it too.
+//!
+//! [`HistoryRecorder`] does both for every printer of a [`Fleet`], writing one file per printer.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use octoprint_rs::config::FleetConfig;
+//! use octoprint_rs::history::{HistoryFormat, HistoryRecorder};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let fleet = FleetConfig::load("printers.json")?.fleet()?;
+//! let mut recorder = HistoryRecorder::new("temperatures").format(HistoryFormat::JsonLines);
+//! loop {
+//!     for (name, error) in recorder.record(&fleet).await.errors() {
+//!         eprintln!("{}: {}", name, error);
+//!     }
+//!     tokio::time::sleep(Duration::from_secs(60)).await;
+//! }
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::OctoPrintApi,
+    errors::{DeviceStateError, HistoryError, OctoPrintError},
+    fleet::{Fleet, FleetResults},
+    types::{BedState, PrinterTool, ToolState},
+};
+
+/// The number of samples requested from OctoPrint by default. OctoPrint keeps a sample every
+/// two seconds, so this is about ten minutes.
+const DEFAULT_LIMIT: u32 = 300;
+
+/// The temperatures of every heater at one point in time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemperatureRecord {
+    /// Seconds since the unix epoch.
+    pub time: u64,
+    /// The tools and the bed by name.
+    #[serde(flatten)]
+    pub heaters: BTreeMap<String, PrinterTool>,
+}
+
+/// The file format of a [`HistoryWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// A `time` column and an `_actual` and `_target` column per heater. The heaters are fixed by
+    /// the first records written to the file, see [`HistoryWriter::fits`].
+    Csv,
+    /// One [`TemperatureRecord`] as JSON per line.
+    JsonLines,
+}
+
+/// Writes [`TemperatureRecord`]s, skipping every record that is not newer than the last one
+/// written.
+pub struct HistoryWriter<W> {
+    out: W,
+    format: HistoryFormat,
+    columns: Option<Vec<String>>,
+    last_time: Option<u64>,
+}
+
+/// Records the temperature history of the printers of a [`Fleet`] into one file per printer,
+/// named after the printer. See the [module docs](self).
+pub struct HistoryRecorder {
+    directory: PathBuf,
+    format: HistoryFormat,
+    limit: u32,
+    writers: BTreeMap<String, HistoryWriter<File>>,
+}
+
+/// Merges the history windows of the tools and the bed into records sorted by time. Samples
+/// with the same time are combined into one record.
+///
+/// Records newer than the end of the shorter window are left out, as the other window does not
+/// cover them yet. They are part of the next, later windows.
+pub fn merge_history(tools: &ToolState, bed: Option<&BedState>) -> Vec<TemperatureRecord> {
+    let tools_end = tools.history.iter().flatten().map(|e| e.time).max();
+    let bed_end = bed
+        .and_then(|b| b.history.as_ref())
+        .into_iter()
+        .flatten()
+        .map(|e| e.time)
+        .max();
+    let end = match (tools_end, bed_end) {
+        (Some(tools_end), Some(bed_end)) => tools_end.min(bed_end),
+        _ => u64::MAX,
+    };
+
+    let mut records: BTreeMap<u64, BTreeMap<String, PrinterTool>> = BTreeMap::new();
+    for entry in tools.history.iter().flatten() {
+        records.entry(entry.time).or_default().extend(
+            entry
+                .tools
+                .iter()
+                .map(|(name, tool)| (name.clone(), tool.clone())),
+        );
+    }
+    for entry in bed.and_then(|b| b.history.as_ref()).into_iter().flatten() {
+        records
+            .entry(entry.time)
+            .or_default()
+            .insert("bed".to_string(), entry.bed.clone());
+    }
+
+    records
+        .into_iter()
+        .take_while(|(time, _)| *time <= end)
+        .map(|(time, heaters)| TemperatureRecord { time, heaters })
+        .collect()
+}
+
+impl HistoryFormat {
+    /// The file extension of the format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            HistoryFormat::Csv => "csv",
+            HistoryFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+impl<W: Write> HistoryWriter<W> {
+    /// Creates a writer for a new, empty output.
+    pub fn new(out: W, format: HistoryFormat) -> Self {
+        HistoryWriter {
+            out,
+            format,
+            columns: None,
+            last_time: None,
+        }
+    }
+
+    /// The time of the newest record written.
+    pub fn last_time(&self) -> Option<u64> {
+        self.last_time
+    }
+
+    /// Whether every heater of the records newer than the last one written has a column. Always
+    /// true for JSON Lines and for a CSV output without records yet.
+    pub fn fits(&self, records: &[TemperatureRecord]) -> bool {
+        let Some(columns) = &self.columns else {
+            return true;
+        };
+        records
+            .iter()
+            .filter(|record| self.last_time.is_none_or(|last| record.time > last))
+            .flat_map(|record| record.heaters.keys())
+            .all(|name| columns.contains(name))
+    }
+
+    /// Writes the records that are newer than the last one written and flushes the output.
+    ///
+    /// # Returns
+    ///
+    /// The number of records written.
+    ///
+    /// # Errors
+    ///
+    /// If the output can not be written, or if a heater has no column in the CSV output. Nothing
+    /// is written then.
+    pub fn write(&mut self, records: &[TemperatureRecord]) -> io::Result<usize> {
+        if !self.fits(records) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "a heater has no column in the CSV output",
+            ));
+        }
+        if self.format == HistoryFormat::Csv && self.columns.is_none() && !records.is_empty() {
+            let columns: BTreeSet<&String> =
+                records.iter().flat_map(|r| r.heaters.keys()).collect();
+            let header: Vec<String> = columns
+                .iter()
+                .flat_map(|name| [format!("{}_actual", name), format!("{}_target", name)])
+                .collect();
+            writeln!(self.out, "time,{}", header.join(","))?;
+            self.columns = Some(columns.into_iter().cloned().collect());
+        }
+
+        let mut written = 0;
+        for record in records {
+            if self.last_time.is_some_and(|last| record.time <= last) {
+                continue;
+            }
+
+            match self.format {
+                HistoryFormat::Csv => self.write_csv(record)?,
+                HistoryFormat::JsonLines => {
+                    serde_json::to_writer(&mut self.out, record)?;
+                    writeln!(self.out)?;
+                }
+            }
+            self.last_time = Some(record.time);
+            written += 1;
+        }
+        self.out.flush()?;
+        Ok(written)
+    }
+
+    fn write_csv(&mut self, record: &TemperatureRecord) -> io::Result<()> {
+        let columns = self.columns.as_deref().unwrap_or_default();
+        let values: Vec<String> = columns
+            .iter()
+            .flat_map(|name| match record.heaters.get(name) {
+                Some(tool) => [
+                    tool.actual.to_string(),
+                    tool.target.map_or(String::new(), |t| t.to_string()),
+                ],
+                None => [String::new(), String::new()],
+            })
+            .collect();
+        writeln!(self.out, "{},{}", record.time, values.join(","))
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl HistoryWriter<File> {
+    /// Opens `path` for appending, creating it if needed. Records are only written if they are
+    /// newer than the last record already in the file, and CSV files keep their columns.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be read or opened, or its content does not match `format`.
+    pub fn append<P: AsRef<Path>>(path: P, format: HistoryFormat) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut writer = HistoryWriter::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+            format,
+        );
+
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let Some(first) = lines.next().transpose()? else {
+            return Ok(writer);
+        };
+        let last = match lines.last().transpose()? {
+            Some(last) => last,
+            None => first.clone(),
+        };
+
+        let invalid = |message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), message),
+            )
+        };
+        match format {
+            HistoryFormat::Csv => {
+                let header: Vec<&str> = first.split(',').collect();
+                if header.first() != Some(&"time") {
+                    return Err(invalid("expected a `time` column first"));
+                }
+                writer.columns = Some(
+                    header[1..]
+                        .iter()
+                        .filter_map(|column| column.strip_suffix("_actual"))
+                        .map(str::to_string)
+                        .collect(),
+                );
+                if last != first {
+                    let time = last.split(',').next().and_then(|t| t.parse().ok());
+                    writer.last_time = Some(time.ok_or_else(|| invalid("invalid last record"))?);
+                }
+            }
+            HistoryFormat::JsonLines => {
+                let record: TemperatureRecord =
+                    serde_json::from_str(&last).map_err(|e| invalid(&e.to_string()))?;
+                writer.last_time = Some(record.time);
+            }
+        }
+        Ok(writer)
+    }
+}
+
+impl HistoryRecorder {
+    /// Creates a recorder that writes CSV files into `directory`, which is created if needed.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        HistoryRecorder {
+            directory: directory.as_ref().to_path_buf(),
+            format: HistoryFormat::Csv,
+            limit: DEFAULT_LIMIT,
+            writers: BTreeMap::new(),
+        }
+    }
+
+    pub fn format(mut self, format: HistoryFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set how many samples of the history to request. The time between two
+    /// [`record`](#method.record)s has to be shorter than this window to not miss samples.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// The file the history of the printer named `name` is written to. Every character of the
+    /// name other than a letter, digit, `-`, `_` or `.` is replaced with `_`, so the file is
+    /// always in the directory of the recorder.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.directory
+            .join(format!("{}.{}", file_name(name), self.format.extension()))
+    }
+
+    /// Requests the temperature history of every printer and appends the new records to its
+    /// file.
+    ///
+    /// # Returns
+    ///
+    /// The number of records written for every printer. Nothing is written for a printer that
+    /// is not connected to OctoPrint, and only the tools for a printer without a heated bed.
+    ///
+    /// When a heater shows up that has no column in the CSV file of a printer, the file is moved
+    /// aside to `<name>.<time of its last record>.csv` and a new file is started.
+    ///
+    /// # Errors
+    ///
+    /// The result of a printer is
+    /// * `Request` - If the history can not be requested.
+    /// * `Io` - If the file of the printer can not be written.
+    pub async fn record<P: OctoPrintApi + 'static>(
+        &mut self,
+        fleet: &Fleet<P>,
+    ) -> FleetResults<usize, HistoryError> {
+        let limit = self.limit;
+        let histories = fleet
+            .run(|printer| async move {
+                let (tools, bed) = tokio::join!(
+                    printer.get_tool_state(Some(limit)),
+                    printer.get_bed_state(Some(limit))
+                );
+                let bed = match bed {
+                    // The printer profile has no heated bed
+                    Err(DeviceStateError::Conflict(_)) => None,
+                    bed => Some(bed),
+                };
+                match (tools, bed) {
+                    (Err(DeviceStateError::Conflict(_)), _) => Ok(vec![]),
+                    (tools, bed) => {
+                        Ok::<_, OctoPrintError>(merge_history(&tools?, bed.transpose()?.as_ref()))
+                    }
+                }
+            })
+            .await;
+
+        histories
+            .into_iter()
+            .map(|(name, records)| {
+                let written = match records {
+                    Ok(records) => self.write(&name, &records),
+                    Err(e) => Err(HistoryError::Request(e)),
+                };
+                (name, written)
+            })
+            .collect()
+    }
+
+    fn write(&mut self, name: &str, records: &[TemperatureRecord]) -> Result<usize, HistoryError> {
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        let path = self.path(name);
+        let io_error = |source| HistoryError::Io {
+            path: path.clone(),
+            source,
+        };
+        let mut writer = match self.writers.remove(name) {
+            Some(writer) => writer,
+            None => {
+                fs::create_dir_all(&self.directory).map_err(io_error)?;
+                HistoryWriter::append(&path, self.format).map_err(io_error)?
+            }
+        };
+
+        if !writer.fits(records) {
+            let last_time = writer.last_time();
+            let full = self.directory.join(format!(
+                "{}.{}.{}",
+                file_name(name),
+                last_time.unwrap_or_default(),
+                self.format.extension()
+            ));
+            // The file has to be closed before it can be moved aside on Windows
+            drop(writer);
+            fs::rename(&path, full).map_err(io_error)?;
+            writer = HistoryWriter::append(&path, self.format).map_err(io_error)?;
+            // The records up to here are in the moved file
+            writer.last_time = last_time;
+        }
+        let written = writer.write(records).map_err(io_error)?;
+        self.writers.insert(name.to_string(), writer);
+        Ok(written)
+    }
+}
+
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
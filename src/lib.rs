@@ -1,55 +1,462 @@
+use std::time::Duration;
+
 use errors::*;
-use reqwest::{Client, StatusCode};
+use reqwest::{header::HeaderValue, Client, Request, RequestBuilder, Response, StatusCode};
+use retry::RetryPolicy;
+use serde::de::DeserializeOwned;
+use trace::RequestTrace;
 use types::*;
 
+pub mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod cache;
+pub mod config;
 pub mod errors;
+#[cfg(feature = "exporter")]
+pub mod exporter;
+pub mod fake;
+pub mod fleet;
+// Only shared by the octoctl and octodash binaries, not part of the library api
+#[cfg(any(feature = "cli", feature = "tui"))]
+#[doc(hidden)]
+pub mod format;
+mod heating;
+pub mod history;
+pub mod queue;
+pub mod retry;
+#[cfg(feature = "test-server")]
+pub mod test_server;
+mod tls;
+mod trace;
 pub mod types;
 
 pub struct Printer {
-    address: String,
-    port: u16,
+    base_url: String,
     api_key: String,
     client: Client,
+    retry_policy: RetryPolicy,
+    cache: Option<cache::ResponseCache>,
 }
 
 pub struct PrinterBuilder {
+    scheme: String,
     address: String,
-    port: u16,
+    port: Option<u16>,
+    path_prefix: String,
     api_key: String,
-    client: Client,
+    tls: tls::TlsOptions,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    cache_ttl: Option<Duration>,
 }
 
 impl PrinterBuilder {
     /// Creates a new `PrinterBuilder` from an `address` and an `api_key`.
-    /// `port` can be set with the [`port()`](#method.port) method and efaults to `80`.
+    /// `port` can be set with the [`port()`](#method.port) method and defaults to `80`, or `443`
+    /// if [`https()`](#method.https) is enabled.
     pub fn new<P: ToString, P2: ToString>(address: P2, api_key: P) -> Self {
         Self {
+            scheme: "http".to_string(),
             address: address.to_string(),
-            port: 80,
+            port: None,
+            path_prefix: String::new(),
             api_key: api_key.to_string(),
-            client: Client::new(),
+            tls: tls::TlsOptions::default(),
+            connect_timeout: None,
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            cache_ttl: None,
+        }
+    }
+
+    /// Creates a new `PrinterBuilder` from a full base url and an `api_key`.
+    ///
+    /// The url can contain a scheme, host, port and path prefix, for example
+    /// `https://farm.example/octoprint-3/`. All endpoints are resolved relative to it, which is
+    /// useful if OctoPrint is running behind a reverse proxy.
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidUrl` - If the url can not be parsed.
+    /// * `UnsupportedScheme` - If the scheme is not `http` or `https`.
+    /// * `MissingHost` - If the url does not contain a host.
+    pub fn from_base_url<U: AsRef<str>, P: ToString>(
+        url: U,
+        api_key: P,
+    ) -> Result<Self, PrinterBuilderError> {
+        let url = reqwest::Url::parse(url.as_ref())
+            .map_err(|e| PrinterBuilderError::InvalidUrl(e.to_string()))?;
+
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(PrinterBuilderError::UnsupportedScheme(
+                url.scheme().to_string(),
+            ));
         }
+
+        let address = url.host_str().ok_or(PrinterBuilderError::MissingHost)?;
+
+        Ok(Self {
+            scheme: url.scheme().to_string(),
+            address: address.to_string(),
+            port: url.port(),
+            path_prefix: url.path().to_string(),
+            api_key: api_key.to_string(),
+            tls: tls::TlsOptions::default(),
+            connect_timeout: None,
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            cache_ttl: None,
+        })
     }
 
     /// Set the `port` of the printer
-    /// If this is not set, it will default to `80`
+    /// If this is not set, it will default to `80`, or `443` if [`https()`](#method.https) is
+    /// enabled.
     pub fn port(mut self, port: u16) -> Self {
-        self.port = port;
+        self.port = Some(port);
+        self
+    }
+
+    /// Use `https` instead of `http` to connect to the printer.
+    pub fn https(mut self, https: bool) -> Self {
+        self.scheme = if https { "https" } else { "http" }.to_string();
+        self
+    }
+
+    /// Set a path prefix that is put in front of every endpoint, for example `octoprint-3` if
+    /// the printer is served at `https://farm.example/octoprint-3/` by a reverse proxy.
+    pub fn path_prefix<P: ToString>(mut self, path_prefix: P) -> Self {
+        self.path_prefix = path_prefix.to_string();
+        self
+    }
+
+    /// Trust the certificates in `pem` in addition to the default root certificates. Use this
+    /// if the printer is served with a self-signed certificate or one issued by a private CA.
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidCertificate` - If `pem` does not contain a valid certificate.
+    pub fn add_root_certificate_pem<C: AsRef<[u8]>>(
+        mut self,
+        pem: C,
+    ) -> Result<Self, PrinterBuilderError> {
+        self.tls.add_root_certificate_pem(pem.as_ref())?;
+        Ok(self)
+    }
+
+    /// Present a client certificate to the server, for servers that require mutual TLS.
+    ///
+    /// # Arguments
+    ///
+    /// * `certificate_pem` - The client certificate, optionally followed by its intermediates.
+    /// * `key_pem` - The private key of the certificate in PKCS#8, PKCS#1 or SEC1 format.
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidCertificate` - If `certificate_pem` does not contain a valid certificate.
+    /// * `InvalidPrivateKey` - If `key_pem` does not contain a supported private key.
+    pub fn client_identity_pem<C: AsRef<[u8]>, K: AsRef<[u8]>>(
+        mut self,
+        certificate_pem: C,
+        key_pem: K,
+    ) -> Result<Self, PrinterBuilderError> {
+        self.tls
+            .set_identity_pem(certificate_pem.as_ref(), key_pem.as_ref())?;
+        Ok(self)
+    }
+
+    /// Only accept a server certificate with the given SHA-256 fingerprint. The certificate is
+    /// accepted even if it is self-signed or expired, as long as the fingerprint matches. The pin
+    /// also applies if [`danger_accept_invalid_certs()`](#method.danger_accept_invalid_certs) is
+    /// enabled.
+    ///
+    /// The fingerprint is hex encoded and may contain colons, as printed by
+    /// `openssl x509 -noout -fingerprint -sha256`.
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidFingerprint` - If `fingerprint` is not a hex encoded SHA-256 hash.
+    pub fn pin_certificate_sha256(
+        mut self,
+        fingerprint: &str,
+    ) -> Result<Self, PrinterBuilderError> {
+        self.tls.set_pinned_sha256(fingerprint)?;
+        Ok(self)
+    }
+
+    /// Accept any server certificate, no matter if it is valid or not.
+    ///
+    /// This makes the connection vulnerable to man-in-the-middle attacks and leaks the api key
+    /// to anyone who intercepts it. Only use this for lab setups. It has no effect if a
+    /// certificate is pinned with [`pin_certificate_sha256()`](#method.pin_certificate_sha256).
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.tls.set_accept_invalid_certs(accept);
+        self
+    }
+
+    /// Set the timeout for connecting to the printer.
+    /// If this is not set, connecting never times out.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout for a whole request, from connecting until the response body has been
+    /// read. This includes uploads, so it has to leave enough time to upload the largest file.
+    /// If this is not set, requests never time out.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the [`RetryPolicy`](retry::RetryPolicy) used for every request.
+    /// If this is not set, requests are never retried.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Cache the responses of file listings, settings and printer profiles.
+    ///
+    /// A cached response younger than `ttl` is returned without asking the printer. Once it is
+    /// older, the request is sent with the `If-None-Match` and `If-Modified-Since` headers of
+    /// the cached response and the cached response is used again if the printer answers with
+    /// `304 Not Modified`. A `ttl` of zero always revalidates.
+    ///
+    /// Changing a file through this `Printer` drops the cached file listings and changing the
+    /// connection drops the cached printer profiles. Changes made elsewhere are only seen once
+    /// the `ttl` expired. If this is not set, nothing is cached.
+    pub fn cache(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
         self
     }
 
     /// Build the `Printer` struct
+    ///
+    /// # Panics
+    ///
+    /// Like [`reqwest::Client::new()`], this panics if the TLS backend can not be initialized.
     pub fn build(self) -> Printer {
+        let port = self
+            .port
+            .unwrap_or(if self.scheme == "https" { 443 } else { 80 });
+
+        let path_prefix = self.path_prefix.trim_matches('/');
+        let base_url = if path_prefix.is_empty() {
+            format!("{}://{}:{}/", self.scheme, self.address, port)
+        } else {
+            format!(
+                "{}://{}:{}/{}/",
+                self.scheme, self.address, port, path_prefix
+            )
+        };
+
+        let mut client = Client::builder();
+        if !self.tls.is_default() {
+            client = client.use_preconfigured_tls(self.tls.client_config());
+        }
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+
         Printer {
-            address: self.address,
-            port: self.port,
+            base_url,
             api_key: self.api_key,
-            client: self.client,
+            client: client
+                .build()
+                .expect("failed to initialize the TLS backend"),
+            retry_policy: self.retry_policy,
+            cache: self.cache_ttl.map(cache::ResponseCache::new),
         }
     }
+
+    /// Build a [`blocking::Printer`] that can be used without an async runtime.
+    ///
+    /// # Panics
+    ///
+    /// Like [`PrinterBuilder::build`], and if the runtime of the blocking printer can not be
+    /// started.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> blocking::Printer {
+        blocking::Printer::new(self.build())
+    }
 }
 
 impl Printer {
+    /// Joins `path` onto the base url of the printer. `path` is always treated as relative to
+    /// the base url, so a leading `/` does not escape the path prefix. Absolute urls are
+    /// returned as is.
+    fn endpoint(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            return path.to_string();
+        }
+
+        format!("{}{}", self.base_url, path.trim_start_matches('/'))
+    }
+
+    /// Sends a request with the api key and checks the status code of the response. Every
+    /// request to the printer goes through here. The api key is left out if the request goes to
+    /// another origin than the base url, like a webcam on another host.
+    ///
+    /// If the server responds with an error status code, the body is read into an
+    /// `OctoPrintError::Status`, so the methods only have to map status codes to their own
+    /// error enum.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, OctoPrintError> {
+        let mut request = request.build()?;
+        if self.is_same_origin(request.url()) {
+            request
+                .headers_mut()
+                .insert("X-Api-Key", self.api_key_header()?);
+        }
+        let endpoint = request.url().path().to_string();
+
+        let trace = RequestTrace::start(&self.base_url, request.method(), &endpoint);
+        let (res, retries) = trace.instrument(self.execute(request, &trace)).await;
+        trace.finish(res.as_ref().ok().map(Response::status), retries);
+
+        let res = res.map_err(|source| OctoPrintError::Request {
+            endpoint: Some(endpoint.clone()),
+            source,
+        })?;
+
+        let status = res.status();
+        if status.is_success() {
+            return Ok(res);
+        }
+
+        let body = res.text().await.map_err(|source| OctoPrintError::Request {
+            endpoint: Some(endpoint.clone()),
+            source,
+        })?;
+
+        Err(OctoPrintError::Status {
+            endpoint: Some(endpoint),
+            status,
+            body,
+        })
+    }
+
+    /// Sends a request and parses the json response into `T`.
+    async fn request_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, OctoPrintError> {
+        let res = self.send(request).await?;
+        let endpoint = res.url().path().to_string();
+
+        let text = res.text().await.map_err(|source| OctoPrintError::Request {
+            endpoint: Some(endpoint.clone()),
+            source,
+        })?;
+
+        Self::parse_json(endpoint, &text)
+    }
+
+    /// Sends a `GET` request through the response cache, if it is enabled, and parses the json
+    /// response into `T`.
+    async fn request_cached<T: DeserializeOwned>(&self, url: &str) -> Result<T, OctoPrintError> {
+        let Some(cache) = &self.cache else {
+            return self.request_json(self.client.get(url)).await;
+        };
+
+        let endpoint = reqwest::Url::parse(url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| url.to_string());
+        if let Some(text) = cache.fresh(url) {
+            return Self::parse_json(endpoint, &text);
+        }
+
+        let request = cache.conditional(url, self.client.get(url));
+        let res = match self.send(request).await {
+            Ok(res) => res,
+            Err(OctoPrintError::Status { status, .. }) if status == StatusCode::NOT_MODIFIED => {
+                if let Some(text) = cache.revalidated(url) {
+                    return Self::parse_json(endpoint, &text);
+                }
+                // The entry was dropped while the request was in flight
+                return self.request_json(self.client.get(url)).await;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let headers = res.headers().clone();
+        let text = res.text().await.map_err(|source| OctoPrintError::Request {
+            endpoint: Some(endpoint.clone()),
+            source,
+        })?;
+
+        let parsed = Self::parse_json(endpoint, &text)?;
+        cache.store(url, &headers, &text);
+        Ok(parsed)
+    }
+
+    /// Drops the cached responses of every url below `path`.
+    fn invalidate_cache(&self, path: &str) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(&self.endpoint(path));
+        }
+    }
+
+    fn parse_json<T: DeserializeOwned>(endpoint: String, text: &str) -> Result<T, OctoPrintError> {
+        let result = &mut serde_json::Deserializer::from_str(text);
+        serde_path_to_error::deserialize(result).map_err(|e| OctoPrintError::Parse {
+            endpoint: Some(endpoint),
+            path: e.path().to_string(),
+            message: e.inner().to_string(),
+        })
+    }
+
+    /// Whether `url` has the scheme, host and port of the base url.
+    fn is_same_origin(&self, url: &reqwest::Url) -> bool {
+        reqwest::Url::parse(&self.base_url).is_ok_and(|base| base.origin() == url.origin())
+    }
+
+    /// The api key as a header value that is marked as sensitive, so it is redacted whenever
+    /// the request is logged.
+    fn api_key_header(&self) -> Result<HeaderValue, OctoPrintError> {
+        let mut value = HeaderValue::from_str(&self.api_key).map_err(|_| {
+            OctoPrintError::InvalidArgument("the api key is not a valid header value".to_string())
+        })?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+
+    /// Executes a request, retrying it according to the [`RetryPolicy`](retry::RetryPolicy).
+    /// Returns the result of the last attempt and the number of retries.
+    async fn execute(
+        &self,
+        request: Request,
+        trace: &RequestTrace,
+    ) -> (Result<Response, reqwest::Error>, u32) {
+        if !self.retry_policy.allows(request.method()) {
+            return (self.client.execute(request).await, 0);
+        }
+
+        let mut attempt = 0;
+        loop {
+            // Streaming bodies can not be cloned and are only sent once
+            let Some(retry) = request.try_clone() else {
+                return (self.client.execute(request).await, attempt);
+            };
+
+            let result = self.client.execute(retry).await;
+            if attempt >= self.retry_policy.max_retries || !self.retry_policy.should_retry(&result)
+            {
+                return (result, attempt);
+            }
+
+            let delay = self.retry_policy.backoff(attempt);
+            trace.retry(attempt + 1, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
     //
     //  INFO: General printer information
     //
@@ -62,21 +469,9 @@ impl Printer {
     /// * `ReqwestError` - If the request fails
     /// * `ParseError` - If the response can not be parsed
     pub async fn get_api_version(&self) -> Result<types::ApiVersion, InformationRequestError> {
-        let url = format!("http://{}:{}/api/version", self.address, self.port);
+        let url = self.endpoint("api/version");
 
-        dbg!(&url);
-
-        let res = self
-            .client
-            .get(&url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await;
-
-        res.map_err(InformationRequestError::ReqwestError)?
-            .json::<types::ApiVersion>()
-            .await
-            .map_err(|e| InformationRequestError::ParseError(e.to_string()))
+        Ok(self.request_json(self.client.get(&url)).await?)
     }
 
     //
@@ -94,18 +489,9 @@ impl Printer {
     pub async fn get_connection(
         &self,
     ) -> Result<types::PrinterConnection, InformationRequestError> {
-        let url = format!("http://{}:{}/api/connection", self.address, self.port);
-        let res = self
-            .client
-            .get(&url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await;
+        let url = self.endpoint("api/connection");
 
-        res.map_err(InformationRequestError::ReqwestError)?
-            .json::<types::PrinterConnection>()
-            .await
-            .map_err(|e| InformationRequestError::ParseError(e.to_string()))
+        Ok(self.request_json(self.client.get(&url)).await?)
     }
 
     /// Set the connection settings of the printer
@@ -123,25 +509,29 @@ impl Printer {
         &self,
         connection: types::ConnectionCommandDescriptor,
     ) -> Result<(), SetConnectionError> {
-        let url = format!("http://{}:{}/api/connection", self.address, self.port);
-        let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&connection.to_post())
-            .send()
-            .await;
+        let url = self.endpoint("api/connection");
 
-        let body = res.map_err(SetConnectionError::ReqwestError)?;
-        if body.status().is_success() {
-            Ok(())
-        } else {
-            let text = body
-                .text()
-                .await
-                .map_err(SetConnectionError::ReqwestError)?;
-            Err(SetConnectionError::BadRequest(text))
-        }
+        self.send(self.client.post(&url).json(&connection.to_post()))
+            .await?;
+        // The current printer profile follows the connection
+        self.invalidate_cache("api/printerprofiles");
+        Ok(())
+    }
+
+    /// Returns every printer profile configured in OctoPrint, including their build volume and
+    /// extruders.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a `InformationRequestError`
+    /// * `ReqwestError` - If the request fails
+    /// * `ParseError` - If the response can not be parsed
+    pub async fn get_printer_profiles(
+        &self,
+    ) -> Result<types::printer_profiles::Profiles, InformationRequestError> {
+        let url = self.endpoint("api/printerprofiles");
+
+        Ok(self.request_cached(&url).await?)
     }
 
     //
@@ -180,42 +570,99 @@ impl Printer {
             ""
         };
 
-        let url = format!(
-            "http://{}:{}/api/files{}{}",
-            self.address, self.port, location, query_params
-        );
+        let url = self.endpoint(&format!("api/files{}{}", location, query_params));
 
-        dbg!(&url);
+        if files_descriptor.force {
+            return Ok(self.request_json(self.client.get(&url)).await?);
+        }
+        Ok(self.request_cached(&url).await?)
+    }
 
-        let res = self
-            .client
-            .get(&url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await;
+    /// Walks all files on the printer recursively and aggregates their print history.
+    ///
+    /// # Returns
+    ///
+    /// A [`PrintStatisticsReport`](types::printer_files::PrintStatisticsReport) containing the
+    /// success and failure counts, the estimated print time per printer profile and the last
+    /// printed date, as well as the statistics of every printed file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`get_files()`](#method.get_files).
+    pub async fn print_statistics(
+        &self,
+    ) -> Result<types::printer_files::PrintStatisticsReport, FileRequestError> {
+        let files = self
+            .get_files(types::FilesFetchDescriptor {
+                location: types::FilesLocation::Root,
+                recursive: true,
+                force: false,
+            })
+            .await?;
+
+        Ok(types::printer_files::PrintStatisticsReport::from_entries(
+            &files.files,
+        ))
+    }
 
-        let body = res.map_err(FileRequestError::ReqwestError)?;
-        let status = body.status();
+    /// Uploads a file to the printer.
+    ///
+    /// # Arguments
+    ///
+    /// * `upload` - A struct describing the file to upload
+    ///     * `path` - The location and the path of the file, including its name
+    ///     * `contents` - The contents of the file
+    ///     * `select` - If the file should be selected after the upload
+    ///     * `print` - If the file should be printed after the upload
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`FileUploadError`](errors::FileUploadError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `ParseError` - If the response can not be parsed.
+    /// * `BadRequest` - If the server responds with a `400` StatusCode.
+    /// * `NotFound` - If the location does not exist.
+    /// * `Conflict` - If the file is currently being printed.
+    /// * `UnsupportedFileType` - If the file is neither G-code nor a model.
+    pub async fn upload_file(
+        &self,
+        upload: types::FileUploadDescriptor,
+    ) -> Result<types::UploadResponse, FileUploadError> {
+        let location = match upload.path.location {
+            types::FileLocation::Local => "local",
+            types::FileLocation::Sdcard => "sdcard",
+        };
 
-        if status.is_server_error() {
-            return Err(FileRequestError::ServerError);
+        let path = upload.path.path.trim_matches('/');
+        let (folder, file_name) = path.rsplit_once('/').unwrap_or(("", path));
+        if file_name.is_empty() {
+            return Err(OctoPrintError::InvalidArgument(
+                "the upload path does not contain a file name".to_string(),
+            )
+            .into());
         }
 
-        let text = body
-            .text()
-            .await
-            .map_err(FileRequestError::ReqwestError)?;
-
-        if status.is_success() {
-            let result = &mut serde_json::Deserializer::from_str(text.as_str());
-            let deserialized = serde_path_to_error::deserialize(result)
-                .map_err(|e| FileRequestError::ParseError(e.to_string()))?;
-            return Ok(deserialized);
+        let part =
+            reqwest::multipart::Part::bytes(upload.contents).file_name(file_name.to_string());
+        let mut form = reqwest::multipart::Form::new().part("file", part);
+        if !folder.is_empty() {
+            form = form.text("path", folder.to_string());
         }
-        Err(FileRequestError::NotFound(text))
-    }
+        if upload.select || upload.print {
+            form = form.text("select", "true");
+        }
+        if upload.print {
+            form = form.text("print", "true");
+        }
+
+        let url = self.endpoint(&format!("api/files/{}", location));
 
-    //  TODO: Make file uploads work
+        let res = self
+            .request_json(self.client.post(&url).multipart(form))
+            .await;
+        self.invalidate_cache("api/files");
+        Ok(res?)
+    }
 
     /// Gets a single file or folder from the printer
     ///
@@ -259,35 +706,12 @@ impl Printer {
             ""
         };
 
-        let url = format!(
-            "http://{}:{}/api/files/{}{}{}",
-            self.address, self.port, location, path, query_params,
-        );
+        let url = self.endpoint(&format!("api/files/{}{}{}", location, path, query_params));
 
-        let res = self
-            .client
-            .get(&url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await;
-
-        let body = res.map_err(FileRequestError::ReqwestError)?;
-        let status = body.status();
-
-        let text = body
-            .text()
-            .await
-            .map_err(FileRequestError::ReqwestError)?;
-        match status {
-            StatusCode::NOT_FOUND => Err(FileRequestError::NotFound(text)),
-            StatusCode::INTERNAL_SERVER_ERROR => Err(FileRequestError::ServerError),
-            _ => {
-                let result = &mut serde_json::Deserializer::from_str(text.as_str());
-                let deserialized = serde_path_to_error::deserialize(result)
-                    .map_err(|e| FileRequestError::ParseError(e.to_string()))?;
-                Ok(deserialized)
-            }
+        if file_descriptor.force {
+            return Ok(self.request_json(self.client.get(&url)).await?);
         }
+        Ok(self.request_cached(&url).await?)
     }
 
     /// Will issue a file command to the printer.
@@ -306,7 +730,7 @@ impl Printer {
     /// * `ReqwestError` - If the request fails
     /// * `Conflict` - If the server responds with a `409` status code
     /// * `BadRequest` - If the server responds with a `400` or `500` status code.
-    ///    Make sure to check your destination path.
+    ///   Make sure to check your destination path.
     ///
     /// # Example
     /// ```
@@ -345,39 +769,14 @@ impl Printer {
             .strip_prefix('/')
             .unwrap_or(&command.path.path);
 
-        let url = format!(
-            "http://{}:{}/api/files/{}{}",
-            self.address, self.port, location, path
-        );
+        let url = self.endpoint(&format!("api/files/{}{}", location, path));
 
         let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&command.to_post())
-            .send()
+            .send(self.client.post(&url).json(&command.to_post()))
             .await;
-
-        let body = res.map_err(FileCommandError::ReqwestError)?;
-        let status = body.status();
-
-        // Handle success case
-        if status.is_success() {
-            return Ok(());
-        }
-
-        // Handle error case
-        let text = body
-            .text()
-            .await
-            .map_err(FileCommandError::ReqwestError)?;
-        match status {
-            StatusCode::INTERNAL_SERVER_ERROR | StatusCode::BAD_REQUEST => {
-                Err(FileCommandError::BadRequest(text))
-            }
-            StatusCode::CONFLICT => Err(FileCommandError::Conflict(text)),
-            _ => unreachable!(),
-        }
+        self.invalidate_cache("api/files");
+        res?;
+        Ok(())
     }
 
     /// Deletes a file from the printer
@@ -389,37 +788,12 @@ impl Printer {
 
         let path = path.path.strip_prefix('/').unwrap_or(&path.path);
 
-        let url = format!(
-            "http://{}:{}/api/files/{}{}",
-            self.address, self.port, location, path
-        );
-
-        let res = self
-            .client
-            .delete(&url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await;
-
-        let body = res.map_err(FileDeletionError::ReqwestError)?;
-        let status = body.status();
-
-        // Handle success case
-        if status.is_success() {
-            return Ok(());
-        }
+        let url = self.endpoint(&format!("api/files/{}{}", location, path));
 
-        // Handle error case
-        let text = body
-            .text()
-            .await
-            .map_err(FileDeletionError::ReqwestError)?;
-        match status {
-            StatusCode::NOT_FOUND => Err(FileDeletionError::NotFound(text)),
-            StatusCode::CONFLICT => Err(FileDeletionError::Conflict(text)),
-            StatusCode::INTERNAL_SERVER_ERROR => Err(FileDeletionError::ServerError),
-            _ => unreachable!(),
-        }
+        let res = self.send(self.client.delete(&url)).await;
+        self.invalidate_cache("api/files");
+        res?;
+        Ok(())
     }
 
     //
@@ -437,32 +811,14 @@ impl Printer {
     /// If something goes wrong, it will return a [`JobCommandError`]
     /// * `ReqwestError` - If the request fails
     /// * `Conflict` - If the server responds with a `409` status code
-    /// This can happen if the printer is already printing and you try to start a new print
-    /// or delete the file its currently printing.
+    ///   This can happen if the printer is already printing and you try to start a new print
+    ///   or delete the file its currently printing.
     pub async fn issue_job_command(&self, command: JobCommand) -> Result<(), JobCommandError> {
-        let url = "/api/job";
-
-        let res = self
-            .client
-            .post(url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&command.to_raw_command())
-            .send()
-            .await;
+        let url = self.endpoint("api/job");
 
-        let body = res.map_err(JobCommandError::ReqwestError)?;
-        match body.status() {
-            StatusCode::NO_CONTENT => Ok(()),
-            StatusCode::INTERNAL_SERVER_ERROR => Err(JobCommandError::ServerError),
-            StatusCode::CONFLICT => {
-                let text = body
-                    .text()
-                    .await
-                    .map_err(JobCommandError::ReqwestError)?;
-                Err(JobCommandError::Conflict(text))
-            }
-            _ => unreachable!(),
-        }
+        self.send(self.client.post(&url).json(&command.to_raw_command()))
+            .await?;
+        Ok(())
     }
 
     /// Gets the current job information from the printer
@@ -476,7 +832,7 @@ impl Printer {
     /// If there is an error, it will return a `InformationRequestError`
     /// * `ReqwestError` - If the request fails
     /// * `ParseError` - If the response can not be parsed.
-    /// This can happen if the wrapper is outdated and they changed something in the api. (cry about it)
+    ///   This can happen if the wrapper is outdated and they changed something in the api. (cry about it)
     ///
     /// # Example
     ///
@@ -493,28 +849,9 @@ impl Printer {
     ///     .await;
     /// # }
     pub async fn get_job(&self) -> Result<types::JobInformation, InformationRequestError> {
-        let url = "/api/job";
+        let url = self.endpoint("api/job");
 
-        let res = self
-            .client
-            .get(url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await;
-
-        let body = res.map_err(InformationRequestError::ReqwestError)?;
-        if body.status().is_server_error() {
-            return Err(InformationRequestError::ServerError);
-        }
-
-        let text = body
-            .text()
-            .await
-            .map_err(InformationRequestError::ReqwestError)?;
-        let result = &mut serde_json::Deserializer::from_str(text.as_str());
-        let deserialized = serde_path_to_error::deserialize(result)
-            .map_err(|e| InformationRequestError::ParseError(e.to_string()))?;
-        Ok(deserialized)
+        Ok(self.request_json(self.client.get(&url)).await?)
     }
 
     //
@@ -532,7 +869,7 @@ impl Printer {
     /// If there is an error this function will return a [`PrinterCommandError`](errors::PrinterCommandError) enum.
     /// * `ReqwestError` - If the request fails
     /// * `Conflict` - If the server responds with a `409` status code. This can happen if the
-    /// printer is not connected.
+    ///   printer is not connected.
     ///
     /// # Example
     ///
@@ -551,31 +888,41 @@ impl Printer {
     ///     .await;
     /// # }
     pub async fn get_printer_telemetry(&self) -> Result<types::RawPrinter, DeviceStateError> {
-        let url = format!("http://{}:{}/api/printer", self.address, self.port);
+        let url = self.endpoint("api/printer");
 
-        let res = self
-            .client
-            .get(&url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await;
+        Ok(self.request_json(self.client.get(&url)).await?)
+    }
 
-        let body = res.map_err(DeviceStateError::ReqwestError)?;
-        let status = body.status();
-        let text = body
-            .text()
-            .await
-            .map_err(DeviceStateError::ReqwestError)?;
-        match status {
-            StatusCode::INTERNAL_SERVER_ERROR => Err(DeviceStateError::ServerError),
-            StatusCode::CONFLICT => Err(DeviceStateError::Conflict(text)),
-            _ => {
-                let result = &mut serde_json::Deserializer::from_str(text.as_str());
-                let deserialized = serde_path_to_error::deserialize(result)
-                    .map_err(|e| DeviceStateError::ParseError(e.to_string()))?;
-                Ok(deserialized)
-            }
+    /// Sends G-code commands to the printer, in order.
+    ///
+    /// # Arguments
+    ///
+    /// `commands` - The commands to send, like `"M115"` or `"G28 X"`.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`PrinterCommandError`](errors::PrinterCommandError)
+    /// enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `BadRequest` - If `commands` is empty.
+    /// * `Conflict` - If the server responds with a `409` StatusCode. This means the printer is not
+    ///   operational.
+    pub async fn send_gcode(&self, commands: Vec<String>) -> Result<(), PrinterCommandError> {
+        if commands.is_empty() {
+            return Err(PrinterCommandError::BadRequest(
+                "At least one command is required".to_string(),
+            ));
         }
+
+        let url = self.endpoint("api/printer/command");
+
+        self.send(
+            self.client
+                .post(&url)
+                .json(&types::GcodeCommand { commands }),
+        )
+        .await?;
+        Ok(())
     }
 
     //
@@ -596,9 +943,9 @@ impl Printer {
     /// If there is an error, it will return a [`PrintheadMoveError`](errors::PrintheadMoveError) enum.
     /// * `ReqwestError` - If the request fails
     /// * `BadRequest` - If the server responds with a `400` status code. Can happen if you give
-    /// it implossible values.
+    ///   it implossible values.
     /// * `Conflict` - If the server responds with a `409` status code. This means the printer is
-    /// currently printing.
+    ///   currently printing.
     ///
     /// # Example
     ///
@@ -609,36 +956,11 @@ impl Printer {
         &self,
         command: types::PrintheadMoveDescriptor,
     ) -> Result<(), ToolCommandError> {
-        let url = format!(
-            "http://{}:{}/api/printer/printhead",
-            self.address, self.port
-        );
+        let url = self.endpoint("api/printer/printhead");
 
-        let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&command.to_post())
-            .send()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
-
-        match res.status() {
-            StatusCode::NO_CONTENT => Ok(()),
-            StatusCode::INTERNAL_SERVER_ERROR => Err(ToolCommandError::ServerError),
-            status @ StatusCode::BAD_REQUEST | status @ StatusCode::CONFLICT => {
-                let text = res
-                    .text()
-                    .await
-                    .map_err(ToolCommandError::ReqwestError)?;
-                Err(match status {
-                    StatusCode::BAD_REQUEST => ToolCommandError::BadRequest(text),
-                    StatusCode::CONFLICT => ToolCommandError::Conflict(text),
-                    _ => unreachable!(),
-                })
-            }
-            _ => unreachable!(),
-        }
+        self.send(self.client.post(&url).json(&command.to_post()))
+            .await?;
+        Ok(())
     }
 
     /// Changes the feedrate of the printhead.
@@ -654,9 +976,9 @@ impl Printer {
     /// If there is an error, it will return a [`PrintheadCommandError`](errors::PrintheadCommandError) enum.
     /// * `ReqwestError` - If the request fails
     /// * `BadRequest` - If the server responds with a `400` StatusCode. This means you didnt give
-    /// it the a valid factor.
+    ///   it the a valid factor.
     /// * `Conflict` - If the server responds with a `409` StatusCode. This means the printer is
-    /// currently printing
+    ///   currently printing
     pub async fn change_printhead_feedrate(&self, factor: f32) -> Result<(), ToolCommandError> {
         if !(0.5..=2.0).contains(&factor) {
             return Err(ToolCommandError::BadRequest(
@@ -664,105 +986,100 @@ impl Printer {
             ));
         }
 
-        let url = format!(
-            "http://{}:{}/api/printer/printhead",
-            self.address, self.port
-        );
+        let url = self.endpoint("api/printer/printhead");
 
-        let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&types::PrintheadCommand::from_feedrate(factor))
-            .send()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
-
-        match res.status() {
-            StatusCode::NO_CONTENT => Ok(()),
-            StatusCode::INTERNAL_SERVER_ERROR => Err(ToolCommandError::ServerError),
-            status @ StatusCode::BAD_REQUEST | status @ StatusCode::CONFLICT => {
-                let text = res
-                    .text()
-                    .await
-                    .map_err(ToolCommandError::ReqwestError)?;
-                Err(match status {
-                    StatusCode::BAD_REQUEST => ToolCommandError::BadRequest(text),
-                    StatusCode::CONFLICT => ToolCommandError::Conflict(text),
-                    _ => unreachable!(),
-                })
-            }
-            _ => unreachable!(),
-        }
+        self.send(
+            self.client
+                .post(&url)
+                .json(&types::PrintheadCommand::from_feedrate(factor)),
+        )
+        .await?;
+        Ok(())
     }
 
     //
     //  NOTE: TOOL COMMANDS
     //
 
-    /// Used to change the temperature of a specified tool.
+    /// Used to change the temperature of one or more tools.
     /// This tools is usually called tool0 but you can get the tools by running
     /// [`get_tool_state()`](#method.get_tool_state).
     ///
     /// # Arguments
     ///
-    /// This function takes in a [`ToolTempDescriptor`](types::ToolTempDescriptor) which has two
-    /// variants, `Target` and `Offset`,
-    ///
-    /// Both of these variants have a `tool` field which is the target tool. This can be gotten by 
-    /// running [`get_tool_state()`](#method.get_tool_state).
+    /// This function takes in a [`ToolTempDescriptor`](types::ToolTempDescriptor) which has four
+    /// variants, `Target`, `Offset`, `Targets` and `Offsets`.
     ///
     /// `Offset` has two fields:
     /// * `tool` - The target tool.
-    /// * `offset` - The amount to offset the temperature by
+    /// * `amount` - The amount to offset the temperature by
     ///
     /// `Target` has two fields:
     /// * `tool` - The target tool.
-    /// * `target` - The target temperature.
+    /// * `temperature` - The target temperature.
+    ///
+    /// `Targets` and `Offsets` set the targets or offsets of every tool in the map with a single
+    /// command. Their tools are checked against the extruders of the current printer profile
+    /// first, which takes another request unless the profiles are
+    /// [cached](PrinterBuilder::cache). The tool of a single `Target` or `Offset` is only
+    /// checked if the profiles are cached. If the profiles can not be fetched, the command is
+    /// sent without checking it.
+    ///
+    /// # Errors
     ///
-    /// # Errors 
-    /// 
     /// If there is an error, it will return a [`ToolCommandError`](errors::ToolCommandError) enum.
     /// * `ReqwestError` - If the request fails.
     /// * `BadRequest` - If the server responds with a `400` StatusCode. This usually means you
-    /// didnt give it a valid tool. Run [`get_tool_state()`](#method.get_tool_state) to get the
-    /// valid tools.
+    ///   didnt give it a valid tool. Run [`get_tool_state()`](#method.get_tool_state) to get the
+    ///   valid tools. Also returned without sending the command if `Targets` or `Offsets` is
+    ///   empty or a checked tool is not one of the printer profile.
     /// * `Conflict` - If the server responds with a `409` StatusCode. This usually means there was a
-    /// conflict and the printer is currently printing.
+    ///   conflict and the printer is currently printing.
     pub async fn tool_temperature(
         &self,
         command: ToolTempDescriptor,
     ) -> Result<(), ToolCommandError> {
-        let url = format!("http://{}:{}/api/printer/tool", &self.address, &self.port);
-
-        let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&command.to_json())
-            .send()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
-
-        let status = res.status();
+        let changed = command.tools();
+        if changed.is_empty() {
+            return Err(ToolCommandError::BadRequest("no tools given".to_string()));
+        }
 
-        if status.is_success() {
-            return Ok(());
+        let many = matches!(
+            command,
+            ToolTempDescriptor::Targets(_) | ToolTempDescriptor::Offsets(_)
+        );
+        if !many && self.cache.is_none() {
+            return self.send_tool_temperature(command).await;
         }
-        if status.is_server_error() {
-            return Err(ToolCommandError::ServerError);
+
+        // The check is only a courtesy, the printer rejects unknown tools itself
+        let Ok(profiles) = self.get_printer_profiles().await else {
+            return self.send_tool_temperature(command).await;
+        };
+        if let Some(profile) = profiles.current() {
+            let tools = profile.tools();
+            if let Some(tool) = changed.iter().find(|tool| !tools.contains(tool)) {
+                return Err(ToolCommandError::BadRequest(format!(
+                    "{} is not a tool of the printer profile {}, which has {} heated tools",
+                    tool,
+                    profile.id,
+                    tools.len()
+                )));
+            }
         }
 
-        let text = res
-            .text()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
+        self.send_tool_temperature(command).await
+    }
 
-        match status {
-            StatusCode::BAD_REQUEST => Err(ToolCommandError::BadRequest(text)),
-            StatusCode::CONFLICT => Err(ToolCommandError::Conflict(text)),
-            _ => unreachable!(),
-        }
+    async fn send_tool_temperature(
+        &self,
+        command: ToolTempDescriptor,
+    ) -> Result<(), ToolCommandError> {
+        let url = self.endpoint("api/printer/tool");
+
+        self.send(self.client.post(&url).json(&command.to_json()))
+            .await?;
+        Ok(())
     }
 
     /// Gets the current state of all the tools on the printer.
@@ -777,9 +1094,9 @@ impl Printer {
     /// If there is an error, it will return a [`DeviceStateError`](errors::DeviceStateError) enum.
     /// * `ReqwestError` - If the request fails.
     /// * `ParseError` - If the response can not be parsed. This usually means the wrapper is 
-    /// outdated and they changed something in the api. (cry about it)
+    ///   outdated and they changed something in the api. (cry about it)
     /// * `Conflict` - If the server responds with a `409` StatusCode. This usually means the
-    /// printer is not operational or not connected.
+    ///   printer is not operational or not connected.
     pub async fn get_tool_state(
         &self,
         history: Option<u32>,
@@ -791,35 +1108,49 @@ impl Printer {
             query = "false".to_string();
         }
 
-        let url = format!(
-            "http://{}:{}/api/printer?history={}",
-            &self.address, &self.port, query
-        );
+        let url = self.endpoint(&format!("api/printer/tool?history={}", query));
 
-        let res = self
-            .client
-            .get(&url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await
-            .map_err(DeviceStateError::ReqwestError)?;
+        Ok(self.request_json(self.client.get(&url)).await?)
+    }
 
-        let status = res.status();
-        let text = res
-            .text()
-            .await
-            .map_err(DeviceStateError::ReqwestError)?;
-
-        match status {
-            StatusCode::CONFLICT => Err(DeviceStateError::Conflict(text)),
-            status if status.is_success() => {
-                let result = &mut serde_json::Deserializer::from_str(text.as_str());
-                let deserialized = serde_path_to_error::deserialize(result)
-                    .map_err(|e| DeviceStateError::ParseError(e.to_string()))?;
-                Ok(deserialized)
-            }
-            _ => unreachable!(),
-        }
+    /// Sets the target temperature of `tool` and waits until the measured temperature stayed
+    /// within `tolerance` of it for `stable_for`. Works for cooling down as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool` - The tool to heat, for example `tool0`.
+    /// * `target` - The target temperature in °C.
+    /// * `tolerance` - How far in °C the temperature may be from the target.
+    /// * `stable_for` - How long the temperature has to stay within the tolerance.
+    /// * `timeout` - How long to wait at most, starting after the target was set.
+    ///
+    /// # Returns
+    ///
+    /// The last temperature measured.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`HeatError`](errors::HeatError) enum.
+    /// * `Request` - If the target can not be set or the temperature can not be read. Also if
+    ///   `target` is 0°C or below, as a heater that is turned off never settles there.
+    /// * `Timeout` - If the temperature did not settle before `timeout`.
+    /// * `ThermalRunawaySuspected` - If the temperature drifted more than 10°C further away from
+    ///   the target than the closest it got. The heater is turned off first.
+    pub async fn heat_and_wait(
+        &self,
+        tool: ToolId,
+        target: f32,
+        tolerance: f32,
+        stable_for: Duration,
+        timeout: Duration,
+    ) -> Result<f32, HeatError> {
+        let band = heating::Band {
+            target,
+            tolerance,
+            stable_for,
+            timeout,
+        };
+        heating::heat_and_wait(self, heating::Heater::Tool(tool), band).await
     }
 
     /// Will select a tool on the printer. The selected tool can then be used to extrude or
@@ -827,54 +1158,28 @@ impl Printer {
     ///
     /// # Arguments
     ///
-    /// `tool` - A `String` representing the tool to select. Available tools can be gotten by 
-    /// running [`get_tool_state()`](#method.get_tool_state).
+    /// `tool` - The [`ToolId`](types::ToolId) of the tool to select. Available tools can be gotten
+    /// by running [`get_tool_state()`](#method.get_tool_state).
     ///
     /// # Errors 
     ///
     /// If there is an error, it will return a [`ToolCommandError`](errors::ToolCommandError) enum.
     /// * `ReqwestError` - If the request fails.
     /// * `BadRequest` - If the server responds with a `400` StatusCode. This usually means you
-    /// specified an invalid tool. Run [`get_tool_state()`](#method.get_tool_state) to get the valid
-    /// tools.
+    ///   specified an invalid tool. Run [`get_tool_state()`](#method.get_tool_state) to get the valid
+    ///   tools.
     /// * `Conflict` - If the server responds with a `409` StatusCode. This usually means the printer
-    /// is either currently printing or not operational.
-    pub async fn select_tool(&self, tool: String) -> Result<(), ToolCommandError> {
-        let url = format!("http://{}:{}/api/printer/tool", &self.address, &self.port);
+    ///   is either currently printing or not operational.
+    pub async fn select_tool(&self, tool: ToolId) -> Result<(), ToolCommandError> {
+        let url = self.endpoint("api/printer/tool");
 
         let request = ToolCommand::Select {
             command: "select".to_string(),
-            tool,
+            tool: tool.to_string(),
         };
 
-        let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&request)
-            .send()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
-
-        let status = res.status();
-
-        if status.is_success() {
-            return Ok(());
-        }
-        if status.is_server_error() {
-            return Err(ToolCommandError::ServerError);
-        }
-
-        let text = res
-            .text()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
-
-        match status {
-            StatusCode::BAD_REQUEST => Err(ToolCommandError::BadRequest(text)),
-            StatusCode::CONFLICT => Err(ToolCommandError::Conflict(text)),
-            _ => unreachable!(),
-        }
+        self.send(self.client.post(&url).json(&request)).await?;
+        Ok(())
     }
 
     /// Extrudes using the selected tool.
@@ -890,46 +1195,20 @@ impl Printer {
     /// If there is an error, it will return a [`ToolCommandError`](errors::ToolCommandError) enum.
     /// * `ReqwestError` - If the request fails.
     /// * `BadRequest` - If the server responds with a `400` StatusCode. This usually means you
-    /// specified an invalid tool. Run [`get_tool_state()`](#method.get_tool_state) to get the valid
-    /// tools.
+    ///   specified an invalid tool. Run [`get_tool_state()`](#method.get_tool_state) to get the valid
+    ///   tools.
     /// * `Conflict` - If the server responds with a `409` StatusCode. This usually means the printer
-    /// is either currently printing or not operational.
+    ///   is either currently printing or not operational.
     pub async fn extrude(&self, amount: f32) -> Result<(), ToolCommandError> {
-        let url = format!("http://{}:{}/api/printer/tool", &self.address, &self.port);
+        let url = self.endpoint("api/printer/tool");
 
         let request = ToolCommand::Extrude {
             command: "select".to_string(),
             amount,
         };
 
-        let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&request)
-            .send()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
-
-        let status = res.status();
-
-        if status.is_success() {
-            return Ok(());
-        }
-        if status.is_server_error() {
-            return Err(ToolCommandError::ServerError);
-        }
-
-        let text = res
-            .text()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
-
-        match status {
-            StatusCode::BAD_REQUEST => Err(ToolCommandError::BadRequest(text)),
-            StatusCode::CONFLICT => Err(ToolCommandError::Conflict(text)),
-            _ => unreachable!(),
-        }
+        self.send(self.client.post(&url).json(&request)).await?;
+        Ok(())
     }
 
     /// retracts filament from the selected tool.
@@ -953,118 +1232,459 @@ impl Printer {
     /// If there is an error, it will return a [`ToolCommandError`](errors::ToolCommandError) enum.
     /// * `ReqwestError` - If the request fails.
     /// * `BadRequest` - If the server responds with a `400` StatusCode. This usually means you
-    /// havent specified a valid tool or requested a factor outside of the supported range. Run 
-    /// [`get_tool_state()`](#method.get_tool_state) to get the valid tools.
+    ///   havent specified a valid tool or requested a factor outside of the supported range. Run
+    ///   [`get_tool_state()`](#method.get_tool_state) to get the valid tools.
     /// * `Conflict` - If the server responds with a `409` StatusCode. This usually means the printer
-    /// is either currently printing or not operational.
+    ///   is either currently printing or not operational.
     pub async fn change_tool_flowrate(&self, factor: f32) -> Result<(), ToolCommandError> {
-        let url = format!("http://{}:{}/api/printer/tool", &self.address, &self.port);
+        let url = self.endpoint("api/printer/tool");
 
         let request = ToolCommand::Flowrate {
             command: "select".to_string(),
             factor,
         };
 
-        let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&request)
-            .send()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
+        self.send(self.client.post(&url).json(&request)).await?;
+        Ok(())
+    }
 
-        let status = res.status();
+    //
+    //  NOTE: BED COMMANDS
+    //
 
-        if status.is_success() {
-            return Ok(());
-        }
-        if status.is_server_error() {
-            return Err(ToolCommandError::ServerError);
-        }
+    pub async fn change_bed_temp(
+        &self,
+        command: BedTempDescriptor,
+    ) -> Result<(), ToolCommandError> {
+        let url = self.endpoint("api/printer/bed");
 
-        let text = res
-            .text()
-            .await
-            .map_err(ToolCommandError::ReqwestError)?;
+        self.send(self.client.post(&url).json(&command.to_json()))
+            .await?;
+        Ok(())
+    }
 
-        match status {
-            StatusCode::BAD_REQUEST => Err(ToolCommandError::BadRequest(text)),
-            StatusCode::CONFLICT => Err(ToolCommandError::Conflict(text)),
-            _ => unreachable!(),
+    pub async fn get_bed_state(&self, history: Option<u32>) -> Result<BedState, DeviceStateError> {
+        let query: String;
+        if let Some(x) = history {
+            query = format!("true&limit={}", x);
+        } else {
+            query = "false".to_string();
         }
+
+        let url = self.endpoint(&format!("api/printer/bed?history={}", query));
+
+        Ok(self.request_json(self.client.get(&url)).await?)
+    }
+
+    /// Sets the target temperature of the bed and waits until the measured temperature stayed
+    /// within `tolerance` of it for `stable_for`.
+    ///
+    /// See [`heat_and_wait()`](#method.heat_and_wait) for the arguments and errors.
+    pub async fn heat_bed_and_wait(
+        &self,
+        target: f32,
+        tolerance: f32,
+        stable_for: Duration,
+        timeout: Duration,
+    ) -> Result<f32, HeatError> {
+        let band = heating::Band {
+            target,
+            tolerance,
+            stable_for,
+            timeout,
+        };
+        heating::heat_and_wait(self, heating::Heater::Bed, band).await
     }
 
     //
-    //  NOTE: BED COMMANDS
+    //  NOTE: TEMPERATURE PROFILES
     //
 
-    pub async fn change_bed_temp(
+    /// Gets the temperature profiles configured in OctoPrint's settings, like `PLA` or `ABS`.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a
+    /// [`TemperatureProfileError`](errors::TemperatureProfileError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `ParseError` - If the settings can not be parsed.
+    pub async fn get_temperature_profiles(
         &self,
-        command: BedTempDescriptor,
-    ) -> Result<(), ToolCommandError> {
-        let url = format!("http://{}:{}/api/printer/bed", &self.address, &self.port);
+    ) -> Result<Vec<TemperatureProfile>, TemperatureProfileError> {
+        let url = self.endpoint("api/settings");
 
-        let res = self
-            .client
-            .post(&url)
-            .header("X-Api-Key", &self.api_key)
-            .json(&command.to_json())
-            .send()
+        let settings: TemperatureSettings = self.request_cached(&url).await?;
+        Ok(settings.temperature.profiles)
+    }
+
+    /// Sets the targets of every extruder, the bed and the chamber to the temperatures of the
+    /// temperature profile called `name`. The bed and the chamber are only set if the current
+    /// printer profile has them heated, the chamber also only if the temperature profile sets it.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the temperature profile. Available profiles can be gotten by running
+    /// [`get_temperature_profiles()`](#method.get_temperature_profiles).
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a
+    /// [`TemperatureProfileError`](errors::TemperatureProfileError) enum.
+    /// * `ReqwestError` - If a request fails.
+    /// * `ParseError` - If the settings or printer profiles can not be parsed.
+    /// * `UnknownProfile` - If there is no temperature profile called `name`.
+    /// * `Conflict` - If the server responds with a `409` StatusCode. This usually means the
+    ///   printer is not operational.
+    pub async fn apply_temperature_profile(
+        &self,
+        name: &str,
+    ) -> Result<(), TemperatureProfileError> {
+        let profile = self
+            .get_temperature_profiles()
+            .await?
+            .into_iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| TemperatureProfileError::UnknownProfile(name.to_string()))?;
+
+        self.set_all_temperatures(profile.extruder, profile.bed, profile.chamber)
             .await
-            .map_err(ToolCommandError::ReqwestError)?;
+    }
 
-        let status = res.status();
+    /// Sets the targets of every extruder, the bed and the chamber to `0`, turning the heaters
+    /// off.
+    ///
+    /// # Errors
+    ///
+    /// See [`apply_temperature_profile()`](#method.apply_temperature_profile).
+    pub async fn cool_down(&self) -> Result<(), TemperatureProfileError> {
+        self.set_all_temperatures(0.0, 0.0, Some(0.0)).await
+    }
 
-        if status.is_success() {
-            return Ok(());
+    /// Sets the targets of the heaters the current printer profile has.
+    async fn set_all_temperatures(
+        &self,
+        extruder: f32,
+        bed: f32,
+        chamber: Option<f32>,
+    ) -> Result<(), TemperatureProfileError> {
+        let profiles = self
+            .get_printer_profiles()
+            .await
+            .map_err(OctoPrintError::from)?;
+        let (tools, heated_bed, heated_chamber) = match profiles.current() {
+            Some(profile) => (profile.tools(), profile.heated_bed, profile.heated_chamber),
+            None => (vec![ToolId::new(0)], true, false),
+        };
+
+        let targets = tools.into_iter().map(|tool| (tool, extruder)).collect();
+        self.send_tool_temperature(ToolTempDescriptor::Targets(targets))
+            .await
+            .map_err(OctoPrintError::from)?;
+        if heated_bed {
+            self.change_bed_temp(BedTempDescriptor::Target { target: bed })
+                .await
+                .map_err(OctoPrintError::from)?;
         }
-        if status.is_server_error() {
-            return Err(ToolCommandError::ServerError);
+        if let Some(target) = chamber.filter(|_| heated_chamber) {
+            // The chamber takes the same commands as the bed
+            let url = self.endpoint("api/printer/chamber");
+            let command = BedTempDescriptor::Target { target }.to_json();
+            self.send(self.client.post(&url).json(&command)).await?;
         }
+        Ok(())
+    }
+
+    //
+    //  NOTE: PLUGINS
+    //
+
+    /// Gets the Raspberry Pi information reported by the bundled `pi_support` plugin, including
+    /// the decoded throttle state. Use this to detect undervoltage or overheating of the Pi.
+    ///
+    /// # Returns
+    ///
+    /// A [`PiSupport`](types::PiSupport) struct. The throttle bitmask is decoded into a
+    /// [`ThrottleState`](types::ThrottleState).
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`InformationRequestError`](errors::InformationRequestError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `ParseError` - If the response can not be parsed.
+    /// * `NotFound` - If the plugin is not available. This usually means OctoPrint is not
+    ///   running on a Raspberry Pi.
+    pub async fn get_pi_support(&self) -> Result<PiSupport, InformationRequestError> {
+        let url = self.endpoint("plugin/pi_support");
 
-        let text = res.text().await.map_err(ToolCommandError::ReqwestError)?;
+        Ok(self.request_json(self.client.get(&url)).await?)
+    }
 
-        match status {
-            StatusCode::BAD_REQUEST => Err(ToolCommandError::BadRequest(text)),
-            StatusCode::CONFLICT => Err(ToolCommandError::Conflict(text)),
-            _ => unreachable!(),
+    /// Gets the currently active action command prompt from the `action_command_prompt` plugin.
+    ///
+    /// # Returns
+    ///
+    /// `Some(ActionPrompt)` if the printer is currently showing a prompt, otherwise `None`.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`InformationRequestError`](errors::InformationRequestError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `ParseError` - If the response can not be parsed.
+    /// * `NotFound` - If the plugin is not available.
+    pub async fn get_action_prompt(&self) -> Result<Option<ActionPrompt>, InformationRequestError> {
+        let url = self.endpoint("plugin/action_command_prompt");
+
+        let prompt: RawActionPrompt = self.request_json(self.client.get(&url)).await?;
+        Ok(prompt.to_prompt())
+    }
+
+    /// Answers the currently active action command prompt.
+    ///
+    /// # Arguments
+    ///
+    /// `choice` - The index of the choice in [`ActionPrompt::choices`](types::ActionPrompt) to
+    /// select. Get the current prompt with [`get_action_prompt()`](#method.get_action_prompt).
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`PromptCommandError`](errors::PromptCommandError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `BadRequest` - If the server responds with a `400` StatusCode. This means the choice is
+    ///   not valid for the current prompt.
+    /// * `Conflict` - If the server responds with a `409` StatusCode. This means there is no
+    ///   active prompt.
+    pub async fn select_prompt_choice(&self, choice: usize) -> Result<(), PromptCommandError> {
+        let url = self.endpoint("plugin/action_command_prompt");
+
+        self.send(
+            self.client
+                .post(&url)
+                .json(&ActionPromptCommand::select(choice)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    //
+    //  NOTE: LANGUAGE PACKS
+    //
+
+    /// Gets all installed language packs.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`InformationRequestError`](errors::InformationRequestError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `ParseError` - If the response can not be parsed.
+    pub async fn get_language_packs(&self) -> Result<LanguagePacks, InformationRequestError> {
+        let url = self.endpoint("api/languages");
+
+        Ok(self.request_json(self.client.get(&url)).await?)
+    }
+
+    /// Uploads and installs a language pack archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The name of the archive. OctoPrint uses the extension to detect the
+    ///   archive type, so this has to end in `.zip`, `.tar.gz`, `.tgz` or `.tar`.
+    /// * `contents` - The contents of the archive.
+    ///
+    /// # Returns
+    ///
+    /// The installed language packs after the upload.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`LanguagePackError`](errors::LanguagePackError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `ParseError` - If the response can not be parsed.
+    /// * `BadRequest` - If the server responds with a `400` StatusCode. This usually means the
+    ///   archive is invalid.
+    pub async fn upload_language_pack<S: ToString>(
+        &self,
+        file_name: S,
+        contents: Vec<u8>,
+    ) -> Result<LanguagePacks, LanguagePackError> {
+        let url = self.endpoint("api/languages");
+
+        let part = reqwest::multipart::Part::bytes(contents).file_name(file_name.to_string());
+        let form = reqwest::multipart::Form::new().part("file", part);
+
+        Ok(self
+            .request_json(self.client.post(&url).multipart(form))
+            .await?)
+    }
+
+    /// Deletes an installed language pack.
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - The locale of the pack, e.g. `de`.
+    /// * `pack` - The identifier of the component the pack translates. This is `_core` for
+    ///   OctoPrint itself or the plugin identifier.
+    ///
+    /// # Returns
+    ///
+    /// The installed language packs after the deletion.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`LanguagePackError`](errors::LanguagePackError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `ParseError` - If the response can not be parsed.
+    /// * `Unexpected` - With an `InvalidArgument` if `locale` or `pack` is empty, `.` or `..`.
+    ///   Other characters like `/` are percent-encoded.
+    pub async fn delete_language_pack(
+        &self,
+        locale: &str,
+        pack: &str,
+    ) -> Result<LanguagePacks, LanguagePackError> {
+        if let Some(segment) = [locale, pack]
+            .into_iter()
+            .find(|segment| matches!(*segment, "" | "." | ".."))
+        {
+            return Err(OctoPrintError::InvalidArgument(format!(
+                "`{}` is not a valid locale or pack",
+                segment
+            ))
+            .into());
         }
+
+        let mut url = reqwest::Url::parse(&self.endpoint("api/languages"))
+            .map_err(|e| OctoPrintError::InvalidArgument(e.to_string()))?;
+        url.path_segments_mut()
+            .expect("http urls have a path")
+            .push(locale)
+            .push(pack);
+
+        Ok(self.request_json(self.client.delete(url)).await?)
     }
 
-    pub async fn get_bed_state(&self, history: Option<u32>) -> Result<BedState, DeviceStateError> {
-        let query: String;
-        if let Some(x) = history {
-            query = format!("true&limit={}", x);
-        } else {
-            query = "false".to_string();
+    //
+    //  NOTE: WEBCAM
+    //
+
+    /// Resolves the snapshot url of a webcam, see [`snapshot()`](#method.snapshot).
+    fn snapshot_endpoint(&self, snapshot_url: &str) -> String {
+        let Ok(base) = reqwest::Url::parse(&self.base_url) else {
+            return self.endpoint(snapshot_url);
+        };
+        let Ok(url) = base.join(snapshot_url) else {
+            return self.endpoint(snapshot_url);
+        };
+
+        let host = url.host_str().unwrap_or_default();
+        let local = host.eq_ignore_ascii_case("localhost")
+            || host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback() || ip.is_unspecified());
+        if !local || url.host_str() == base.host_str() {
+            return url.to_string();
         }
 
-        let url = format!(
-            "http://{}:{}/api/printer?history={}",
-            &self.address, &self.port, query
-        );
+        let mut proxied = base;
+        proxied.set_path(&format!("/webcam{}", url.path()));
+        proxied.set_query(url.query());
+        proxied.to_string()
+    }
 
-        let res = self
-            .client
-            .get(&url)
-            .header("X-Api-Key", &self.api_key)
-            .send()
-            .await
-            .map_err(DeviceStateError::ReqwestError)?;
+    /// Gets the webcams configured on the server. This requires OctoPrint 1.9 or newer.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`InformationRequestError`](errors::InformationRequestError) enum.
+    /// * `ReqwestError` - If the request fails.
+    /// * `ParseError` - If the response can not be parsed.
+    /// * `NotFound` - If the server is older than 1.9 and does not support the endpoint.
+    pub async fn get_webcams(&self) -> Result<Webcams, InformationRequestError> {
+        let url = self.endpoint("api/webcams");
 
-        let status = res.status();
-        let text = res.text().await.map_err(DeviceStateError::ReqwestError)?;
-
-        match status {
-            StatusCode::CONFLICT => Err(DeviceStateError::Conflict(text)),
-            status if status.is_success() => {
-                let result = &mut serde_json::Deserializer::from_str(text.as_str());
-                let deserialized = serde_path_to_error::deserialize(result)
-                    .map_err(|e| DeviceStateError::ParseError(e.to_string()))?;
-                Ok(deserialized)
+        Ok(self.request_json(self.client.get(&url)).await?)
+    }
+
+    /// Takes a snapshot with the first webcam that supports snapshots.
+    ///
+    /// The webcam is looked up with [`get_webcams()`](#method.get_webcams). If the server is older
+    /// than 1.9, the webcam settings from `/api/settings` are used instead. Relative snapshot
+    /// urls are resolved against the base url of the printer, root-relative ones like
+    /// `/webcam/?action=snapshot` against its origin, and the snapshot is fetched with the same
+    /// client. The api key is only sent along if the snapshot url has the same scheme, host
+    /// and port as the base url.
+    ///
+    /// A snapshot url on a loopback or unspecified host, like OctoPi's default
+    /// `http://127.0.0.1:8080/?action=snapshot`, only works on the printer itself. Unless the
+    /// printer is addressed by that same host, the snapshot is fetched through the `/webcam/`
+    /// proxy of the printer instead, e.g. `http://octopi.local/webcam/?action=snapshot`.
+    ///
+    /// # Returns
+    ///
+    /// A [`Snapshot`](types::Snapshot) containing the raw image and the configured orientation.
+    ///
+    /// # Errors
+    ///
+    /// If there is an error, it will return a [`SnapshotError`](errors::SnapshotError) enum.
+    /// * `ReqwestError` - If a request fails.
+    /// * `ParseError` - If the webcam settings can not be parsed.
+    /// * `NoSnapshotUrl` - If there is no webcam that supports snapshots.
+    /// * `SnapshotFailed` - If the webcam responds with an error.
+    pub async fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        let (snapshot_url, orientation) = match self.get_webcams().await {
+            Ok(webcams) => webcams
+                .webcams
+                .into_iter()
+                .filter(|webcam| webcam.can_snapshot)
+                .find_map(|webcam| {
+                    let orientation = webcam.orientation();
+                    webcam
+                        .compat
+                        .and_then(|compat| compat.snapshot)
+                        .map(|url| (url, orientation))
+                })
+                .ok_or(SnapshotError::NoSnapshotUrl)?,
+            Err(InformationRequestError::NotFound(_)) => {
+                let webcam = self.get_legacy_webcam_settings().await?;
+                let orientation = webcam.orientation();
+                webcam
+                    .snapshot_url
+                    .filter(|url| !url.is_empty())
+                    .map(|url| (url, orientation))
+                    .ok_or(SnapshotError::NoSnapshotUrl)?
             }
-            _ => unreachable!(),
-        }
+            Err(e) => return Err(OctoPrintError::from(e).into()),
+        };
+
+        let url = self.snapshot_endpoint(&snapshot_url);
+
+        let res = match self.send(self.client.get(&url)).await {
+            Ok(res) => res,
+            Err(OctoPrintError::Status { body, .. }) => {
+                return Err(SnapshotError::SnapshotFailed(body))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let content_type = res
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        let image = res.bytes().await.map_err(SnapshotError::ReqwestError)?;
+
+        Ok(Snapshot {
+            image,
+            content_type,
+            orientation,
+        })
+    }
+
+    async fn get_legacy_webcam_settings(&self) -> Result<LegacyWebcam, SnapshotError> {
+        let url = self.endpoint("api/settings");
+
+        let settings: WebcamSettings = self.request_cached(&url).await?;
+        Ok(settings.webcam)
     }
 }
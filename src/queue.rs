@@ -0,0 +1,634 @@
+//! A print queue that dispatches G-code to the printers of a [`Fleet`].
+//!
+//! Jobs are submitted with the [`Requirements`] a printer has to meet. Every
+//! [`tick`](PrintQueue::tick) the queue checks on the jobs that are being printed and hands the
+//! oldest pending jobs to idle, operational printers whose profile meets their requirements:
+//! the G-code is uploaded to the `queue` folder of the local storage of the printer and
+//! selected for printing.
+//!
+//! The queue keeps its state in a directory, so a restarted queue picks up the jobs it was
+//! printing and the ones still pending.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use octoprint_rs::fleet::Fleet;
+//! use octoprint_rs::queue::{PrintQueue, Requirements};
+//! use octoprint_rs::PrinterBuilder;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), octoprint_rs::errors::QueueError> {
+//! let mut fleet = Fleet::new();
+//! fleet.add("prusa-1", PrinterBuilder::new("10.0.0.11", "API_KEY").build());
+//!
+//! let mut queue = PrintQueue::open("print-queue")?;
+//! let gcode = std::fs::read("benchy.gcode")?;
+//! queue.submit(
+//!     "benchy.gcode",
+//!     gcode,
+//!     Requirements {
+//!         nozzle_diameter: Some(0.4),
+//!         ..Requirements::default()
+//!     },
+//! )?;
+//!
+//! loop {
+//!     for event in queue.tick(&fleet).await? {
+//!         println!("{:?}", event);
+//!     }
+//!     tokio::time::sleep(Duration::from_secs(10)).await;
+//! }
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::OctoPrintApi,
+    errors::{DeviceStateError, OctoPrintError, QueueError},
+    fleet::Fleet,
+    types::{
+        printer_files::GCodeAnalysis, printer_profiles::Profile, Dimension, FileCommand,
+        FileCommandDescriptor, FileLocation, FileUploadDescriptor, PathDescriptor,
+    },
+};
+
+const STATE_FILE: &str = "queue.json";
+const GCODE_FOLDER: &str = "gcode";
+const PRINTER_FOLDER: &str = "queue";
+/// Nozzle diameters closer than this are considered equal, in millimeters.
+const NOZZLE_TOLERANCE: f32 = 0.01;
+
+/// A queue of print jobs, persisted in a directory. See the [module docs](self).
+pub struct PrintQueue {
+    directory: PathBuf,
+    state: QueueState,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct QueueState {
+    next_id: u64,
+    jobs: Vec<QueuedJob>,
+}
+
+/// What a printer has to offer to print a job. Requirements that are `None` are met by every
+/// printer.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Requirements {
+    /// The id of the printer profile the printer has to be connected with.
+    pub printer_profile: Option<String>,
+    /// The nozzle diameter in millimeters.
+    pub nozzle_diameter: Option<f32>,
+    /// The size of the print, which has to fit into the build volume of the printer.
+    pub dimensions: Option<Dimension>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueuedJob {
+    pub id: u64,
+    /// The name the job was submitted with.
+    pub name: String,
+    /// The path of the file in the local storage of the printer. Once the job is dispatched,
+    /// this is the path the printer stored the file at.
+    pub path: String,
+    pub requirements: Requirements,
+    pub status: JobStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum JobStatus {
+    /// Waiting for a printer.
+    Pending,
+    /// Being printed by `printer`. `completion` is the progress in percent when the queue last
+    /// checked on the job.
+    Printing {
+        printer: String,
+        completion: Option<f32>,
+    },
+    Done {
+        printer: String,
+    },
+    Failed {
+        printer: String,
+        reason: String,
+    },
+}
+
+/// What happened during a [`tick`](PrintQueue::tick).
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueueEvent {
+    /// The job was uploaded to the printer and its print started.
+    Dispatched {
+        job: u64,
+        printer: String,
+    },
+    /// The job could not be uploaded or started. It stays pending and is dispatched again with
+    /// the next tick, unless its G-code can not be read. Then the job failed.
+    DispatchFailed {
+        job: u64,
+        printer: String,
+        error: String,
+    },
+    PrintDone {
+        job: u64,
+        printer: String,
+    },
+    PrintFailed {
+        job: u64,
+        printer: String,
+        reason: String,
+    },
+}
+
+impl Requirements {
+    /// Requires a printer large enough for the print analyzed by OctoPrint.
+    pub fn from_analysis(analysis: &GCodeAnalysis) -> Self {
+        Requirements {
+            dimensions: Some(analysis.dimensions.clone()),
+            ..Requirements::default()
+        }
+    }
+
+    /// Whether a printer with `profile` can print the job.
+    pub fn is_met_by(&self, profile: &Profile) -> bool {
+        let profile_matches = self
+            .printer_profile
+            .as_ref()
+            .is_none_or(|id| *id == profile.id);
+        let nozzle_matches = self
+            .nozzle_diameter
+            .is_none_or(|d| (d - profile.extruder.nozzle_diameter).abs() < NOZZLE_TOLERANCE);
+        let fits = self.dimensions.as_ref().is_none_or(|d| {
+            d.width <= profile.volume.width
+                && d.depth <= profile.volume.depth
+                && d.height <= profile.volume.height
+        });
+
+        profile_matches && nozzle_matches && fits
+    }
+}
+
+impl JobStatus {
+    fn printer(&self) -> Option<&str> {
+        match self {
+            JobStatus::Pending => None,
+            JobStatus::Printing { printer, .. }
+            | JobStatus::Done { printer }
+            | JobStatus::Failed { printer, .. } => Some(printer),
+        }
+    }
+}
+
+impl PrintQueue {
+    /// Opens the queue saved in `directory`, or creates an empty one if there is none.
+    ///
+    /// # Errors
+    ///
+    /// * `Io` - If the directory can not be created or the saved state can not be read
+    /// * `Corrupt` - If the saved state can not be parsed
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Self, QueueError> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(directory.join(GCODE_FOLDER))?;
+
+        let state = match fs::read(directory.join(STATE_FILE)) {
+            Ok(data) => {
+                serde_json::from_slice(&data).map_err(|e| QueueError::Corrupt(e.to_string()))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => QueueState::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(PrintQueue { directory, state })
+    }
+
+    /// Adds a job to the end of the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The file name of the job, e.g. `benchy.gcode`
+    /// * `gcode` - The contents of the file
+    /// * `requirements` - What a printer has to offer to print the job
+    ///
+    /// # Returns
+    ///
+    /// The id of the job.
+    ///
+    /// # Errors
+    ///
+    /// * `Io` - If the G-code or the queue can not be saved
+    pub fn submit<S: ToString, C: Into<Vec<u8>>>(
+        &mut self,
+        name: S,
+        gcode: C,
+        requirements: Requirements,
+    ) -> Result<u64, QueueError> {
+        let id = self.state.next_id;
+        let name = name.to_string();
+        let file_name = name.rsplit(['/', '\\']).next().unwrap_or(&name);
+
+        fs::write(self.gcode_path(id), gcode.into())?;
+        self.state.next_id += 1;
+        self.state.jobs.push(QueuedJob {
+            id,
+            path: format!("{}/{}-{}", PRINTER_FOLDER, id, file_name),
+            name,
+            requirements,
+            status: JobStatus::Pending,
+        });
+        self.save()?;
+
+        Ok(id)
+    }
+
+    /// Every job in the queue, oldest first.
+    pub fn jobs(&self) -> &[QueuedJob] {
+        &self.state.jobs
+    }
+
+    pub fn job(&self, id: u64) -> Option<&QueuedJob> {
+        self.state.jobs.iter().find(|job| job.id == id)
+    }
+
+    /// Whether every job is either done or failed.
+    pub fn is_finished(&self) -> bool {
+        self.state.jobs.iter().all(|job| {
+            matches!(
+                job.status,
+                JobStatus::Done { .. } | JobStatus::Failed { .. }
+            )
+        })
+    }
+
+    /// Queues a failed job again.
+    ///
+    /// # Errors
+    ///
+    /// * `UnknownJob` - If there is no job with this id
+    /// * `InvalidState` - If the job did not fail
+    /// * `Io` - If the queue can not be saved
+    pub fn retry(&mut self, id: u64) -> Result<(), QueueError> {
+        let job = self.job_mut(id)?;
+        if !matches!(job.status, JobStatus::Failed { .. }) {
+            return Err(QueueError::InvalidState(format!("job {} did not fail", id)));
+        }
+
+        job.status = JobStatus::Pending;
+        self.save()
+    }
+
+    /// Removes a job that is not being printed from the queue.
+    ///
+    /// # Errors
+    ///
+    /// * `UnknownJob` - If there is no job with this id
+    /// * `InvalidState` - If the job is being printed
+    /// * `Io` - If the queue can not be saved
+    pub fn remove(&mut self, id: u64) -> Result<QueuedJob, QueueError> {
+        let index = self
+            .state
+            .jobs
+            .iter()
+            .position(|job| job.id == id)
+            .ok_or(QueueError::UnknownJob(id))?;
+        if let JobStatus::Printing { printer, .. } = &self.state.jobs[index].status {
+            return Err(QueueError::InvalidState(format!(
+                "job {} is being printed by {}",
+                id, printer
+            )));
+        }
+
+        let job = self.state.jobs.remove(index);
+        self.remove_gcode(id)?;
+        self.save()?;
+        Ok(job)
+    }
+
+    /// Checks on the jobs being printed, then dispatches pending jobs to idle printers.
+    ///
+    /// A print is done once its printer finished it at 100%. It failed if the printer stopped
+    /// it earlier, reports an error or is no longer connected. Printers that can not be reached
+    /// are checked on again with the next tick.
+    ///
+    /// A pending job is dispatched to the first printer by name that is operational, not
+    /// printing, not busy with another job of the queue and whose current printer profile
+    /// meets the requirements of the job.
+    ///
+    /// # Returns
+    ///
+    /// What happened, in order.
+    ///
+    /// # Errors
+    ///
+    /// * `Io` - If the queue can not be saved. A job whose G-code can not be read fails instead.
+    pub async fn tick<P: OctoPrintApi + 'static>(
+        &mut self,
+        fleet: &Fleet<P>,
+    ) -> Result<Vec<QueueEvent>, QueueError> {
+        let mut events = vec![];
+        let mut changed = self.monitor(fleet, &mut events).await;
+        changed |= self.dispatch(fleet, &mut events).await;
+
+        if changed {
+            self.save()?;
+        }
+        Ok(events)
+    }
+
+    /// Runs [`tick`](#method.tick) every `interval` until every job is done or failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of a tick.
+    pub async fn run<P: OctoPrintApi + 'static>(
+        &mut self,
+        fleet: &Fleet<P>,
+        interval: Duration,
+    ) -> Result<(), QueueError> {
+        loop {
+            self.tick(fleet).await?;
+            if self.is_finished() {
+                return Ok(());
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn monitor<P: OctoPrintApi + 'static>(
+        &mut self,
+        fleet: &Fleet<P>,
+        events: &mut Vec<QueueEvent>,
+    ) -> bool {
+        let mut changed = false;
+        for index in 0..self.state.jobs.len() {
+            let job = &self.state.jobs[index];
+            let JobStatus::Printing {
+                printer: name,
+                completion: last_completion,
+            } = &job.status
+            else {
+                continue;
+            };
+            let Some(printer) = fleet.get(name) else {
+                continue;
+            };
+
+            let (id, printer_name) = (job.id, name.clone());
+            let status = match check(printer.as_ref(), &job.path).await {
+                None => continue,
+                Some(Check::Printing(completion)) if completion == *last_completion => continue,
+                Some(Check::Printing(completion)) => JobStatus::Printing {
+                    printer: printer_name,
+                    completion,
+                },
+                Some(Check::Done) => {
+                    // The print is done either way, a left over file only takes up space
+                    let _ = self.remove_gcode(id);
+                    events.push(QueueEvent::PrintDone {
+                        job: id,
+                        printer: printer_name.clone(),
+                    });
+                    JobStatus::Done {
+                        printer: printer_name,
+                    }
+                }
+                Some(Check::Failed(reason)) => {
+                    events.push(QueueEvent::PrintFailed {
+                        job: id,
+                        printer: printer_name.clone(),
+                        reason: reason.clone(),
+                    });
+                    JobStatus::Failed {
+                        printer: printer_name,
+                        reason,
+                    }
+                }
+            };
+
+            self.state.jobs[index].status = status;
+            changed = true;
+        }
+        changed
+    }
+
+    async fn dispatch<P: OctoPrintApi + 'static>(
+        &mut self,
+        fleet: &Fleet<P>,
+        events: &mut Vec<QueueEvent>,
+    ) -> bool {
+        if !self
+            .state
+            .jobs
+            .iter()
+            .any(|job| job.status == JobStatus::Pending)
+        {
+            return false;
+        }
+
+        let busy: HashSet<String> = self
+            .state
+            .jobs
+            .iter()
+            .filter(|job| matches!(job.status, JobStatus::Printing { .. }))
+            .filter_map(|job| job.status.printer().map(str::to_string))
+            .collect();
+        let mut idle: BTreeMap<String, Profile> = fleet
+            .run(|printer| async move { idle_profile(printer.as_ref()).await })
+            .await
+            .into_iter()
+            .filter(|(name, _)| !busy.contains(name))
+            .filter_map(|(name, profile)| Some((name, profile.ok()??)))
+            .collect();
+
+        let mut changed = false;
+        for index in 0..self.state.jobs.len() {
+            let job = &self.state.jobs[index];
+            if job.status != JobStatus::Pending {
+                continue;
+            }
+            let Some(name) = idle
+                .iter()
+                .find(|(_, profile)| job.requirements.is_met_by(profile))
+                .map(|(name, _)| name.clone())
+            else {
+                continue;
+            };
+            let Some(profile) = idle.remove(&name) else {
+                continue;
+            };
+            let Some(printer) = fleet.get(&name) else {
+                continue;
+            };
+
+            let gcode = match fs::read(self.gcode_path(job.id)) {
+                Ok(gcode) => gcode,
+                Err(e) => {
+                    // Retrying will not bring the file back, so the job failed and the printer
+                    // is free for the next one
+                    let reason = format!("the G-code can not be read: {}", e);
+                    events.push(QueueEvent::DispatchFailed {
+                        job: job.id,
+                        printer: name.clone(),
+                        error: reason.clone(),
+                    });
+                    self.state.jobs[index].status = JobStatus::Failed {
+                        printer: name.clone(),
+                        reason,
+                    };
+                    idle.insert(name, profile);
+                    changed = true;
+                    continue;
+                }
+            };
+            match start(printer.as_ref(), &job.path, gcode).await {
+                Ok(path) => {
+                    events.push(QueueEvent::Dispatched {
+                        job: job.id,
+                        printer: name.clone(),
+                    });
+                    self.state.jobs[index].path = path;
+                    self.state.jobs[index].status = JobStatus::Printing {
+                        printer: name,
+                        completion: None,
+                    };
+                    changed = true;
+                }
+                Err(e) => events.push(QueueEvent::DispatchFailed {
+                    job: job.id,
+                    printer: name,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        changed
+    }
+
+    fn job_mut(&mut self, id: u64) -> Result<&mut QueuedJob, QueueError> {
+        self.state
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or(QueueError::UnknownJob(id))
+    }
+
+    fn gcode_path(&self, id: u64) -> PathBuf {
+        self.directory
+            .join(GCODE_FOLDER)
+            .join(format!("{}.gcode", id))
+    }
+
+    fn remove_gcode(&self, id: u64) -> Result<(), QueueError> {
+        match fs::remove_file(self.gcode_path(id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the state to a temporary file first, so a crash never leaves a half written
+    /// state behind.
+    fn save(&self) -> Result<(), QueueError> {
+        let data = serde_json::to_vec_pretty(&self.state)
+            .map_err(|e| QueueError::Corrupt(e.to_string()))?;
+        let temporary = self.directory.join(format!("{}.tmp", STATE_FILE));
+        fs::write(&temporary, data)?;
+        fs::rename(temporary, self.directory.join(STATE_FILE))?;
+        Ok(())
+    }
+}
+
+/// How a print is doing, see [`check`].
+enum Check {
+    Printing(Option<f32>),
+    Done,
+    Failed(String),
+}
+
+/// Checks on the print of `path`. Returns `None` if the printer can not be reached.
+async fn check<P: OctoPrintApi>(printer: &P, path: &str) -> Option<Check> {
+    let state = match printer.get_printer_telemetry().await {
+        Ok(state) => state,
+        Err(DeviceStateError::Conflict(_)) => {
+            return Some(Check::Failed("the printer is not operational".to_string()))
+        }
+        Err(_) => return None,
+    };
+    let job = printer.get_job().await.ok()?;
+
+    let ours = job.job.file.path.as_deref() == Some(path);
+    let completion = job.progress.completion;
+    let flags = &state.state.flags;
+    if flags.printing || flags.paused || flags.pausing || flags.cancelling {
+        return Some(if ours {
+            Check::Printing(completion)
+        } else {
+            Check::Failed("the printer is printing another file".to_string())
+        });
+    }
+
+    let reason = if ours && completion.is_some_and(|c| c >= 100.0) {
+        return Some(Check::Done);
+    } else if flags.error || flags.closed_or_error {
+        format!("the printer reports an error: {}", state.state.text)
+    } else if let (true, Some(completion)) = (ours, completion) {
+        format!("the print stopped at {:.1}%", completion)
+    } else {
+        "the file is no longer selected".to_string()
+    };
+    Some(Check::Failed(reason))
+}
+
+/// The current profile of the printer if it is idle and operational.
+async fn idle_profile<P: OctoPrintApi>(printer: &P) -> Result<Option<Profile>, OctoPrintError> {
+    let state = printer.get_printer_telemetry().await?;
+    let flags = &state.state.flags;
+    if !flags.operational || !flags.ready || flags.printing || flags.paused || flags.error {
+        return Ok(None);
+    }
+
+    let profiles = printer.get_printer_profiles().await?;
+    Ok(profiles.current().cloned())
+}
+
+/// Uploads the G-code to `path` and starts printing it. Returns the path the printer stored
+/// the file at, which differs from `path` if OctoPrint sanitized the file name.
+async fn start<P: OctoPrintApi>(
+    printer: &P,
+    path: &str,
+    gcode: Vec<u8>,
+) -> Result<String, OctoPrintError> {
+    let local = |path: &str| PathDescriptor {
+        location: FileLocation::Local,
+        path: path.to_string(),
+    };
+
+    let mut upload = printer
+        .upload_file(FileUploadDescriptor {
+            path: local(path),
+            contents: gcode,
+            select: false,
+            print: false,
+        })
+        .await?;
+    let path = upload
+        .files
+        .remove("local")
+        .and_then(|file| file.path)
+        .unwrap_or_else(|| path.to_string());
+
+    printer
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Select { print: true },
+            path: local(&path),
+        })
+        .await?;
+    Ok(path)
+}
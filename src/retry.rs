@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{Method, StatusCode};
+
+/// Describes if and how failed requests are retried.
+///
+/// Only requests that failed to connect, timed out or got a `502`, `503` or `504` response are
+/// retried. Between the attempts the printer waits with an exponential backoff, starting at
+/// `initial_backoff` and doubling every attempt up to `max_backoff`. Every delay is randomly
+/// shortened by up to half so many clients don't retry at the same time.
+///
+/// By default only `GET` and `HEAD` requests are retried. Commands like starting a job or moving
+/// the printhead are not idempotent, so they are only retried if `retry_commands` is enabled.
+///
+/// # Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use octoprint_rs::PrinterBuilder;
+/// # use octoprint_rs::retry::RetryPolicy;
+/// let printer = PrinterBuilder::new("localhost", "API_KEY")
+///     .retry_policy(RetryPolicy {
+///         max_retries: 3,
+///         initial_backoff: Duration::from_millis(200),
+///         ..RetryPolicy::default()
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub retry_commands: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Never retries.
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            retry_commands: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a request with the given method may be retried at all.
+    pub(crate) fn allows(&self, method: &Method) -> bool {
+        self.retry_commands || *method == Method::GET || *method == Method::HEAD
+    }
+
+    /// Whether the result of an attempt is worth retrying.
+    pub(crate) fn should_retry(&self, result: &Result<reqwest::Response, reqwest::Error>) -> bool {
+        match result {
+            Ok(res) => matches!(
+                res.status(),
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            Err(e) => e.is_connect() || e.is_timeout(),
+        }
+    }
+
+    /// The jittered delay before retry number `attempt`, starting at `0`.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
@@ -0,0 +1,260 @@
+//! A small G-code interpreter for the virtual printer.
+//!
+//! A file is turned into a [`Program`] of timed lines up front. Only what the virtual printer
+//! reacts to is understood: moves with their feedrate, homing, dwells, tool changes and the
+//! `M104`/`M109`/`M140`/`M190` temperature commands. Everything else takes no time.
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+const DEFAULT_FEEDRATE: f64 = 3000.0;
+const FILAMENT_DIAMETER: f64 = 1.75;
+
+/// A temperature command of a line.
+#[derive(Clone)]
+pub(crate) struct Heat {
+    /// `tool{n}` or `bed`.
+    pub heater: String,
+    pub target: f32,
+    /// Whether the print waits until the heater reached the target, like `M109` and `M190`.
+    pub wait: bool,
+}
+
+/// A single line of the file and what it does.
+#[derive(Clone)]
+pub(crate) struct Line {
+    /// The byte offset right after the line.
+    pub end: u64,
+    /// The seconds the line takes to execute, not counting heating.
+    pub duration: f64,
+    /// The estimated seconds from the start of the print until the line is done.
+    pub done_at: f64,
+    /// The old and the new Z height, if the line changes it.
+    pub z_change: Option<(f32, f32)>,
+    pub heat: Option<Heat>,
+}
+
+#[derive(Default)]
+pub(crate) struct Program {
+    pub lines: Vec<Line>,
+    /// The estimated print time in seconds, not counting heating.
+    pub total: f64,
+    printing_area: Option<[f64; 6]>,
+    filament: BTreeMap<usize, f64>,
+}
+
+struct Machine {
+    position: [f64; 4],
+    absolute: bool,
+    absolute_extrusion: bool,
+    feedrate: f64,
+    tool: usize,
+}
+
+impl Program {
+    pub(crate) fn parse(data: &[u8]) -> Self {
+        let mut program = Program::default();
+        let mut machine = Machine {
+            position: [0.0; 4],
+            absolute: true,
+            absolute_extrusion: true,
+            feedrate: DEFAULT_FEEDRATE,
+            tool: 0,
+        };
+
+        let mut offset = 0;
+        for raw in data.split_inclusive(|b| *b == b'\n') {
+            offset += raw.len() as u64;
+
+            let text = String::from_utf8_lossy(raw);
+            let code = text
+                .split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_ascii_uppercase();
+            let mut words = code.split_whitespace();
+            let command = words.next().unwrap_or("");
+            let params: BTreeMap<char, f64> = words
+                .filter_map(|word| {
+                    let mut chars = word.chars();
+                    let letter = chars.next()?;
+                    Some((letter, chars.as_str().parse().ok()?))
+                })
+                .collect();
+
+            let old_z = machine.position[2];
+            let (duration, heat) = program.execute(&mut machine, command, &params);
+            let new_z = machine.position[2];
+
+            program.total += duration;
+            program.lines.push(Line {
+                end: offset,
+                duration,
+                done_at: program.total,
+                z_change: (old_z != new_z).then_some((old_z as f32, new_z as f32)),
+                heat,
+            });
+        }
+        program
+    }
+
+    /// Runs a single command on the machine, returning how long it takes.
+    fn execute(
+        &mut self,
+        machine: &mut Machine,
+        command: &str,
+        params: &BTreeMap<char, f64>,
+    ) -> (f64, Option<Heat>) {
+        let tool = |machine: &Machine| {
+            let index = params.get(&'T').map_or(machine.tool, |t| *t as usize);
+            format!("tool{}", index)
+        };
+        let target = || {
+            params
+                .get(&'S')
+                .or(params.get(&'R'))
+                .copied()
+                .unwrap_or(0.0) as f32
+        };
+
+        match command {
+            "G0" | "G1" => return (self.travel(machine, params), None),
+            "G4" => {
+                let seconds = params.get(&'S').copied().unwrap_or(0.0);
+                let millis = params.get(&'P').copied().unwrap_or(0.0);
+                return (seconds + millis / 1000.0, None);
+            }
+            "G28" => {
+                let all = !['X', 'Y', 'Z'].iter().any(|axis| params.contains_key(axis));
+                for (index, axis) in ['X', 'Y', 'Z'].iter().enumerate() {
+                    if all || params.contains_key(axis) {
+                        machine.position[index] = 0.0;
+                    }
+                }
+            }
+            "G90" => machine.absolute = true,
+            "G91" => machine.absolute = false,
+            "M82" => machine.absolute_extrusion = true,
+            "M83" => machine.absolute_extrusion = false,
+            "G92" => {
+                for (index, axis) in ['X', 'Y', 'Z', 'E'].iter().enumerate() {
+                    if let Some(value) = params.get(axis) {
+                        machine.position[index] = *value;
+                    }
+                }
+            }
+            "M104" | "M109" => {
+                let heat = Heat {
+                    heater: tool(machine),
+                    target: target(),
+                    wait: command == "M109",
+                };
+                return (0.0, Some(heat));
+            }
+            "M140" | "M190" => {
+                let heat = Heat {
+                    heater: "bed".to_string(),
+                    target: target(),
+                    wait: command == "M190",
+                };
+                return (0.0, Some(heat));
+            }
+            _ => {
+                if let Some(index) = command.strip_prefix('T').and_then(|t| t.parse().ok()) {
+                    machine.tool = index;
+                }
+            }
+        }
+        (0.0, None)
+    }
+
+    /// Moves the machine, tracking the printed area and the used filament.
+    fn travel(&mut self, machine: &mut Machine, params: &BTreeMap<char, f64>) -> f64 {
+        if let Some(feedrate) = params.get(&'F').filter(|f| **f > 0.0) {
+            machine.feedrate = *feedrate;
+        }
+
+        let start = machine.position;
+        for (index, axis) in ['X', 'Y', 'Z', 'E'].iter().enumerate() {
+            let Some(value) = params.get(axis) else {
+                continue;
+            };
+            let absolute = if index == 3 {
+                machine.absolute_extrusion
+            } else {
+                machine.absolute
+            };
+            machine.position[index] = if absolute {
+                *value
+            } else {
+                start[index] + value
+            };
+        }
+
+        let end = machine.position;
+        let extruded = end[3] - start[3];
+        let distance = (0..3)
+            .map(|i| (end[i] - start[i]).powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        *self.filament.entry(machine.tool).or_default() += extruded;
+        if extruded > 0.0 && distance > 0.0 {
+            for point in [start, end] {
+                let area = self
+                    .printing_area
+                    .get_or_insert([point[0], point[1], point[2], point[0], point[1], point[2]]);
+                for i in 0..3 {
+                    area[i] = area[i].min(point[i]);
+                    area[i + 3] = area[i + 3].max(point[i]);
+                }
+            }
+        }
+
+        let length = if distance > 0.0 {
+            distance
+        } else {
+            extruded.abs()
+        };
+        length / (machine.feedrate / 60.0)
+    }
+
+    /// The `gcodeAnalysis` OctoPrint reports for a file.
+    pub(crate) fn analysis(&self) -> Value {
+        let [min_x, min_y, min_z, max_x, max_y, max_z] = self.printing_area.unwrap_or_default();
+        let cross_section = std::f64::consts::PI * (FILAMENT_DIAMETER / 2.0).powi(2);
+
+        let filament: BTreeMap<String, Value> = self
+            .filament
+            .iter()
+            .map(|(tool, length)| {
+                let length = length.max(0.0);
+                let volume = length * cross_section / 1000.0;
+                (
+                    format!("tool{}", tool),
+                    json!({ "length": length, "volume": volume }),
+                )
+            })
+            .collect();
+
+        json!({
+            "dimensions": {
+                "width": max_x - min_x,
+                "depth": max_y - min_y,
+                "height": max_z - min_z,
+            },
+            "estimatedPrintTime": self.total,
+            "printingArea": {
+                "minX": min_x,
+                "minY": min_y,
+                "minZ": min_z,
+                "maxX": max_x,
+                "maxY": max_y,
+                "maxZ": max_z,
+            },
+            "filament": filament,
+        })
+    }
+}
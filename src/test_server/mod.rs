@@ -0,0 +1,307 @@
+//! An in-process OctoPrint emulator for integration tests, enabled by the `test-server` feature.
+//!
+//! [`TestServer`] starts a local HTTP server that keeps its state between requests: the
+//! connection can be closed and opened again, files can be uploaded, moved, copied and deleted,
+//! a selected file is printed with a simulated progress and heaters ramp towards their targets.
+//! Every api request has to carry the api key of the server.
+//!
+//! # Example
+//!
+//! ```
+//! use octoprint_rs::test_server::TestServer;
+//! use octoprint_rs::types::JobCommand;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let server = TestServer::start();
+//! server.add_file("benchy.gcode", "G28\nG1 X10 Y10\n");
+//!
+//! let printer = server.printer();
+//! assert!(printer.issue_job_command(JobCommand::Start).await.is_err());
+//! # }
+//! ```
+
+mod gcode;
+mod multipart;
+mod state;
+
+use std::{
+    convert::Infallible,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex, MutexGuard},
+    thread::JoinHandle,
+    time::Instant,
+};
+
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use tokio::sync::oneshot;
+
+use crate::{types::Event, Printer, PrinterBuilder};
+use state::{Emulator, Origin, Reply, Settings};
+
+/// Builds a [`TestServer`].
+pub struct TestServerBuilder {
+    api_key: String,
+    settings: Settings,
+}
+
+/// A running OctoPrint emulator. The server is shut down when this is dropped.
+pub struct TestServer {
+    address: SocketAddr,
+    api_key: String,
+    emulator: Arc<Mutex<Emulator>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for TestServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestServerBuilder {
+    /// A server with one tool at 21°C that heats at 10°C per second and prints 1 kB per second.
+    pub fn new() -> Self {
+        TestServerBuilder {
+            api_key: "TEST_API_KEY".to_string(),
+            settings: Settings {
+                tools: 1,
+                ambient: 21.0,
+                heating_rate: 10.0,
+                print_speed: 1000,
+                simulate_gcode: false,
+                time_factor: 1.0,
+            },
+        }
+    }
+
+    /// The api key the server expects in the `X-Api-Key` header.
+    pub fn api_key<S: ToString>(mut self, api_key: S) -> Self {
+        self.api_key = api_key.to_string();
+        self
+    }
+
+    /// The number of tools, named `tool0` to `tool{count - 1}`.
+    pub fn tools(mut self, count: usize) -> Self {
+        self.settings.tools = count;
+        self
+    }
+
+    /// The temperature heaters start at and cool down to.
+    pub fn ambient_temperature(mut self, temperature: f32) -> Self {
+        self.settings.ambient = temperature;
+        self
+    }
+
+    /// How many degrees per second heaters move towards their target.
+    pub fn heating_rate(mut self, degrees_per_second: f32) -> Self {
+        self.settings.heating_rate = degrees_per_second;
+        self
+    }
+
+    /// How many bytes of the selected file are printed per second.
+    pub fn print_speed(mut self, bytes_per_second: u64) -> Self {
+        self.settings.print_speed = bytes_per_second;
+        self
+    }
+
+    /// Drives prints by simulating the G-code of the file instead of printing at a fixed
+    /// `print_speed`, turning the server into a virtual printer.
+    ///
+    /// Every line advances `filepos` and takes as long as its moves at their feedrate.
+    /// `M104` and `M140` set the target of a tool or the bed, `M109` and `M190` additionally
+    /// hold the print until the heater reached its target. Every change of the Z height emits
+    /// a `ZChange` event, see [`TestServer::events`].
+    pub fn simulate_gcode(mut self, simulate: bool) -> Self {
+        self.settings.simulate_gcode = simulate;
+        self
+    }
+
+    /// How many simulated seconds pass per real second, to run long prints in a test. Applies
+    /// to the progress of prints and to heating.
+    pub fn time_factor(mut self, factor: f64) -> Self {
+        self.settings.time_factor = factor;
+        self
+    }
+
+    /// Starts the server on a random local port, on a thread of its own.
+    ///
+    /// # Panics
+    ///
+    /// If no local port can be bound or the runtime of the server can not be started.
+    pub fn start(self) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind the test server");
+        listener
+            .set_nonblocking(true)
+            .expect("failed to bind the test server");
+        let address = listener
+            .local_addr()
+            .expect("failed to bind the test server");
+
+        let base_url = format!("http://{}/", address);
+        let emulator = Arc::new(Mutex::new(Emulator::new(self.settings, base_url)));
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+
+        let service_emulator = emulator.clone();
+        let api_key: Arc<str> = self.api_key.clone().into();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime of the test server");
+
+        let thread = std::thread::spawn(move || {
+            runtime.block_on(async move {
+                let make_service = make_service_fn(move |_| {
+                    let emulator = service_emulator.clone();
+                    let api_key = api_key.clone();
+                    async move {
+                        Ok::<_, Infallible>(service_fn(move |request| {
+                            handle(emulator.clone(), api_key.clone(), request)
+                        }))
+                    }
+                });
+
+                let server = Server::from_tcp(listener)
+                    .expect("failed to start the test server")
+                    .serve(make_service)
+                    .with_graceful_shutdown(async {
+                        let _ = shutdown_signal.await;
+                    });
+                let _ = server.await;
+            })
+        });
+
+        TestServer {
+            address,
+            api_key: self.api_key,
+            emulator,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl TestServer {
+    /// Starts a server with the default settings, see [`TestServerBuilder::new`].
+    pub fn start() -> Self {
+        TestServerBuilder::new().start()
+    }
+
+    pub fn address(&self) -> String {
+        self.address.ip().to_string()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.address.port()
+    }
+
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    /// The base url of the server, e.g. `http://127.0.0.1:12345/`.
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.address)
+    }
+
+    /// A `PrinterBuilder` pointing at this server, to add further options.
+    pub fn printer_builder(&self) -> PrinterBuilder {
+        PrinterBuilder::new(self.address(), &self.api_key).port(self.port())
+    }
+
+    /// A `Printer` pointing at this server.
+    pub fn printer(&self) -> Printer {
+        self.printer_builder().build()
+    }
+
+    /// Stores a file in the local storage of the printer, like an upload would.
+    pub fn add_file<C: Into<Vec<u8>>>(&self, path: &str, contents: C) {
+        self.emulator().store(Origin::Local, path, contents.into());
+    }
+
+    /// Stores a file on the SD card of the printer.
+    pub fn add_sd_file<C: Into<Vec<u8>>>(&self, path: &str, contents: C) {
+        self.emulator().store(Origin::Sdcard, path, contents.into());
+    }
+
+    /// Every event the printer emitted so far, oldest first. Besides `ZChange` these are
+    /// `PrintStarted`, `PrintPaused`, `PrintResumed`, `PrintCancelled`, `PrintFailed`,
+    /// `PrintDone`, `Connected` and `Disconnected`.
+    pub fn events(&self) -> Vec<Event> {
+        self.emulator().events()
+    }
+
+    fn emulator(&self) -> MutexGuard<'_, Emulator> {
+        let mut emulator = lock(&self.emulator);
+        emulator.tick(Instant::now());
+        emulator
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn lock(emulator: &Mutex<Emulator>) -> MutexGuard<'_, Emulator> {
+    // A panic while handling a request must not take down every following request
+    emulator.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+async fn handle(
+    emulator: Arc<Mutex<Emulator>>,
+    api_key: Arc<str>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let (parts, body) = request.into_parts();
+
+    let authorized = parts
+        .headers
+        .get("X-Api-Key")
+        .is_some_and(|key| key.as_bytes() == api_key.as_bytes());
+
+    let reply = match hyper::body::to_bytes(body).await {
+        Err(_) => Reply::error(hyper::StatusCode::BAD_REQUEST, "Could not read the body"),
+        Ok(_) if !authorized => Reply::error(hyper::StatusCode::FORBIDDEN, "Invalid API key"),
+        Ok(body) => {
+            let content_type = parts
+                .headers
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok());
+
+            let mut emulator = lock(&emulator);
+            emulator.tick(Instant::now());
+            emulator.handle(
+                &parts.method,
+                parts.uri.path(),
+                parts.uri.query().unwrap_or(""),
+                content_type,
+                &body,
+            )
+        }
+    };
+
+    let response = Response::builder().status(reply.status);
+    let response = match (reply.body, reply.error) {
+        (Some(body), _) => response
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        (None, Some(error)) => response
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from(error)),
+        (None, None) => response.body(Body::empty()),
+    };
+
+    Ok(response.expect("the response is always valid"))
+}
@@ -0,0 +1,1146 @@
+//! The state machine behind the emulator. Every request is answered synchronously from here,
+//! after the simulation was advanced to the current time.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use hyper::{Method, StatusCode};
+use serde_json::{json, Map, Value};
+
+use super::{gcode::Program, multipart};
+use crate::types::Event;
+
+/// The number of temperature samples kept for the `history` of the temperature endpoints.
+const HISTORY_LENGTH: usize = 300;
+const PORT: &str = "VIRTUAL";
+const BAUDRATE: u64 = 115200;
+const TOTAL_SPACE: u64 = 16_000_000_000;
+
+/// The knobs of the simulation, set through the `TestServerBuilder`.
+#[derive(Clone)]
+pub(crate) struct Settings {
+    pub tools: usize,
+    pub ambient: f32,
+    /// Degrees per second a heater moves towards its target.
+    pub heating_rate: f32,
+    /// Bytes of the selected file that are printed per second, unless G-code is simulated.
+    pub print_speed: u64,
+    /// Whether prints are driven by simulating the G-code of the file.
+    pub simulate_gcode: bool,
+    /// How many simulated seconds pass per real second.
+    pub time_factor: f64,
+}
+
+/// The response to a request.
+pub(crate) struct Reply {
+    pub status: StatusCode,
+    pub body: Option<Value>,
+    pub error: Option<String>,
+}
+
+impl Reply {
+    fn json(body: Value) -> Self {
+        Reply {
+            status: StatusCode::OK,
+            body: Some(body),
+            error: None,
+        }
+    }
+
+    fn no_content() -> Self {
+        Reply {
+            status: StatusCode::NO_CONTENT,
+            body: None,
+            error: None,
+        }
+    }
+
+    pub(crate) fn error(status: StatusCode, message: &str) -> Self {
+        Reply {
+            status,
+            body: None,
+            error: Some(message.to_string()),
+        }
+    }
+}
+
+type Result<T = Reply> = std::result::Result<T, Reply>;
+
+fn bad_request(message: &str) -> Reply {
+    Reply::error(StatusCode::BAD_REQUEST, message)
+}
+
+fn conflict(message: &str) -> Reply {
+    Reply::error(StatusCode::CONFLICT, message)
+}
+
+fn not_found() -> Reply {
+    Reply::error(StatusCode::NOT_FOUND, "Not found")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Origin {
+    Local,
+    Sdcard,
+}
+
+impl Origin {
+    pub(crate) fn parse(origin: &str) -> Option<Self> {
+        match origin {
+            "local" => Some(Origin::Local),
+            "sdcard" => Some(Origin::Sdcard),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Origin::Local => "local",
+            Origin::Sdcard => "sdcard",
+        }
+    }
+}
+
+struct StoredFile {
+    data: Vec<u8>,
+    date: u64,
+    /// The `gcodeAnalysis` of G-code files.
+    analysis: Option<Value>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum JobState {
+    Idle,
+    Printing,
+    Paused,
+}
+
+/// The time of a temperature sample and the actual and target temperature of every heater.
+type Sample = (u64, Vec<(String, f32, f32)>);
+
+struct Heater {
+    actual: f32,
+    target: f32,
+    offset: f32,
+}
+
+pub(crate) struct Emulator {
+    settings: Settings,
+    base_url: String,
+    connected: bool,
+    files: BTreeMap<(Origin, String), StoredFile>,
+    selected: Option<(Origin, String)>,
+    job: JobState,
+    filepos: f64,
+    /// Simulated seconds since the print started.
+    print_time: f64,
+    /// The G-code being printed, if G-code is simulated.
+    program: Option<Program>,
+    /// The index of the next line of the program.
+    line: usize,
+    /// The simulated seconds until the current line is done.
+    line_remaining: f64,
+    /// The heater and target temperature an `M109` or `M190` waits for.
+    waiting: Option<(String, f32)>,
+    events: Vec<Event>,
+    heaters: BTreeMap<String, Heater>,
+    history: VecDeque<Sample>,
+    selected_tool: String,
+    last_tick: Instant,
+}
+
+impl Emulator {
+    pub(crate) fn new(settings: Settings, base_url: String) -> Self {
+        let mut heaters: BTreeMap<String, Heater> = (0..settings.tools)
+            .map(|i| (format!("tool{}", i), Heater::new(settings.ambient)))
+            .collect();
+        heaters.insert("bed".to_string(), Heater::new(settings.ambient));
+
+        Emulator {
+            settings,
+            base_url,
+            connected: true,
+            files: BTreeMap::new(),
+            selected: None,
+            job: JobState::Idle,
+            filepos: 0.0,
+            print_time: 0.0,
+            program: None,
+            line: 0,
+            line_remaining: 0.0,
+            waiting: None,
+            events: vec![],
+            heaters,
+            history: VecDeque::new(),
+            selected_tool: "tool0".to_string(),
+            last_tick: Instant::now(),
+        }
+    }
+
+    /// Stores a file, replacing an existing one at the same path.
+    pub(crate) fn store(&mut self, origin: Origin, path: &str, data: Vec<u8>) {
+        let path = path.trim_matches('/').to_string();
+        let is_gcode = [".gcode", ".gco", ".g"]
+            .iter()
+            .any(|extension| path.to_ascii_lowercase().ends_with(extension));
+        let analysis = is_gcode.then(|| Program::parse(&data).analysis());
+
+        self.files.insert(
+            (origin, path),
+            StoredFile {
+                data,
+                date: unix_time(),
+                analysis,
+            },
+        );
+    }
+
+    /// Every event emitted so far, oldest first.
+    pub(crate) fn events(&self) -> Vec<Event> {
+        self.events.clone()
+    }
+
+    /// Advances the simulation to `now`.
+    pub(crate) fn tick(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_tick).as_secs_f64();
+        let mut budget = elapsed * self.settings.time_factor;
+        self.last_tick = now;
+
+        if self.job == JobState::Printing {
+            if self.program.is_some() {
+                self.run_program(&mut budget);
+            } else {
+                self.print_time += budget;
+                self.filepos += self.settings.print_speed as f64 * budget;
+
+                let size = self.selected_file().map_or(0, |file| file.data.len()) as f64;
+                if self.filepos >= size {
+                    self.filepos = size;
+                    self.finish_print();
+                }
+            }
+        }
+        self.ramp(budget);
+
+        let time = unix_time();
+        if self.history.back().is_none_or(|(last, _)| *last < time) {
+            let sample = self
+                .heaters
+                .iter()
+                .map(|(name, heater)| (name.clone(), heater.actual, heater.target))
+                .collect();
+            self.history.push_back((time, sample));
+            if self.history.len() > HISTORY_LENGTH {
+                self.history.pop_front();
+            }
+        }
+    }
+
+    /// Moves every heater towards its target for `seconds`.
+    fn ramp(&mut self, seconds: f64) {
+        let step = self.settings.heating_rate * seconds as f32;
+        for heater in self.heaters.values_mut() {
+            let goal = if heater.target > 0.0 {
+                heater.target
+            } else {
+                self.settings.ambient
+            };
+            heater.actual = if heater.actual < goal {
+                (heater.actual + step).min(goal)
+            } else {
+                (heater.actual - step).max(goal)
+            };
+        }
+    }
+
+    /// Executes the program for up to `budget` simulated seconds, waiting for heaters and
+    /// spending the time of every line. Whatever is left of the budget when the print is done
+    /// or paused stays in `budget`.
+    fn run_program(&mut self, budget: &mut f64) {
+        while self.job == JobState::Printing && *budget > 0.0 {
+            if let Some((heater, target)) = &self.waiting {
+                let actual = self.heaters[heater].actual;
+                let needed = ((target - actual).abs() / self.settings.heating_rate) as f64;
+
+                let spent = needed.min(*budget);
+                self.ramp(spent);
+                self.print_time += spent;
+                *budget -= spent;
+                if spent >= needed {
+                    self.waiting = None;
+                }
+                continue;
+            }
+
+            if self.line_remaining > 0.0 {
+                let spent = self.line_remaining.min(*budget);
+                self.ramp(spent);
+                self.print_time += spent;
+                self.line_remaining -= spent;
+                *budget -= spent;
+                continue;
+            }
+
+            let next = self
+                .program
+                .as_ref()
+                .and_then(|p| p.lines.get(self.line).cloned());
+            let Some(line) = next else {
+                self.finish_print();
+                return;
+            };
+
+            self.line += 1;
+            self.filepos = line.end as f64;
+            self.line_remaining = line.duration;
+            if let Some((old, new)) = line.z_change {
+                self.emit("ZChange", json!({ "old": old, "new": new }));
+            }
+            if let Some(heat) = line.heat {
+                if let Some(heater) = self.heaters.get_mut(&heat.heater) {
+                    heater.target = heat.target;
+                    if heat.wait && heat.target > 0.0 {
+                        self.waiting = Some((heat.heater, heat.target));
+                    }
+                }
+            }
+        }
+    }
+
+    fn emit(&mut self, event_type: &str, payload: Value) {
+        self.events.push(Event {
+            event_type: event_type.to_string(),
+            payload,
+        });
+    }
+
+    /// The payload of the print events, describing the selected file.
+    fn print_payload(&self) -> Value {
+        match (&self.selected, self.selected_file()) {
+            (Some((origin, path)), Some(file)) => json!({
+                "name": path.rsplit('/').next().unwrap_or(path),
+                "path": path,
+                "origin": origin.as_str(),
+                "size": file.data.len(),
+                "time": self.print_time,
+            }),
+            _ => json!({}),
+        }
+    }
+
+    fn start_print(&mut self) {
+        self.filepos = 0.0;
+        self.print_time = 0.0;
+        self.line = 0;
+        self.line_remaining = 0.0;
+        self.waiting = None;
+        self.program = match self.selected_file() {
+            Some(file) if self.settings.simulate_gcode => Some(Program::parse(&file.data)),
+            _ => None,
+        };
+        self.job = JobState::Printing;
+        self.emit("PrintStarted", self.print_payload());
+    }
+
+    fn finish_print(&mut self) {
+        self.job = JobState::Idle;
+        self.waiting = None;
+        self.emit("PrintDone", self.print_payload());
+    }
+
+    /// Stops the print because it was cancelled or the printer disconnected.
+    fn fail_print(&mut self, reason: &str) {
+        self.job = JobState::Idle;
+        self.waiting = None;
+        if reason == "cancelled" {
+            self.emit("PrintCancelled", self.print_payload());
+        }
+
+        let mut payload = self.print_payload();
+        payload["reason"] = json!(reason);
+        self.emit("PrintFailed", payload);
+    }
+
+    /// Answers a request. Authentication was already checked by the server.
+    pub(crate) fn handle(
+        &mut self,
+        method: &Method,
+        path: &str,
+        query: &str,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Reply {
+        let path = path.trim_matches('/');
+        let query = Query(query);
+
+        let result = match (method, path) {
+            (&Method::GET, "api/version") => Ok(Reply::json(json!({
+                "api": "0.1",
+                "server": "1.9.3",
+                "text": "OctoPrint 1.9.3",
+            }))),
+            (&Method::GET, "api/connection") => Ok(self.connection()),
+            (&Method::GET, "api/printerprofiles") => Ok(self.printer_profiles()),
+            (&Method::POST, "api/connection") => parse(body).and_then(|c| self.set_connection(c)),
+            (&Method::GET, "api/files") => Ok(self.list(&[Origin::Local, Origin::Sdcard], &query)),
+            (&Method::GET, "api/job") => Ok(self.job()),
+            (&Method::POST, "api/job") => parse(body).and_then(|c| self.job_command(c)),
+            (&Method::GET, "api/printer") => self.printer(&query),
+            (&Method::GET, "api/printer/tool") => self.temperatures(false, &query),
+            (&Method::POST, "api/printer/tool") => parse(body).and_then(|c| self.tool_command(c)),
+            (&Method::GET, "api/printer/bed") => self.temperatures(true, &query),
+            (&Method::POST, "api/printer/bed") => parse(body).and_then(|c| self.bed_command(c)),
+            (&Method::POST, "api/printer/printhead") => {
+                parse(body).and_then(|c| self.printhead_command(c))
+            }
+            (&Method::POST, "api/printer/command") => {
+                parse(body).and_then(|c| self.gcode_command(c))
+            }
+            _ => match path.strip_prefix("api/files/").map(|p| p.split_once('/')) {
+                Some(Some((origin, file))) => match Origin::parse(origin) {
+                    Some(origin) => self.file_request(method, origin, file, &query, body),
+                    None => Err(not_found()),
+                },
+                Some(None) => match (method, Origin::parse(&path["api/files/".len()..])) {
+                    (&Method::GET, Some(origin)) => Ok(self.list(&[origin], &query)),
+                    (&Method::POST, Some(origin)) => self.upload(origin, content_type, body),
+                    _ => Err(not_found()),
+                },
+                None => Err(not_found()),
+            },
+        };
+
+        result.unwrap_or_else(|reply| reply)
+    }
+
+    //
+    //  NOTE: CONNECTION
+    //
+
+    fn state_text(&self) -> &'static str {
+        match (self.connected, self.job) {
+            (false, _) => "Offline",
+            (true, JobState::Idle) => "Operational",
+            (true, JobState::Printing) => "Printing",
+            (true, JobState::Paused) => "Paused",
+        }
+    }
+
+    fn require_operational(&self) -> Result<()> {
+        if self.connected {
+            Ok(())
+        } else {
+            Err(conflict("Printer is not operational"))
+        }
+    }
+
+    fn require_idle(&self) -> Result<()> {
+        self.require_operational()?;
+        if self.job == JobState::Printing {
+            return Err(conflict("Printer is currently printing"));
+        }
+        Ok(())
+    }
+
+    fn connection(&self) -> Reply {
+        Reply::json(json!({
+            "current": {
+                "state": if self.connected { self.state_text() } else { "Closed" },
+                "port": self.connected.then_some(PORT),
+                "baudrate": self.connected.then_some(BAUDRATE),
+                "printerProfile": "_default",
+            },
+            "options": {
+                "ports": [PORT],
+                "baudrates": [BAUDRATE],
+                "printerProfiles": [{ "id": "_default", "name": "Default" }],
+            },
+        }))
+    }
+
+    fn printer_profiles(&self) -> Reply {
+        let tools = self.settings.tools;
+        Reply::json(json!({
+            "profiles": {
+                "_default": {
+                    "id": "_default",
+                    "name": "Default",
+                    "model": "Generic RepRap Printer",
+                    "current": true,
+                    "default": true,
+                    "heatedBed": true,
+                    "heatedChamber": false,
+                    "volume": {
+                        "formFactor": "rectangular",
+                        "origin": "lowerleft",
+                        "width": 200.0,
+                        "depth": 200.0,
+                        "height": 200.0,
+                    },
+                    "extruder": {
+                        "count": tools,
+                        "nozzleDiameter": 0.4,
+                        "sharedNozzle": false,
+                        "offsets": vec![[0.0, 0.0]; tools],
+                    },
+                },
+            },
+        }))
+    }
+
+    fn set_connection(&mut self, command: Value) -> Result {
+        match command["command"].as_str() {
+            Some("connect") => {
+                let port = command["port"].as_str().unwrap_or(PORT);
+                let baudrate = command["baudrate"].as_u64().unwrap_or(BAUDRATE);
+                if port != PORT || baudrate != BAUDRATE {
+                    return Err(bad_request("Invalid port or baudrate"));
+                }
+                if !self.connected {
+                    self.connected = true;
+                    self.emit("Connected", json!({ "port": port, "baudrate": baudrate }));
+                }
+            }
+            Some("disconnect") => {
+                if self.job != JobState::Idle {
+                    self.fail_print("error");
+                }
+                if self.connected {
+                    self.connected = false;
+                    self.emit("Disconnected", json!({}));
+                }
+            }
+            Some("fake_ack") => {}
+            _ => return Err(bad_request("Unknown command")),
+        }
+        Ok(Reply::no_content())
+    }
+
+    //
+    //  NOTE: FILES
+    //
+
+    fn selected_file(&self) -> Option<&StoredFile> {
+        self.selected.as_ref().and_then(|key| self.files.get(key))
+    }
+
+    fn is_in_use(&self, key: &(Origin, String)) -> bool {
+        self.job != JobState::Idle && self.selected.as_ref() == Some(key)
+    }
+
+    fn free_space(&self) -> u64 {
+        let used: u64 = self.files.values().map(|f| f.data.len() as u64).sum();
+        TOTAL_SPACE.saturating_sub(used)
+    }
+
+    fn file_entry(&self, origin: Origin, path: &str, file: &StoredFile) -> Value {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let resource = format!("{}api/files/{}/{}", self.base_url, origin.as_str(), path);
+        let download = format!(
+            "{}downloads/files/{}/{}",
+            self.base_url,
+            origin.as_str(),
+            path
+        );
+
+        json!({
+            "name": name,
+            "display": name,
+            "path": path,
+            "type": "machinecode",
+            "typePath": ["machinecode", "gcode"],
+            "origin": origin.as_str(),
+            "date": file.date,
+            "size": file.data.len(),
+            "refs": { "resource": resource, "download": download },
+            "gcodeAnalysis": file.analysis,
+        })
+    }
+
+    fn folder_entry(&self, origin: Origin, path: &str, recursive: bool) -> Value {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let resource = format!("{}api/files/{}/{}", self.base_url, origin.as_str(), path);
+        let children = if recursive {
+            self.entries(origin, path, true)
+        } else {
+            vec![]
+        };
+
+        json!({
+            "name": name,
+            "display": name,
+            "path": path,
+            "type": "folder",
+            "typePath": ["folder"],
+            "origin": origin.as_str(),
+            "children": children,
+            "refs": { "resource": resource },
+        })
+    }
+
+    /// The files and folders directly in `folder`, folders with their children if `recursive`.
+    fn entries(&self, origin: Origin, folder: &str, recursive: bool) -> Vec<Value> {
+        let prefix = if folder.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", folder)
+        };
+
+        let mut folders = BTreeSet::new();
+        let mut entries = vec![];
+        for ((file_origin, path), file) in &self.files {
+            let Some(rest) = path
+                .strip_prefix(&prefix)
+                .filter(|_| *file_origin == origin)
+            else {
+                continue;
+            };
+            match rest.split_once('/') {
+                Some((child, _)) => {
+                    folders.insert(format!("{}{}", prefix, child));
+                }
+                None => entries.push(self.file_entry(origin, path, file)),
+            }
+        }
+
+        for folder in folders {
+            entries.push(self.folder_entry(origin, &folder, recursive));
+        }
+        entries
+    }
+
+    fn list(&self, origins: &[Origin], query: &Query) -> Reply {
+        let recursive = query.flag("recursive");
+        let files: Vec<Value> = origins
+            .iter()
+            .flat_map(|origin| self.entries(*origin, "", recursive))
+            .collect();
+
+        Reply::json(json!({
+            "files": files,
+            "free": self.free_space(),
+            "total": TOTAL_SPACE,
+        }))
+    }
+
+    fn is_folder(&self, origin: Origin, path: &str) -> bool {
+        let prefix = format!("{}/", path);
+        self.files
+            .keys()
+            .any(|(file_origin, file)| *file_origin == origin && file.starts_with(&prefix))
+    }
+
+    fn file_request(
+        &mut self,
+        method: &Method,
+        origin: Origin,
+        path: &str,
+        query: &Query,
+        body: &[u8],
+    ) -> Result {
+        let path = path.trim_matches('/').to_string();
+        let key = (origin, path.clone());
+
+        match *method {
+            Method::GET => match self.files.get(&key) {
+                Some(file) => Ok(Reply::json(self.file_entry(origin, &path, file))),
+                None if self.is_folder(origin, &path) => Ok(Reply::json(self.folder_entry(
+                    origin,
+                    &path,
+                    query.flag("recursive"),
+                ))),
+                None => Err(not_found()),
+            },
+            Method::POST => {
+                let command: Value = parse(body)?;
+                self.file_command(key, command)
+            }
+            Method::DELETE => {
+                if !self.files.contains_key(&key) {
+                    return Err(not_found());
+                }
+                if self.is_in_use(&key) {
+                    return Err(conflict("Trying to delete a file that is currently in use"));
+                }
+                self.files.remove(&key);
+                if self.selected.as_ref() == Some(&key) {
+                    self.selected = None;
+                }
+                Ok(Reply::no_content())
+            }
+            _ => Err(not_found()),
+        }
+    }
+
+    fn file_command(&mut self, key: (Origin, String), command: Value) -> Result {
+        if !self.files.contains_key(&key) {
+            return Err(not_found());
+        }
+
+        match command["command"].as_str() {
+            Some("select") => {
+                let print = command["print"].as_bool().unwrap_or(false);
+                self.select(key, print)?;
+            }
+            Some("unselect") => {
+                if self.job != JobState::Idle {
+                    return Err(conflict("Printer is busy"));
+                }
+                self.selected = None;
+            }
+            Some(action @ ("copy" | "move")) => {
+                let Some(destination) = command["destination"].as_str() else {
+                    return Err(bad_request("No destination given"));
+                };
+                if action == "move" && self.is_in_use(&key) {
+                    return Err(conflict("Trying to move a file that is currently in use"));
+                }
+
+                let name = key.1.rsplit('/').next().unwrap_or(&key.1).to_string();
+                let destination = destination.trim_matches('/');
+                let path = if destination.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", destination, name)
+                };
+                let target = (key.0, path);
+                if self.files.contains_key(&target) {
+                    return Err(conflict("File already exists"));
+                }
+
+                let data = if action == "move" {
+                    let file = self.files.remove(&key).expect("checked above");
+                    if self.selected.as_ref() == Some(&key) {
+                        self.selected = Some(target.clone());
+                    }
+                    file.data
+                } else {
+                    self.files[&key].data.clone()
+                };
+                self.store(target.0, &target.1, data);
+            }
+            _ => return Err(bad_request("Unknown command")),
+        }
+        Ok(Reply::no_content())
+    }
+
+    fn select(&mut self, key: (Origin, String), print: bool) -> Result<()> {
+        if self.job != JobState::Idle {
+            return Err(conflict("Printer is already printing"));
+        }
+        if print {
+            self.require_operational()?;
+        }
+
+        self.selected = Some(key);
+        self.filepos = 0.0;
+        self.print_time = 0.0;
+        if print {
+            self.start_print();
+        }
+        Ok(())
+    }
+
+    fn upload(&mut self, origin: Origin, content_type: Option<&str>, body: &[u8]) -> Result {
+        let parts = content_type
+            .and_then(|content_type| multipart::parse(content_type, body))
+            .ok_or_else(|| bad_request("Expected a multipart/form-data body"))?;
+
+        let field = |name: &str| {
+            parts
+                .iter()
+                .find(|part| part.name == name)
+                .and_then(|part| std::str::from_utf8(&part.data).ok())
+        };
+        let Some(file) = parts.iter().find(|part| part.name == "file") else {
+            return Err(bad_request("No file included"));
+        };
+        let Some(name) = file.filename.as_deref().filter(|name| !name.is_empty()) else {
+            return Err(bad_request("No filename given"));
+        };
+
+        let folder = field("path").unwrap_or("").trim_matches('/');
+        let path = if folder.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", folder, name)
+        };
+        let key = (origin, path.clone());
+        if self.is_in_use(&key) {
+            return Err(conflict(
+                "Trying to overwrite a file that is currently in use",
+            ));
+        }
+
+        self.store(origin, &path, file.data.clone());
+
+        let print = field("print") == Some("true");
+        let done = if print || field("select") == Some("true") {
+            self.select(key, print).is_ok()
+        } else {
+            true
+        };
+
+        let mut files = Map::new();
+        let entry = self.file_entry(origin, &path, &self.files[&(origin, path.clone())]);
+        files.insert(origin.as_str().to_string(), entry);
+
+        Ok(Reply {
+            status: StatusCode::CREATED,
+            body: Some(json!({ "files": files, "done": done })),
+            error: None,
+        })
+    }
+
+    //
+    //  NOTE: JOB
+    //
+
+    fn job(&self) -> Reply {
+        let selected = self
+            .selected
+            .as_ref()
+            .and_then(|key| Some((key, self.files.get(key)?)));
+        let speed = self.settings.print_speed.max(1) as f64;
+        let (estimated, time_left) = match (&self.program, selected) {
+            (Some(program), _) => {
+                let done = self
+                    .line
+                    .checked_sub(1)
+                    .map_or(0.0, |i| program.lines[i].done_at);
+                (
+                    Some(program.total),
+                    program.total - done + self.line_remaining,
+                )
+            }
+            (None, Some((_, file))) => {
+                let size = file.data.len() as f64;
+                (Some(size / speed), (size - self.filepos) / speed)
+            }
+            (None, None) => (None, 0.0),
+        };
+
+        let (file, progress) = match selected {
+            Some(((origin, path), file)) => {
+                let size = file.data.len() as f64;
+                let completion = if size > 0.0 {
+                    self.filepos / size * 100.0
+                } else {
+                    100.0
+                };
+                (
+                    json!({
+                        "name": path.rsplit('/').next().unwrap_or(path),
+                        "origin": origin.as_str(),
+                        "path": path,
+                        "size": file.data.len(),
+                        "date": file.date,
+                    }),
+                    json!({
+                        "completion": completion,
+                        "filepos": self.filepos as u64,
+                        "printTime": self.print_time as u64,
+                        "printTimeLeft": time_left.max(0.0).ceil() as u64,
+                        "printTimeOrigin": "estimate",
+                    }),
+                )
+            }
+            None => (
+                json!({ "name": null, "origin": null, "path": null, "size": null, "date": null }),
+                json!({
+                    "completion": null,
+                    "filepos": null,
+                    "printTime": null,
+                    "printTimeLeft": null,
+                    "printTimeOrigin": null,
+                }),
+            ),
+        };
+
+        Reply::json(json!({
+            "job": {
+                "file": file,
+                "estimatedPrintTime": estimated,
+                "lastPrintTime": null,
+                "filament": { "length": null, "volume": null },
+                "user": null,
+            },
+            "progress": progress,
+            "state": self.state_text(),
+        }))
+    }
+
+    fn job_command(&mut self, command: Value) -> Result {
+        self.require_operational()?;
+
+        match (command["command"].as_str(), self.job) {
+            (Some("start"), JobState::Idle) => {
+                let Some(key) = self.selected.clone() else {
+                    return Err(conflict("No file selected"));
+                };
+                self.select(key, true)?;
+            }
+            (Some("restart"), JobState::Paused) => self.start_print(),
+            (Some("cancel"), JobState::Printing | JobState::Paused) => self.fail_print("cancelled"),
+            (Some("pause"), JobState::Printing | JobState::Paused) => {
+                let job = match (command["action"].as_str().unwrap_or("toggle"), self.job) {
+                    ("pause" | "toggle", JobState::Printing) => JobState::Paused,
+                    ("resume" | "toggle", JobState::Paused) => JobState::Printing,
+                    ("pause" | "resume", state) => state,
+                    _ => return Err(bad_request("Unknown action")),
+                };
+                if job != self.job {
+                    self.job = job;
+                    let event = if job == JobState::Paused {
+                        "PrintPaused"
+                    } else {
+                        "PrintResumed"
+                    };
+                    self.emit(event, self.print_payload());
+                }
+            }
+            (Some("start" | "restart" | "cancel" | "pause"), _) => {
+                return Err(conflict(
+                    "Printer is not in the right state for this command",
+                ));
+            }
+            _ => return Err(bad_request("Unknown command")),
+        }
+        Ok(Reply::no_content())
+    }
+
+    //
+    //  NOTE: PRINTER
+    //
+
+    fn heater(&self, name: &str) -> Value {
+        let heater = &self.heaters[name];
+        json!({ "actual": heater.actual, "target": heater.target, "offset": heater.offset })
+    }
+
+    /// The current temperatures and, if requested, their history. Only the bed, or every heater
+    /// but the bed.
+    fn temperature_state(&self, bed: bool, query: &Query) -> Map<String, Value> {
+        let wanted = |name: &str| (name == "bed") == bed;
+
+        let mut state: Map<String, Value> = self
+            .heaters
+            .keys()
+            .filter(|name| wanted(name))
+            .map(|name| (name.clone(), self.heater(name)))
+            .collect();
+
+        if query.flag("history") {
+            let limit = query
+                .get("limit")
+                .and_then(|l| l.parse().ok())
+                .unwrap_or(usize::MAX);
+            let history: Vec<Value> = self
+                .history
+                .iter()
+                .rev()
+                .take(limit)
+                .rev()
+                .map(|(time, samples)| {
+                    let mut entry = Map::new();
+                    entry.insert("time".to_string(), json!(time));
+                    for (name, actual, target) in samples.iter().filter(|s| wanted(&s.0)) {
+                        entry.insert(name.clone(), json!({ "actual": actual, "target": target }));
+                    }
+                    Value::Object(entry)
+                })
+                .collect();
+            state.insert("history".to_string(), Value::Array(history));
+        }
+        state
+    }
+
+    fn temperatures(&self, bed: bool, query: &Query) -> Result {
+        self.require_operational()?;
+        Ok(Reply::json(Value::Object(
+            self.temperature_state(bed, query),
+        )))
+    }
+
+    fn printer(&self, query: &Query) -> Result {
+        self.require_operational()?;
+
+        let mut temperature = self.temperature_state(false, query);
+        temperature.insert("bed".to_string(), self.heater("bed"));
+
+        Ok(Reply::json(json!({
+            "temperature": temperature,
+            "sd": { "ready": true },
+            "state": {
+                "text": self.state_text(),
+                "flags": {
+                    "operational": true,
+                    "paused": self.job == JobState::Paused,
+                    "pausing": false,
+                    "printing": self.job == JobState::Printing,
+                    "cancelling": false,
+                    "sdReady": true,
+                    "error": false,
+                    "ready": self.job == JobState::Idle,
+                    "closedOrError": false,
+                },
+            },
+        })))
+    }
+
+    fn tool_command(&mut self, command: Value) -> Result {
+        self.require_operational()?;
+
+        match command["command"].as_str() {
+            Some(kind @ ("target" | "offset")) => {
+                let key = if kind == "target" {
+                    "targets"
+                } else {
+                    "offsets"
+                };
+                let Some(values) = command[key].as_object() else {
+                    return Err(bad_request("No values given"));
+                };
+
+                let mut updates = vec![];
+                for (tool, value) in values {
+                    let valid = tool != "bed" && self.heaters.contains_key(tool);
+                    match value.as_f64().filter(|_| valid) {
+                        Some(value) => updates.push((tool.clone(), value as f32)),
+                        None => return Err(bad_request("Invalid tool or value")),
+                    }
+                }
+
+                for (tool, value) in updates {
+                    let heater = self.heaters.get_mut(&tool).expect("validated above");
+                    if kind == "target" {
+                        heater.target = value;
+                    } else {
+                        heater.offset = value;
+                    }
+                }
+            }
+            Some("select") => {
+                self.require_idle()?;
+                match command["tool"].as_str() {
+                    Some(tool) if tool != "bed" && self.heaters.contains_key(tool) => {
+                        self.selected_tool = tool.to_string();
+                    }
+                    _ => return Err(bad_request("Invalid tool")),
+                }
+            }
+            Some("extrude") => {
+                self.require_idle()?;
+                if !command["amount"].is_number() {
+                    return Err(bad_request("Invalid amount"));
+                }
+            }
+            Some("flowrate") => match command["factor"].as_f64() {
+                Some(factor) if (0.75..=1.25).contains(&factor) => {}
+                _ => return Err(bad_request("Invalid factor")),
+            },
+            _ => return Err(bad_request("Unknown command")),
+        }
+        Ok(Reply::no_content())
+    }
+
+    fn bed_command(&mut self, command: Value) -> Result {
+        self.require_operational()?;
+
+        let bed = self.heaters.get_mut("bed").expect("the bed always exists");
+        match command["command"].as_str() {
+            Some("target") => match command["target"].as_f64() {
+                Some(target) => bed.target = target as f32,
+                None => return Err(bad_request("Invalid target")),
+            },
+            Some("offset") => match command["offset"].as_f64() {
+                Some(offset) => bed.offset = offset as f32,
+                None => return Err(bad_request("Invalid offset")),
+            },
+            _ => return Err(bad_request("Unknown command")),
+        }
+        Ok(Reply::no_content())
+    }
+
+    /// Sends G-code to the printer. Only temperature commands have an effect, waiting ones
+    /// don't block.
+    fn gcode_command(&mut self, command: Value) -> Result {
+        self.require_operational()?;
+
+        let commands = match (&command["command"], &command["commands"]) {
+            (Value::String(command), Value::Null) => vec![command.as_str()],
+            (Value::Null, Value::Array(commands)) => commands
+                .iter()
+                .map(|c| c.as_str().ok_or_else(|| bad_request("Invalid command")))
+                .collect::<Result<_>>()?,
+            _ => return Err(bad_request("Expected command or commands")),
+        };
+        if commands.is_empty() {
+            return Err(bad_request("No commands given"));
+        }
+
+        let program = Program::parse(commands.join("\n").as_bytes());
+        for heat in program.lines.into_iter().filter_map(|line| line.heat) {
+            if let Some(heater) = self.heaters.get_mut(&heat.heater) {
+                heater.target = heat.target;
+            }
+        }
+        Ok(Reply::no_content())
+    }
+
+    fn printhead_command(&mut self, command: Value) -> Result {
+        match command["command"].as_str() {
+            Some("jog" | "home") => self.require_idle()?,
+            Some("feedrate") => {
+                self.require_operational()?;
+                if !command["factor"]
+                    .as_f64()
+                    .is_some_and(|f| (0.5..=2.0).contains(&f))
+                {
+                    return Err(bad_request("Invalid factor"));
+                }
+            }
+            _ => return Err(bad_request("Unknown command")),
+        }
+        Ok(Reply::no_content())
+    }
+}
+
+impl Heater {
+    fn new(ambient: f32) -> Self {
+        Heater {
+            actual: ambient,
+            target: 0.0,
+            offset: 0.0,
+        }
+    }
+}
+
+/// The query string of a request.
+struct Query<'a>(&'a str);
+
+impl Query<'_> {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.get(name) == Some("true")
+    }
+}
+
+fn parse(body: &[u8]) -> Result<Value> {
+    serde_json::from_slice(body).map_err(|_| bad_request("Expected a json body"))
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
@@ -0,0 +1,211 @@
+use std::{sync::Arc, time::SystemTime};
+
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+    Certificate, ClientConfig, OwnedTrustAnchor, PrivateKey, RootCertStore, ServerName,
+};
+use sha2::{Digest, Sha256};
+
+use crate::errors::PrinterBuilderError;
+
+/// The TLS options collected by the `PrinterBuilder`.
+///
+/// If none of the options are set the default TLS backend of `reqwest` is used, otherwise a
+/// `rustls` config is built from them and handed to `reqwest`.
+#[derive(Default)]
+pub(crate) struct TlsOptions {
+    root_certificates: Vec<Certificate>,
+    identity: Option<(Vec<Certificate>, PrivateKey)>,
+    pinned_sha256: Option<[u8; 32]>,
+    accept_invalid_certs: bool,
+}
+
+impl TlsOptions {
+    pub(crate) fn is_default(&self) -> bool {
+        self.root_certificates.is_empty()
+            && self.identity.is_none()
+            && self.pinned_sha256.is_none()
+            && !self.accept_invalid_certs
+    }
+
+    /// Adds every certificate in `pem` as a trusted root.
+    pub(crate) fn add_root_certificate_pem(
+        &mut self,
+        pem: &[u8],
+    ) -> Result<(), PrinterBuilderError> {
+        let certificates = parse_certificates(pem)?;
+        if certificates.is_empty() {
+            return Err(PrinterBuilderError::InvalidCertificate(
+                "no certificate found in PEM".to_string(),
+            ));
+        }
+
+        let mut store = RootCertStore::empty();
+        for certificate in &certificates {
+            store
+                .add(certificate)
+                .map_err(|e| PrinterBuilderError::InvalidCertificate(e.to_string()))?;
+        }
+
+        self.root_certificates.extend(certificates);
+        Ok(())
+    }
+
+    pub(crate) fn set_identity_pem(
+        &mut self,
+        certificate_pem: &[u8],
+        key_pem: &[u8],
+    ) -> Result<(), PrinterBuilderError> {
+        let certificates = parse_certificates(certificate_pem)?;
+        if certificates.is_empty() {
+            return Err(PrinterBuilderError::InvalidCertificate(
+                "no certificate found in PEM".to_string(),
+            ));
+        }
+
+        let key = parse_private_key(key_pem)?;
+        rustls::sign::any_supported_type(&key)
+            .map_err(|e| PrinterBuilderError::InvalidPrivateKey(e.to_string()))?;
+
+        self.identity = Some((certificates, key));
+        Ok(())
+    }
+
+    pub(crate) fn set_pinned_sha256(
+        &mut self,
+        fingerprint: &str,
+    ) -> Result<(), PrinterBuilderError> {
+        self.pinned_sha256 = Some(parse_fingerprint(fingerprint)?);
+        Ok(())
+    }
+
+    pub(crate) fn set_accept_invalid_certs(&mut self, accept: bool) {
+        self.accept_invalid_certs = accept;
+    }
+
+    /// Builds the `rustls` config that is handed to `reqwest`. A pinned fingerprint takes
+    /// precedence over accepting invalid certificates, so the pin can not be turned off by it.
+    pub(crate) fn client_config(&self) -> ClientConfig {
+        let verifier: Arc<dyn ServerCertVerifier> = if let Some(fingerprint) = self.pinned_sha256 {
+            Arc::new(PinnedCertVerifier { fingerprint })
+        } else if self.accept_invalid_certs {
+            Arc::new(AcceptAnyCertVerifier)
+        } else {
+            let mut roots = RootCertStore::empty();
+            roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    anchor.subject,
+                    anchor.spki,
+                    anchor.name_constraints,
+                )
+            }));
+            for certificate in &self.root_certificates {
+                // Validated when the certificate was added
+                let _ = roots.add(certificate);
+            }
+            Arc::new(WebPkiVerifier::new(roots, None))
+        };
+
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(verifier);
+
+        match &self.identity {
+            Some((certificates, key)) => builder
+                .with_client_auth_cert(certificates.clone(), key.clone())
+                .expect("private key is validated when the identity is set"),
+            None => builder.with_no_client_auth(),
+        }
+    }
+}
+
+/// Accepts the server certificate if its SHA-256 fingerprint matches the pinned one, no matter
+/// who issued it. The handshake signature is still verified against the certificate.
+struct PinnedCertVerifier {
+    fingerprint: [u8; 32],
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint: [u8; 32] = Sha256::digest(&end_entity.0).into();
+
+        if fingerprint == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "server certificate does not match the pinned fingerprint".to_string(),
+            ))
+        }
+    }
+}
+
+/// Accepts any server certificate. Only meant for lab setups.
+struct AcceptAnyCertVerifier;
+
+impl ServerCertVerifier for AcceptAnyCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+fn parse_certificates(pem: &[u8]) -> Result<Vec<Certificate>, PrinterBuilderError> {
+    rustls_pemfile::certs(&mut &*pem)
+        .map(|certificates| certificates.into_iter().map(Certificate).collect())
+        .map_err(|e| PrinterBuilderError::InvalidCertificate(e.to_string()))
+}
+
+fn parse_private_key(pem: &[u8]) -> Result<PrivateKey, PrinterBuilderError> {
+    let items = rustls_pemfile::read_all(&mut &*pem)
+        .map_err(|e| PrinterBuilderError::InvalidPrivateKey(e.to_string()))?;
+
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            PrinterBuilderError::InvalidPrivateKey("no private key found in PEM".to_string())
+        })
+}
+
+/// Parses a hex encoded SHA-256 fingerprint. Colons and whitespace between the bytes are
+/// ignored, so both `AB:CD:...` and `abcd...` are accepted.
+fn parse_fingerprint(fingerprint: &str) -> Result<[u8; 32], PrinterBuilderError> {
+    let digits: Vec<u8> = fingerprint
+        .bytes()
+        .filter(|c| *c != b':' && !c.is_ascii_whitespace())
+        .collect();
+
+    let invalid = || PrinterBuilderError::InvalidFingerprint(fingerprint.to_string());
+
+    // `from_str_radix` would also accept a sign in front of a digit
+    if digits.len() != 64 || !digits.iter().all(u8::is_ascii_hexdigit) {
+        return Err(invalid());
+    }
+
+    let mut bytes = [0u8; 32];
+    for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+        let pair = std::str::from_utf8(pair).expect("hex digits are ascii");
+        *byte = u8::from_str_radix(pair, 16).expect("validated above");
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,86 @@
+use std::{future::Future, time::Duration};
+
+use reqwest::{Method, StatusCode};
+
+/// Traces a single request to the printer.
+///
+/// With the `tracing` feature every request gets a `octoprint_request` span carrying the
+/// endpoint, method, printer and, once the request finished, the status, latency and number of
+/// retries. The span is entered while the request is sent, so events of the HTTP client belong to
+/// it. Without the feature all of this compiles to nothing.
+///
+/// Headers are never recorded, so the api key does not end up in the logs.
+pub(crate) struct RequestTrace {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    started: std::time::Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl RequestTrace {
+    pub(crate) fn start(printer: &str, method: &Method, endpoint: &str) -> Self {
+        let span = tracing::debug_span!(
+            "octoprint_request",
+            printer = %printer,
+            method = %method,
+            endpoint = %endpoint,
+            status = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+            retries = tracing::field::Empty,
+        );
+
+        RequestTrace {
+            span,
+            started: std::time::Instant::now(),
+        }
+    }
+
+    /// Enters the span whenever `future` is polled.
+    pub(crate) fn instrument<F: Future>(&self, future: F) -> impl Future<Output = F::Output> {
+        tracing::Instrument::instrument(future, self.span.clone())
+    }
+
+    pub(crate) fn retry(&self, attempt: u32, delay: Duration) {
+        tracing::debug!(
+            parent: &self.span,
+            attempt,
+            delay_ms = delay.as_millis() as u64,
+            "retrying request"
+        );
+    }
+
+    pub(crate) fn finish(&self, status: Option<StatusCode>, retries: u32) {
+        let latency = self.started.elapsed();
+
+        self.span.record("latency_ms", latency.as_millis() as u64);
+        self.span.record("retries", retries);
+
+        match status {
+            Some(status) => {
+                self.span.record("status", status.as_u16());
+                if status.is_success() {
+                    tracing::debug!(parent: &self.span, %status, "request finished");
+                } else {
+                    tracing::warn!(parent: &self.span, %status, "request failed");
+                }
+            }
+            None => tracing::warn!(parent: &self.span, "request could not be sent"),
+        }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl RequestTrace {
+    pub(crate) fn start(_printer: &str, _method: &Method, _endpoint: &str) -> Self {
+        RequestTrace {}
+    }
+
+    pub(crate) fn instrument<F: Future>(&self, future: F) -> F {
+        future
+    }
+
+    pub(crate) fn retry(&self, _attempt: u32, _delay: Duration) {}
+
+    pub(crate) fn finish(&self, _status: Option<StatusCode>, _retries: u32) {}
+}
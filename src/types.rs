@@ -1,4 +1,8 @@
-use std::{collections::HashMap, iter::zip};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -6,7 +10,7 @@ use serde::{Deserialize, Serialize};
 //  INFO: HELPER STRUCTS
 //
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Dimension {
     pub width: f32,
     pub height: f32,
@@ -54,31 +58,31 @@ pub struct ApiVersion {
 /// This is the struct
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PrinterConnection {
-    current: PrinterConnectionStateCurrent,
-    options: PrinterConnectionStateOptions,
+    pub current: PrinterConnectionStateCurrent,
+    pub options: PrinterConnectionStateOptions,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PrinterConnectionStateCurrent {
-    state: String,
-    port: Option<String>,
-    baudrate: Option<u32>,
+    pub state: String,
+    pub port: Option<String>,
+    pub baudrate: Option<u32>,
     #[serde(rename = "printerProfile")]
-    printer_profile: String,
+    pub printer_profile: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PrinterConnectionStateOptions {
-    ports: Vec<String>,
-    baudrates: Vec<u32>,
+    pub ports: Vec<String>,
+    pub baudrates: Vec<u32>,
     #[serde(rename = "printerProfiles")]
-    printer_profiles: Vec<PrinterProfile>,
+    pub printer_profiles: Vec<PrinterProfile>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PrinterProfile {
-    name: String,
-    id: String,
+    pub name: String,
+    pub id: String,
 }
 
 //
@@ -155,6 +159,88 @@ impl ConnectionCommandDescriptor {
     }
 }
 
+//
+//  INFO: PRINTER PROFILES
+//
+
+pub mod printer_profiles {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Serialize};
+
+    /// Every printer profile configured in OctoPrint, by id.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct Profiles {
+        pub profiles: HashMap<String, Profile>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Profile {
+        pub id: String,
+        pub name: String,
+        pub model: Option<String>,
+        /// Whether this is the profile of the current connection.
+        #[serde(default)]
+        pub current: bool,
+        #[serde(default)]
+        pub default: bool,
+        #[serde(default)]
+        pub heated_bed: bool,
+        #[serde(default)]
+        pub heated_chamber: bool,
+        pub volume: Volume,
+        pub extruder: Extruder,
+    }
+
+    /// The build volume in millimeters. For a `circular` form factor `width` and `depth` are
+    /// the diameter of the bed.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Volume {
+        pub form_factor: String,
+        pub origin: String,
+        pub width: f32,
+        pub depth: f32,
+        pub height: f32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Extruder {
+        pub count: u32,
+        pub nozzle_diameter: f32,
+        #[serde(default)]
+        pub shared_nozzle: bool,
+        #[serde(default)]
+        pub offsets: Vec<[f32; 2]>,
+    }
+
+    impl Profile {
+        /// The tools with a heater of their own. Extruders sharing a nozzle share the heater of
+        /// `tool0`.
+        pub fn tools(&self) -> Vec<super::ToolId> {
+            let count = if self.extruder.shared_nozzle {
+                1
+            } else {
+                self.extruder.count
+            };
+            (0..count).map(super::ToolId::new).collect()
+        }
+    }
+
+    impl Profiles {
+        /// The profile of the current connection, or the default profile if the printer is not
+        /// connected.
+        pub fn current(&self) -> Option<&Profile> {
+            let profiles = || self.profiles.values();
+            profiles()
+                .find(|profile| profile.current)
+                .or_else(|| profiles().find(|profile| profile.default))
+        }
+    }
+}
+
 //
 //  INFO: PRINTER FILE COMMANDS
 //
@@ -253,9 +339,9 @@ pub mod printer_files {
     #[derive(Serialize, Deserialize, Debug)]
     pub struct Files {
         #[serde(default)]
-        files: Vec<Entry>,
-        free: u64,
-        total: u64,
+        pub files: Vec<Entry>,
+        pub free: u64,
+        pub total: u64,
     }
 
     #[derive(Serialize, Deserialize, Debug)]
@@ -304,12 +390,12 @@ pub mod printer_files {
     #[derive(Serialize, Deserialize, Debug)]
     #[serde(rename_all = "camelCase")]
     pub struct GCodeAnalysis {
-        dimensions: Dimension,
-        estimated_print_time: Option<f32>,
-        printing_area: Option<GCodeAnalysisArea>,
-        travel_area: Option<GCodeAnalysisArea>,
-        travel_dimensions: Option<Dimension>,
-        filament: Option<GCodeAnalysisTools>,
+        pub dimensions: Dimension,
+        pub estimated_print_time: Option<f32>,
+        pub printing_area: Option<GCodeAnalysisArea>,
+        pub travel_area: Option<GCodeAnalysisArea>,
+        pub travel_dimensions: Option<Dimension>,
+        pub filament: Option<GCodeAnalysisTools>,
     }
 
     #[derive(Serialize, Deserialize, Debug)]
@@ -326,30 +412,161 @@ pub mod printer_files {
     #[derive(Serialize, Deserialize, Debug)]
     pub struct GCodeAnalysisTools {
         #[serde(flatten)]
-        tools: HashMap<String, FilamentTool>,
+        pub tools: HashMap<String, FilamentTool>,
     }
 
     #[derive(Serialize, Deserialize, Debug)]
     pub struct PrintHistory {
-        success: u32,
-        failure: u32,
-        last: PrintHistoryLast,
+        pub success: u32,
+        pub failure: u32,
+        pub last: PrintHistoryLast,
     }
 
     #[derive(Serialize, Deserialize, Debug)]
     #[serde(rename_all = "camelCase")]
     pub struct PrintHistoryLast {
-        date: u64,
-        print_time: Option<f32>,
-        success: bool,
+        pub date: u64,
+        pub print_time: Option<f32>,
+        pub success: bool,
     }
 
     #[derive(Serialize, Deserialize, Debug)]
     #[serde(rename_all = "camelCase")]
     pub struct Statistics {
-        average_print_time: HashMap<String, f32>,
-        last_print_time: HashMap<String, f32>,
+        pub average_print_time: HashMap<String, f32>,
+        pub last_print_time: HashMap<String, f32>,
     }
+
+    /// The print statistics of a single file.
+    ///
+    /// # Fields
+    ///
+    /// * `path`: The path of the file.
+    /// * `origin`: Where the file is stored, either `local` or `sdcard`.
+    /// * `success`: How many times the file was printed successfully.
+    /// * `failure`: How many times printing the file failed.
+    /// * `last_date`: When the file was last printed, as a unix timestamp.
+    /// * `last_success`: Whether the last print of the file was successful.
+    /// * `average_print_time`: The average print time in seconds per printer profile.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct FilePrintStatistics {
+        pub path: String,
+        pub origin: String,
+        pub success: u32,
+        pub failure: u32,
+        pub last_date: u64,
+        pub last_success: bool,
+        pub average_print_time: HashMap<String, f32>,
+    }
+
+    /// Print statistics aggregated over every file that has been printed at least once.
+    ///
+    /// # Fields
+    ///
+    /// * `files`: The statistics of every printed file.
+    /// * `success`: The total amount of successful prints.
+    /// * `failure`: The total amount of failed prints.
+    /// * `print_time_per_profile`: The estimated total print time in seconds per printer profile.
+    ///   OctoPrint only keeps the average print time per profile, so this is the average
+    ///   multiplied by the amount of successful prints of each file. It is exact as long as each
+    ///   file has only been printed with a single profile.
+    /// * `last_printed`: When any file was last printed, as a unix timestamp.
+    #[derive(Serialize, Deserialize, Debug, Default)]
+    pub struct PrintStatisticsReport {
+        pub files: Vec<FilePrintStatistics>,
+        pub success: u32,
+        pub failure: u32,
+        pub print_time_per_profile: HashMap<String, f32>,
+        pub last_printed: Option<u64>,
+    }
+
+    impl PrintStatisticsReport {
+        /// Aggregates the print statistics of all entries, walking into folders recursively.
+        /// Files without a print history are skipped.
+        pub fn from_entries(entries: &[Entry]) -> PrintStatisticsReport {
+            let mut report = PrintStatisticsReport::default();
+            report.collect(entries);
+            report
+        }
+
+        fn collect(&mut self, entries: &[Entry]) {
+            for entry in entries {
+                match entry {
+                    Entry::Folder { children, .. } => self.collect(children),
+                    Entry::File {
+                        path,
+                        origin,
+                        print: Some(print),
+                        statistics,
+                        ..
+                    } => {
+                        let average_print_time = statistics
+                            .as_ref()
+                            .map(|s| s.average_print_time.clone())
+                            .unwrap_or_default();
+
+                        for (profile, average) in &average_print_time {
+                            *self
+                                .print_time_per_profile
+                                .entry(profile.clone())
+                                .or_insert(0.0) += average * print.success as f32;
+                        }
+
+                        self.success += print.success;
+                        self.failure += print.failure;
+                        self.last_printed = self.last_printed.max(Some(print.last.date));
+
+                        self.files.push(FilePrintStatistics {
+                            path: path.clone(),
+                            origin: origin.clone(),
+                            success: print.success,
+                            failure: print.failure,
+                            last_date: print.last.date,
+                            last_success: print.last.success,
+                            average_print_time,
+                        });
+                    }
+                    Entry::File { .. } => {}
+                }
+            }
+        }
+    }
+}
+
+//
+//  INFO: FILE UPLOAD
+//
+
+/// A file to upload to the printer.
+///
+/// `path` is the path of the uploaded file, including its name. Folders in the path are
+/// created if they don't exist.
+pub struct FileUploadDescriptor {
+    pub path: PathDescriptor,
+    pub contents: Vec<u8>,
+    /// Whether to select the file after uploading it.
+    pub select: bool,
+    /// Whether to start printing the file after uploading it. This implies `select`.
+    pub print: bool,
+}
+
+/// The response to an upload. `files` holds the uploaded file by its location, `local` or
+/// `sdcard`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadResponse {
+    pub files: HashMap<String, UploadedFile>,
+    /// Whether the upload is done. Uploads to the SD card are still being streamed to the
+    /// printer if this is `false`.
+    #[serde(default)]
+    pub done: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadedFile {
+    pub name: String,
+    pub origin: String,
+    pub path: Option<String>,
+    pub refs: Option<printer_files::Refs>,
 }
 
 //
@@ -399,41 +616,41 @@ impl JobCommand {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct JobInformation {
-    job: Job,
-    progress: JobProgress,
-    state: String,
+    pub job: Job,
+    pub progress: JobProgress,
+    pub state: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Job {
     #[serde(rename = "estimatedPrintTime")]
-    estimated_print_time: Option<f32>,
-    filament: FilamentToolOpt,
-    file: JobFile,
+    pub estimated_print_time: Option<f32>,
+    pub filament: FilamentToolOpt,
+    pub file: JobFile,
     #[serde(rename = "lastPrintTime")]
-    last_print_time: Option<f32>,
-    user: Option<String>,
+    pub last_print_time: Option<f32>,
+    pub user: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct JobFile {
-    date: Option<u64>,
-    name: Option<String>,
-    origin: Option<String>,
-    path: Option<String>,
-    size: Option<u64>,
+    pub date: Option<u64>,
+    pub name: Option<String>,
+    pub origin: Option<String>,
+    pub path: Option<String>,
+    pub size: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct JobProgress {
-    completion: Option<f32>,
-    filepos: Option<u64>,
+    pub completion: Option<f32>,
+    pub filepos: Option<u64>,
     #[serde(rename = "printTime")]
-    print_time: Option<u64>,
+    pub print_time: Option<u64>,
     #[serde(rename = "printTimeLeft")]
-    print_time_left: Option<u64>,
+    pub print_time_left: Option<u64>,
     #[serde(rename = "printTimeOrigin")]
-    print_time_origin: Option<String>,
+    pub print_time_origin: Option<String>,
 }
 
 //
@@ -447,7 +664,7 @@ pub struct RawPrinter {
     pub state: PrinterState,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PrinterTool {
     pub actual: f32,
     pub target: Option<f32>,
@@ -486,6 +703,13 @@ pub struct PrinterStateFlags {
     pub closed_or_error: bool,
 }
 
+/// The body of a `POST /api/printer/command` request, see
+/// [`Printer::send_gcode`](crate::Printer::send_gcode).
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GcodeCommand {
+    pub commands: Vec<String>,
+}
+
 //
 //  INFO: PRINTHEAD
 //
@@ -565,10 +789,81 @@ impl PrintheadCommand {
 //  NOTE: TOOL TEMP
 //
 
+/// A tool of the printer, called `tool0`, `tool1`, ... by OctoPrint.
+///
+/// It parses from and serializes to the name OctoPrint uses:
+///
+/// ```
+/// use octoprint_rs::types::ToolId;
+///
+/// let tool: ToolId = "tool1".parse().unwrap();
+/// assert_eq!(tool, ToolId::new(1));
+/// assert_eq!(tool.to_string(), "tool1");
+/// assert!("bed".parse::<ToolId>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ToolId(u32);
+
+impl ToolId {
+    pub fn new(index: u32) -> Self {
+        ToolId(index)
+    }
+
+    /// The index of the tool, `0` for `tool0`.
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for ToolId {
+    fn from(index: u32) -> Self {
+        ToolId(index)
+    }
+}
+
+impl fmt::Display for ToolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tool{}", self.0)
+    }
+}
+
+impl FromStr for ToolId {
+    type Err = crate::errors::InvalidToolId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_prefix("tool")
+            .filter(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|index| index.parse().ok())
+            .map(ToolId)
+            .ok_or_else(|| crate::errors::InvalidToolId(s.to_string()))
+    }
+}
+
+impl Serialize for ToolId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Sets the target or offset temperature of one tool, or of many tools at once.
+///
+/// The tools are checked against the extruder count of the current printer profile before
+/// they are sent, always for `Targets` and `Offsets` and for a single `Target` or `Offset` only
+/// if the profiles are cached, see
+/// [`Printer::tool_temperature`](crate::Printer::tool_temperature).
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ToolTempDescriptor {
-    Target { tool: String, temperature: f32 },
-    Offset { tool: String, amount: f32 },
+    Target { tool: ToolId, temperature: f32 },
+    Offset { tool: ToolId, amount: f32 },
+    Targets(BTreeMap<ToolId, f32>),
+    Offsets(BTreeMap<ToolId, f32>),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -579,20 +874,40 @@ pub struct ToolTempCommand {
 }
 
 impl ToolTempDescriptor {
+    /// Every tool the command changes.
+    pub fn tools(&self) -> Vec<ToolId> {
+        match self {
+            Self::Target { tool, .. } | Self::Offset { tool, .. } => vec![*tool],
+            Self::Targets(values) | Self::Offsets(values) => values.keys().copied().collect(),
+        }
+    }
+
     pub fn to_json(self) -> ToolTempCommand {
+        let names = |values: BTreeMap<ToolId, f32>| {
+            Some(
+                values
+                    .into_iter()
+                    .map(|(tool, value)| (tool.to_string(), value))
+                    .collect(),
+            )
+        };
         match self {
-            Self::Target { tool, temperature } => ToolTempCommand {
+            Self::Target { tool, temperature } => {
+                Self::Targets(BTreeMap::from([(tool, temperature)])).to_json()
+            }
+            Self::Offset { tool, amount } => {
+                Self::Offsets(BTreeMap::from([(tool, amount)])).to_json()
+            }
+            Self::Targets(targets) => ToolTempCommand {
                 command: "target".to_string(),
-                targets: Some(zip(std::iter::once(tool), std::iter::once(temperature))
-                    .collect::<HashMap<_, _>>()),
-                offsets: None
+                targets: names(targets),
+                offsets: None,
             },
-            Self::Offset { tool, amount } => ToolTempCommand {
+            Self::Offsets(offsets) => ToolTempCommand {
                 command: "offset".to_string(),
-                offsets: Some(zip(std::iter::once(tool), std::iter::once(amount))
-                    .collect::<HashMap<_, _>>()),
-                targets: None
-            }
+                offsets: names(offsets),
+                targets: None,
+            },
         }
     }
 }
@@ -608,7 +923,7 @@ impl ToolTempDescriptor {
 ///
 /// * `tools`: A hashmap of the tools names as the key and their states as the value.
 /// * `history`: An optional vector of temperature history entries. The history is only available
-/// if history was requested, otherwise it will be `None`.
+///   if history was requested, otherwise it will be `None`.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ToolState {
     pub history: Option<Vec<TemperatureHistoryEntry>>,
@@ -616,7 +931,9 @@ pub struct ToolState {
     pub tools: HashMap<String, PrinterTool>,
 }
 
+/// Sent as is, e.g. `{"command": "select", "tool": "tool1"}`.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
 pub enum ToolCommand {
     Select {
         command: String,
@@ -669,10 +986,38 @@ impl BedTempDescriptor {
     }
 }
 
+/// The temperature section of `/api/settings`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TemperatureSettings {
+    pub temperature: TemperatureProfiles,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TemperatureProfiles {
+    #[serde(default)]
+    pub profiles: Vec<TemperatureProfile>,
+}
+
+/// A named set of temperatures configured in OctoPrint's settings, like `PLA` or `ABS`.
+///
+/// # Fields
+///
+/// * `extruder`: The target of every extruder in °C.
+/// * `bed`: The target of the bed in °C.
+/// * `chamber`: The target of the chamber in °C, if the profile sets one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemperatureProfile {
+    pub name: String,
+    pub extruder: f32,
+    pub bed: f32,
+    #[serde(default)]
+    pub chamber: Option<f32>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BedTemperatureHistoryEntry {
-    time: u64,
-    bed: PrinterTool
+    pub time: u64,
+    pub bed: PrinterTool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -680,3 +1025,362 @@ pub struct BedState {
     pub history: Option<Vec<BedTemperatureHistoryEntry>>,
     pub bed: PrinterTool,
 }
+
+//
+//  NOTE: PI SUPPORT PLUGIN
+//
+
+/// The information reported by the bundled `pi_support` plugin. This is only available when
+/// OctoPrint is running on a Raspberry Pi.
+///
+/// # Fields
+///
+/// * `model`: The model of the Raspberry Pi, e.g. `Raspberry Pi 3 Model B Rev 1.2`.
+/// * `throttle_state`: The decoded throttle state of the Pi. See [`ThrottleState`].
+/// * `octopi_version`: The OctoPi version if OctoPrint is running on OctoPi, otherwise `None`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PiSupport {
+    pub model: String,
+    #[serde(with = "throttle_state")]
+    pub throttle_state: ThrottleState,
+    pub octopi_version: Option<String>,
+}
+
+/// The decoded throttle state bitmask of a Raspberry Pi, as reported by `vcgencmd get_throttled`.
+///
+/// The `current_*` flags describe the state right now while the `past_*` flags are set if the
+/// condition has occurred at any point since the Pi was booted.
+///
+/// * `undervoltage`: The supply voltage has dropped below 4.63V. This is usually caused by a bad
+///   power supply or cable.
+/// * `freq_capped`: The ARM frequency has been capped.
+/// * `throttled`: The Pi is currently throttled.
+/// * `soft_temp_limit`: The soft temperature limit has been reached.
+/// * `overheat`: Either `throttled` or `soft_temp_limit` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ThrottleState {
+    pub raw: u32,
+    pub current_undervoltage: bool,
+    pub current_freq_capped: bool,
+    pub current_throttled: bool,
+    pub current_soft_temp_limit: bool,
+    pub current_overheat: bool,
+    pub past_undervoltage: bool,
+    pub past_freq_capped: bool,
+    pub past_throttled: bool,
+    pub past_soft_temp_limit: bool,
+    pub past_overheat: bool,
+}
+
+impl ThrottleState {
+    const UNDERVOLTAGE: u32 = 1 << 0;
+    const FREQ_CAPPED: u32 = 1 << 1;
+    const THROTTLED: u32 = 1 << 2;
+    const SOFT_TEMP_LIMIT: u32 = 1 << 3;
+    const PAST_UNDERVOLTAGE: u32 = 1 << 16;
+    const PAST_FREQ_CAPPED: u32 = 1 << 17;
+    const PAST_THROTTLED: u32 = 1 << 18;
+    const PAST_SOFT_TEMP_LIMIT: u32 = 1 << 19;
+
+    /// Decodes the raw throttle bitmask.
+    pub fn from_bits(raw: u32) -> ThrottleState {
+        let set = |flag: u32| raw & flag != 0;
+
+        ThrottleState {
+            raw,
+            current_undervoltage: set(Self::UNDERVOLTAGE),
+            current_freq_capped: set(Self::FREQ_CAPPED),
+            current_throttled: set(Self::THROTTLED),
+            current_soft_temp_limit: set(Self::SOFT_TEMP_LIMIT),
+            current_overheat: set(Self::THROTTLED | Self::SOFT_TEMP_LIMIT),
+            past_undervoltage: set(Self::PAST_UNDERVOLTAGE),
+            past_freq_capped: set(Self::PAST_FREQ_CAPPED),
+            past_throttled: set(Self::PAST_THROTTLED),
+            past_soft_temp_limit: set(Self::PAST_SOFT_TEMP_LIMIT),
+            past_overheat: set(Self::PAST_THROTTLED | Self::PAST_SOFT_TEMP_LIMIT),
+        }
+    }
+
+    /// Returns `true` if any of the current flags are set.
+    pub fn current_issue(&self) -> bool {
+        self.raw & 0xFFFF != 0
+    }
+
+    /// Returns `true` if any of the past flags are set.
+    pub fn past_issue(&self) -> bool {
+        self.raw >> 16 != 0
+    }
+}
+
+/// Older versions of the Pi Support plugin report the throttle state as a hex string like
+/// `"0x50005"`, current ones as an object with the `raw_value` and some decoded flags. Both are
+/// read, and the state is written as a hex string.
+mod throttle_state {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::ThrottleState;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Hex(String),
+        Decoded {
+            raw_value: i64,
+            #[serde(default)]
+            current_undervoltage: bool,
+            #[serde(default)]
+            current_overheat: bool,
+            #[serde(default)]
+            past_undervoltage: bool,
+            #[serde(default)]
+            past_overheat: bool,
+        },
+    }
+
+    pub fn serialize<S: Serializer>(
+        state: &ThrottleState,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:#x}", state.raw))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ThrottleState, D::Error> {
+        let text = match Raw::deserialize(deserializer)? {
+            Raw::Hex(text) => text,
+            Raw::Decoded { raw_value, .. } if u32::try_from(raw_value).is_ok() => {
+                return Ok(ThrottleState::from_bits(raw_value as u32));
+            }
+            // The plugin could not read the state, only the flags it decoded are known. They
+            // are turned into the bits they stand for, so every field agrees with `raw` and the
+            // state survives being written out again.
+            Raw::Decoded {
+                current_undervoltage,
+                current_overheat,
+                past_undervoltage,
+                past_overheat,
+                ..
+            } => {
+                let bit = |set: bool, flag: u32| if set { flag } else { 0 };
+
+                return Ok(ThrottleState::from_bits(
+                    bit(current_undervoltage, ThrottleState::UNDERVOLTAGE)
+                        | bit(current_overheat, ThrottleState::THROTTLED)
+                        | bit(past_undervoltage, ThrottleState::PAST_UNDERVOLTAGE)
+                        | bit(past_overheat, ThrottleState::PAST_THROTTLED),
+                ));
+            }
+        };
+        let digits = text
+            .strip_prefix("0x")
+            .or_else(|| text.strip_prefix("0X"))
+            .unwrap_or(&text);
+
+        u32::from_str_radix(digits, 16)
+            .map(ThrottleState::from_bits)
+            .map_err(|e| D::Error::custom(format!("invalid throttle state {:?}: {}", text, e)))
+    }
+}
+
+//
+//  NOTE: ACTION COMMAND PROMPT PLUGIN
+//
+
+/// A prompt shown by the printer through `//action:prompt_*` action commands, for example when
+/// the filament runs out or during an `M600` filament change.
+///
+/// # Fields
+///
+/// * `text`: The message of the prompt.
+/// * `choices`: The available choices. Answer the prompt with the index of one of these using
+///   [`select_prompt_choice()`](crate::Printer::select_prompt_choice).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ActionPrompt {
+    pub text: String,
+    pub choices: Vec<String>,
+}
+
+/// The plugin responds with an empty object if there is no active prompt.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawActionPrompt {
+    text: Option<String>,
+    #[serde(default)]
+    choices: Vec<String>,
+}
+
+impl RawActionPrompt {
+    pub fn to_prompt(self) -> Option<ActionPrompt> {
+        self.text.map(|text| ActionPrompt {
+            text,
+            choices: self.choices,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ActionPromptCommand {
+    command: String,
+    choice: usize,
+}
+
+impl ActionPromptCommand {
+    pub fn select(choice: usize) -> ActionPromptCommand {
+        ActionPromptCommand {
+            command: "select".to_string(),
+            choice,
+        }
+    }
+}
+
+//
+//  NOTE: LANGUAGE PACKS
+//
+
+/// The installed language packs, keyed by the identifier of the component they translate.
+/// `_core` is OctoPrint itself, every other key is a plugin identifier.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LanguagePacks {
+    pub language_packs: HashMap<String, LanguagePackComponent>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LanguagePackComponent {
+    pub identifier: String,
+    pub display: String,
+    #[serde(default)]
+    pub languages: Vec<LanguagePack>,
+}
+
+/// A single installed translation.
+///
+/// # Fields
+///
+/// * `locale`: The locale of the translation, e.g. `de`. Use this together with the component
+///   identifier to delete the pack.
+/// * `last_update`: When the pack was last updated, as a unix timestamp.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LanguagePack {
+    pub locale: String,
+    pub locale_display: String,
+    pub locale_english: String,
+    pub last_update: Option<u64>,
+    pub author: Option<String>,
+}
+
+//
+//  NOTE: WEBCAMS
+//
+
+/// The webcams configured on the server, as returned by `/api/webcams` (OctoPrint 1.9+).
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Webcams {
+    #[serde(default)]
+    pub webcams: Vec<Webcam>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Webcam {
+    pub name: String,
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub can_snapshot: bool,
+    #[serde(default)]
+    pub flip_h: bool,
+    #[serde(default)]
+    pub flip_v: bool,
+    #[serde(default)]
+    pub rotate90: bool,
+    pub compat: Option<WebcamCompat>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WebcamCompat {
+    pub snapshot: Option<String>,
+    pub stream: Option<String>,
+}
+
+impl Webcam {
+    pub fn orientation(&self) -> WebcamOrientation {
+        WebcamOrientation {
+            flip_h: self.flip_h,
+            flip_v: self.flip_v,
+            rotate_90: self.rotate90,
+        }
+    }
+}
+
+/// The webcam section of `/api/settings`, used by servers older than 1.9.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WebcamSettings {
+    pub webcam: LegacyWebcam,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyWebcam {
+    pub snapshot_url: Option<String>,
+    #[serde(default)]
+    pub flip_h: bool,
+    #[serde(default)]
+    pub flip_v: bool,
+    #[serde(default)]
+    pub rotate90: bool,
+}
+
+impl LegacyWebcam {
+    pub fn orientation(&self) -> WebcamOrientation {
+        WebcamOrientation {
+            flip_h: self.flip_h,
+            flip_v: self.flip_v,
+            rotate_90: self.rotate90,
+        }
+    }
+}
+
+/// How the image of a webcam has to be transformed to be displayed the right way up.
+///
+/// * `flip_h`: The image should be flipped horizontally.
+/// * `flip_v`: The image should be flipped vertically.
+/// * `rotate_90`: The image should be rotated 90 degrees counter clockwise.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WebcamOrientation {
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub rotate_90: bool,
+}
+
+/// A single image taken from a webcam.
+///
+/// # Fields
+///
+/// * `image`: The raw image, usually a JPEG.
+/// * `content_type`: The content type reported by the webcam, if any.
+/// * `orientation`: The orientation configured for the webcam. The image is not transformed,
+///   this is up to you.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub image: bytes::Bytes,
+    pub content_type: Option<String>,
+    pub orientation: WebcamOrientation,
+}
+
+//
+//  NOTE: EVENTS
+//
+
+/// An event of the OctoPrint event bus, like `PrintStarted`, `PrintDone` or `ZChange`.
+///
+/// # Fields
+///
+/// * `event_type`: The name of the event.
+/// * `payload`: The payload of the event. Its fields depend on the type of the event, e.g.
+///   `ZChange` has the `old` and the `new` Z height.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Event {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
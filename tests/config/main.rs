@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use octoprint_rs::config::*;
+use octoprint_rs::errors::*;
+use octoprint_rs::PrinterBuilder;
+use tokio::test;
+
+fn config_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("octoprint-config-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("secrets")).unwrap();
+    dir
+}
+
+#[test]
+async fn load_json_with_key_file() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/octoprint/api/version")
+        .match_header("X-Api-Key", "KEY_FROM_FILE")
+        .with_status(200)
+        .with_body(r#"{"api": "0.1", "server": "1.9.3", "text": "OctoPrint 1.9.3"}"#)
+        .create_async()
+        .await;
+
+    let dir = config_dir("json");
+    std::fs::write(dir.join("secrets/farm.key"), "KEY_FROM_FILE\n").unwrap();
+    std::fs::write(
+        dir.join("printers.json"),
+        format!(
+            r#"{{
+  "printers": {{
+    "farm": {{
+      "base_url": "{}/octoprint/",
+      "api_key_file": "secrets/farm.key",
+      "request_timeout_secs": 2.5
+    }}
+  }}
+}}"#,
+            server.url()
+        ),
+    )
+    .unwrap();
+
+    let config = FleetConfig::load(dir.join("printers.json")).unwrap();
+    assert_eq!(
+        config.printers["farm"].api_key_file,
+        Some(dir.join("secrets/farm.key"))
+    );
+
+    let fleet = config.fleet().unwrap();
+    let printer = fleet.get("farm").unwrap();
+    assert_eq!(printer.get_api_version().await.unwrap().server, "1.9.3");
+
+    mock.assert_async().await;
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+async fn api_key_from_env() {
+    let config = PrinterConfig {
+        address: Some("10.0.0.11".to_string()),
+        api_key_env: Some("OCTOPRINT_RS_TEST_UNSET_KEY".to_string()),
+        ..PrinterConfig::default()
+    };
+    assert!(matches!(
+        PrinterBuilder::from_config(&config),
+        Err(ConfigError::MissingEnvVar(name)) if name == "OCTOPRINT_RS_TEST_UNSET_KEY"
+    ));
+
+    std::env::set_var("OCTOPRINT_RS_TEST_KEY", "KEY_FROM_ENV");
+    let config = PrinterConfig {
+        api_key_env: Some("OCTOPRINT_RS_TEST_KEY".to_string()),
+        ..config
+    };
+    assert!(PrinterBuilder::from_config(&config).is_ok());
+}
+
+#[test]
+async fn api_key_redacted_from_debug() {
+    let config = PrinterConfig {
+        address: Some("10.0.0.11".to_string()),
+        api_key: Some("SECRET_INLINE_KEY".to_string()),
+        ..PrinterConfig::default()
+    };
+    let fleet = FleetConfig {
+        printers: [("prusa-1".to_string(), config)].into(),
+    };
+
+    let debug = format!("{:?}", fleet);
+    assert!(!debug.contains("SECRET_INLINE_KEY"));
+    assert!(debug.contains("10.0.0.11"));
+}
+
+#[test]
+async fn invalid_configs() {
+    let both = r#"{"printers": {"a": {"address": "a", "base_url": "http://a/", "api_key": "K"}}}"#;
+    match FleetConfig::from_json_str(both).unwrap().builders() {
+        Err(ConfigError::Printer { name, source }) => {
+            assert_eq!(name, "a");
+            assert!(matches!(*source, ConfigError::MissingAddress));
+        }
+        other => panic!("expected a printer error, got {:?}", other.err()),
+    }
+
+    let no_key = r#"{"printers": {"a": {"address": "a"}}}"#;
+    assert!(matches!(
+        FleetConfig::from_json_str(no_key).unwrap().builders(),
+        Err(ConfigError::Printer { source, .. }) if matches!(*source, ConfigError::MissingApiKey)
+    ));
+
+    let typo = r#"{"printers": {"a": {"adress": "a", "api_key": "K"}}}"#;
+    match FleetConfig::from_json_str(typo) {
+        Err(ConfigError::Parse(message)) => assert!(message.contains("adress"), "{}", message),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    let bad_pin = PrinterConfig {
+        address: Some("a".to_string()),
+        api_key: Some("K".to_string()),
+        tls: TlsConfig {
+            pin_sha256: Some("abc".to_string()),
+            ..TlsConfig::default()
+        },
+        ..PrinterConfig::default()
+    };
+    assert!(matches!(
+        PrinterBuilder::from_config(&bad_pin),
+        Err(ConfigError::Builder(
+            PrinterBuilderError::InvalidFingerprint(_)
+        ))
+    ));
+
+    let with_base_url = PrinterConfig {
+        base_url: Some("http://a/".to_string()),
+        api_key: Some("K".to_string()),
+        ..PrinterConfig::default()
+    };
+    for config in [
+        PrinterConfig {
+            port: Some(5000),
+            ..with_base_url.clone()
+        },
+        PrinterConfig {
+            https: true,
+            ..with_base_url.clone()
+        },
+        PrinterConfig {
+            base_path: Some("/octoprint".to_string()),
+            ..with_base_url.clone()
+        },
+    ] {
+        match PrinterBuilder::from_config(&config) {
+            Err(ConfigError::Parse(message)) => {
+                assert!(message.contains("base_url"), "{}", message)
+            }
+            other => panic!("expected a parse error, got {:?}", other.err()),
+        }
+    }
+    assert!(PrinterBuilder::from_config(&with_base_url).is_ok());
+
+    let dir = config_dir("invalid");
+    assert!(matches!(
+        FleetConfig::load(dir.join("printers.yaml")),
+        Err(ConfigError::Io { .. })
+    ));
+    std::fs::write(dir.join("printers.yaml"), "printers: {}\n").unwrap();
+    assert!(matches!(
+        FleetConfig::load(dir.join("printers.yaml")),
+        Err(ConfigError::UnsupportedFormat(_))
+    ));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+async fn load_toml() {
+    let dir = config_dir("toml");
+    std::fs::write(
+        dir.join("printers.toml"),
+        r#"
+[printers.prusa-1]
+address = "10.0.0.11"
+port = 5000
+api_key = "INLINE"
+connect_timeout_secs = 3
+
+[printers.ender]
+base_url = "https://farm.example/octoprint-3/"
+api_key_file = "secrets/ender.key"
+
+[printers.ender.tls]
+danger_accept_invalid_certs = true
+"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("secrets/ender.key"), "ENDER").unwrap();
+
+    let config = FleetConfig::load(dir.join("printers.toml")).unwrap();
+    assert_eq!(config.printers["prusa-1"].port, Some(5000));
+    assert!(config.printers["ender"].tls.danger_accept_invalid_certs);
+
+    let fleet = config.fleet().unwrap();
+    assert_eq!(fleet.names().collect::<Vec<_>>(), ["ender", "prusa-1"]);
+    let _ = std::fs::remove_dir_all(dir);
+}
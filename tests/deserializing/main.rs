@@ -1,4 +1,7 @@
-use octoprint_rs::types::{printer_files::Entry, ToolState, TemperatureHistoryEntry};
+use octoprint_rs::types::{
+    printer_files::Entry, printer_profiles::Profiles, PiSupport, RawActionPrompt,
+    TemperatureHistoryEntry, ThrottleState, ToolState, UploadResponse,
+};
 
 #[test]
 fn parse_file() {
@@ -271,6 +274,7 @@ fn parse_gcode_analysis_travel_dimensions() {
 }
 
 #[test]
+#[allow(clippy::excessive_precision)]
 fn parse_gcode_analysis_estimated_print_time() {
     let json = r#"1368.6617568899217"#;
 
@@ -388,3 +392,207 @@ fn parse_printer_temperature_history() {
     assert_eq!(history[0].time, 1395651928);
     assert_eq!(history[1].time, 1395651926);
 }
+
+#[test]
+fn parse_pi_support_throttle_state() {
+    let json = r#"{
+  "model": "Raspberry Pi 4 Model B Rev 1.1",
+  "throttle_state": "0x20000",
+  "octopi_version": null
+}"#;
+
+    let result = &mut serde_json::Deserializer::from_str(json);
+    let deserialized = serde_path_to_error::deserialize(result);
+
+    let pi_support: PiSupport = deserialized.unwrap();
+
+    assert_eq!(pi_support.throttle_state.raw, 0x20000);
+    assert!(pi_support.throttle_state.past_freq_capped);
+    assert!(pi_support.throttle_state.past_issue());
+    assert!(!pi_support.throttle_state.current_issue());
+    assert_eq!(pi_support.octopi_version, None);
+}
+
+#[test]
+fn parse_pi_support_decoded_throttle_state() {
+    // As sent by the Pi Support plugin bundled with OctoPrint 1.9
+    let json = r#"{
+  "model": "Raspberry Pi 3 Model B Rev 1.2",
+  "throttle_state": {
+    "current_issue": true,
+    "current_overheat": true,
+    "current_undervoltage": true,
+    "past_issue": true,
+    "past_overheat": true,
+    "past_undervoltage": true,
+    "raw_value": 327685
+  },
+  "octopi_version": "0.18.0"
+}"#;
+
+    let result = &mut serde_json::Deserializer::from_str(json);
+    let deserialized = serde_path_to_error::deserialize(result);
+
+    let pi_support: PiSupport = deserialized.unwrap();
+
+    assert_eq!(pi_support.throttle_state.raw, 0x50005);
+    assert!(pi_support.throttle_state.current_undervoltage);
+    assert!(pi_support.throttle_state.past_throttled);
+    assert!(pi_support.throttle_state.current_overheat);
+    assert!(!pi_support.throttle_state.current_freq_capped);
+    assert_eq!(pi_support.octopi_version, Some("0.18.0".to_string()));
+}
+
+#[test]
+fn parse_pi_support_unreadable_throttle_state() {
+    // The plugin reports -1 if `vcgencmd` could not be run
+    let json = r#"{
+  "model": "Raspberry Pi 3 Model B Rev 1.2",
+  "throttle_state": {
+    "current_issue": true,
+    "current_overheat": false,
+    "current_undervoltage": true,
+    "past_issue": true,
+    "past_overheat": true,
+    "past_undervoltage": false,
+    "raw_value": -1
+  },
+  "octopi_version": null
+}"#;
+
+    let result = &mut serde_json::Deserializer::from_str(json);
+    let deserialized = serde_path_to_error::deserialize(result);
+
+    let pi_support: PiSupport = deserialized.unwrap();
+
+    assert!(pi_support.throttle_state.current_undervoltage);
+    assert!(!pi_support.throttle_state.current_overheat);
+    assert!(pi_support.throttle_state.past_overheat);
+    assert!(!pi_support.throttle_state.past_undervoltage);
+    assert!(pi_support.throttle_state.current_issue());
+    assert!(pi_support.throttle_state.past_issue());
+
+    // Every field is derived from the same bits, so a round trip gives the same state
+    assert_eq!(
+        pi_support.throttle_state,
+        ThrottleState::from_bits(pi_support.throttle_state.raw)
+    );
+    let written = serde_json::to_string(&pi_support).unwrap();
+    let read: PiSupport = serde_json::from_str(&written).unwrap();
+    assert_eq!(read.throttle_state, pi_support.throttle_state);
+}
+
+#[test]
+fn parse_empty_action_prompt() {
+    let json = r#"{}"#;
+
+    let result = &mut serde_json::Deserializer::from_str(json);
+    let deserialized = serde_path_to_error::deserialize(result);
+
+    let prompt: RawActionPrompt = deserialized.unwrap();
+
+    assert_eq!(prompt.to_prompt(), None);
+}
+
+#[test]
+fn parse_printer_profiles() {
+    let json = r#"{
+  "profiles": {
+    "_default": {
+      "id": "_default",
+      "name": "Default",
+      "color": "default",
+      "model": "Generic RepRap Printer",
+      "default": true,
+      "current": true,
+      "resource": "http://example.com/api/printerprofiles/_default",
+      "volume": {
+        "formFactor": "rectangular",
+        "origin": "lowerleft",
+        "width": 200,
+        "depth": 200,
+        "height": 200,
+        "custom_box": false
+      },
+      "heatedBed": true,
+      "heatedChamber": false,
+      "axes": {
+        "x": { "speed": 6000, "inverted": false },
+        "y": { "speed": 6000, "inverted": false },
+        "z": { "speed": 200, "inverted": false },
+        "e": { "speed": 300, "inverted": false }
+      },
+      "extruder": {
+        "count": 1,
+        "offsets": [[0.0, 0.0]],
+        "nozzleDiameter": 0.4,
+        "sharedNozzle": false,
+        "defaultExtrusionLength": 5
+      }
+    },
+    "my_profile": {
+      "id": "my_profile",
+      "name": "My Profile",
+      "model": "My Custom Printer",
+      "default": false,
+      "current": false,
+      "volume": {
+        "formFactor": "circular",
+        "origin": "center",
+        "width": 180,
+        "depth": 180,
+        "height": 250
+      },
+      "heatedBed": false,
+      "heatedChamber": false,
+      "extruder": {
+        "count": 2,
+        "offsets": [[0.0, 0.0], [18.0, 0.0]],
+        "nozzleDiameter": 0.6
+      }
+    }
+  }
+}"#;
+
+    let result = &mut serde_json::Deserializer::from_str(json);
+    let deserialized = serde_path_to_error::deserialize(result);
+
+    let profiles: Profiles = deserialized.unwrap();
+
+    let current = profiles.current().unwrap();
+    assert_eq!(current.id, "_default");
+    assert_eq!(current.volume.width, 200.0);
+    assert_eq!(current.extruder.nozzle_diameter, 0.4);
+
+    let custom = &profiles.profiles["my_profile"];
+    assert_eq!(custom.volume.form_factor, "circular");
+    assert_eq!(custom.extruder.count, 2);
+    assert_eq!(custom.extruder.offsets[1], [18.0, 0.0]);
+    assert!(!custom.extruder.shared_nozzle);
+}
+
+#[test]
+fn parse_upload_response() {
+    let json = r#"{
+  "files": {
+    "local": {
+      "name": "whistle_v2.gcode",
+      "origin": "local",
+      "refs": {
+        "resource": "http://example.com/api/files/local/whistle_v2.gcode",
+        "download": "http://example.com/downloads/files/local/whistle_v2.gcode"
+      }
+    }
+  },
+  "done": true
+}"#;
+
+    let result = &mut serde_json::Deserializer::from_str(json);
+    let deserialized = serde_path_to_error::deserialize(result);
+
+    let response: UploadResponse = deserialized.unwrap();
+
+    assert!(response.done);
+    assert_eq!(response.files["local"].name, "whistle_v2.gcode");
+    assert_eq!(response.files["local"].path, None);
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "exporter")]
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+use octoprint_rs::api::OctoPrintApi;
+use octoprint_rs::exporter::Exporter;
+use octoprint_rs::fake::FakePrinter;
+use octoprint_rs::fleet::Fleet;
+use octoprint_rs::types::*;
+use tokio::test;
+
+fn lines(metrics: &str, name: &str) -> Vec<String> {
+    metrics
+        .lines()
+        .filter(|line| line.starts_with(name) && line[name.len()..].starts_with('{'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+async fn renders_printer_metrics() {
+    let mut fleet = Fleet::new();
+    fleet.add("prusa-1", FakePrinter::with_tools(2));
+    fleet.add("ender", FakePrinter::new());
+
+    let prusa = fleet.get("prusa-1").unwrap().clone();
+    prusa.add_file(FileLocation::Local, "benchy.gcode", 1000);
+    prusa
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Select { print: true },
+            path: PathDescriptor {
+                location: FileLocation::Local,
+                path: "benchy.gcode".to_string(),
+            },
+        })
+        .await
+        .unwrap();
+    prusa.set_progress(25.0);
+    prusa.set_tool_actual(ToolId::new(1), 205.5);
+    fleet
+        .get("ender")
+        .unwrap()
+        .set_connection(ConnectionCommandDescriptor::Disconnect)
+        .await
+        .unwrap();
+
+    let exporter = Exporter::new(fleet);
+    exporter.poll().await;
+    exporter.poll().await;
+    let metrics = exporter.render();
+
+    assert_eq!(
+        lines(&metrics, "octoprint_up"),
+        [
+            "octoprint_up{printer=\"ender\"} 1",
+            "octoprint_up{printer=\"prusa-1\"} 1",
+        ]
+    );
+    assert_eq!(
+        lines(&metrics, "octoprint_temperature_actual_celsius"),
+        [
+            "octoprint_temperature_actual_celsius{printer=\"prusa-1\",heater=\"bed\"} 21",
+            "octoprint_temperature_actual_celsius{printer=\"prusa-1\",heater=\"tool0\"} 21",
+            "octoprint_temperature_actual_celsius{printer=\"prusa-1\",heater=\"tool1\"} 205.5",
+        ]
+    );
+    assert!(metrics.contains("octoprint_job_completion_ratio{printer=\"prusa-1\"} 0.25\n"));
+    assert!(metrics.contains("octoprint_printer_state{printer=\"prusa-1\",flag=\"printing\"} 1\n"));
+    assert!(!metrics.contains("octoprint_printer_state{printer=\"ender\""));
+    assert!(metrics.contains("octoprint_storage_total_bytes{printer=\"ender\"} 16000000000\n"));
+    assert!(metrics.contains(
+        "octoprint_request_duration_seconds_count{printer=\"ender\",endpoint=\"api/printer\"} 2\n"
+    ));
+    // A disconnected printer is not an error
+    assert!(metrics.contains(
+        "octoprint_request_errors_total{printer=\"ender\",endpoint=\"api/printer\"} 0\n"
+    ));
+}
+
+#[test]
+async fn serves_metrics() {
+    let mut fleet = Fleet::new();
+    fleet.add("prusa-1", FakePrinter::new());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(
+        Exporter::new(fleet)
+            .interval(Duration::from_millis(10))
+            .serve(listener),
+    );
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let response = reqwest::get(format!("{}/metrics", url)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let metrics = response.text().await.unwrap();
+    assert!(metrics.contains("# TYPE octoprint_up gauge\noctoprint_up{printer=\"prusa-1\"} 1\n"));
+
+    let missing = reqwest::get(format!("{}/", url)).await.unwrap();
+    assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+    server.abort();
+}
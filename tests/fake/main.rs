@@ -0,0 +1,393 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use octoprint_rs::api::OctoPrintApi;
+use octoprint_rs::errors::*;
+use octoprint_rs::fake::FakePrinter;
+use octoprint_rs::types::printer_files::Entry;
+use octoprint_rs::types::*;
+use tokio::test;
+use tokio::time::Instant;
+
+fn local(path: &str) -> PathDescriptor {
+    PathDescriptor {
+        location: FileLocation::Local,
+        path: path.to_string(),
+    }
+}
+
+fn select(path: &str, print: bool) -> FileCommandDescriptor {
+    FileCommandDescriptor {
+        command: FileCommand::Select { print },
+        path: local(path),
+    }
+}
+
+/// Generic over the api, like application code would be
+async fn state_of(printer: &impl OctoPrintApi) -> String {
+    printer.get_job().await.unwrap().state
+}
+
+#[test]
+async fn job_lifecycle() {
+    let printer = FakePrinter::new();
+    printer.add_file(FileLocation::Local, "benchy.gcode", 2000);
+
+    assert!(matches!(
+        printer.issue_job_command(JobCommand::Start).await,
+        Err(JobCommandError::Conflict(_))
+    ));
+
+    printer
+        .issue_file_command(select("benchy.gcode", false))
+        .await
+        .unwrap();
+    printer.issue_job_command(JobCommand::Start).await.unwrap();
+    assert_eq!(state_of(&printer).await, "Printing");
+
+    printer.issue_job_command(JobCommand::Pause).await.unwrap();
+    assert_eq!(state_of(&printer).await, "Paused");
+
+    printer.issue_job_command(JobCommand::Resume).await.unwrap();
+    printer.set_progress(25.0);
+
+    let job = printer.get_job().await.unwrap();
+    assert_eq!(job.progress.completion, Some(25.0));
+    assert_eq!(job.progress.filepos, Some(500));
+    assert_eq!(job.job.file.name.as_deref(), Some("benchy.gcode"));
+
+    printer.set_progress(100.0);
+    assert_eq!(state_of(&printer).await, "Operational");
+}
+
+#[test]
+async fn files() {
+    let printer = FakePrinter::new();
+    printer.add_file(FileLocation::Local, "parts/a.gcode", 10);
+    printer.add_file(FileLocation::Local, "b.gcode", 20);
+
+    let files = printer
+        .get_files(FilesFetchDescriptor {
+            location: FilesLocation::Local,
+            recursive: true,
+            force: false,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(files.files.len(), 2);
+    assert!(files.files.iter().any(|entry| matches!(
+        entry,
+        Entry::Folder { name, children, .. } if name == "parts" && children.len() == 1
+    )));
+
+    printer
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Copy {
+                destination: "parts".to_string(),
+            },
+            path: local("b.gcode"),
+        })
+        .await
+        .unwrap();
+    printer.delete_file(local("b.gcode")).await.unwrap();
+
+    let moved = printer
+        .get_file(FileFetchDescriptor {
+            path: local("parts/b.gcode"),
+            recursive: false,
+            force: false,
+        })
+        .await
+        .unwrap();
+    assert!(matches!(moved, Entry::File { size: Some(20), .. }));
+
+    assert!(matches!(
+        printer.delete_file(local("b.gcode")).await,
+        Err(FileDeletionError::NotFound(_))
+    ));
+}
+
+#[test]
+async fn overwriting_keeps_free_space() {
+    let printer = FakePrinter::new();
+    let free = || async {
+        printer
+            .get_files(FilesFetchDescriptor {
+                location: FilesLocation::Local,
+                recursive: false,
+                force: false,
+            })
+            .await
+            .unwrap()
+            .free
+    };
+    let upload = |size: usize| FileUploadDescriptor {
+        path: local("a.gcode"),
+        contents: vec![b';'; size],
+        select: false,
+        print: false,
+    };
+
+    let before = free().await;
+    printer.upload_file(upload(100)).await.unwrap();
+    assert_eq!(free().await, before - 100);
+
+    printer.upload_file(upload(40)).await.unwrap();
+    assert_eq!(free().await, before - 40);
+
+    printer.delete_file(local("a.gcode")).await.unwrap();
+    assert_eq!(free().await, before);
+}
+
+#[test]
+async fn printed_file_is_locked() {
+    let printer = FakePrinter::new();
+    printer.add_file(FileLocation::Local, "benchy.gcode", 2000);
+
+    printer
+        .issue_file_command(select("benchy.gcode", true))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        printer.delete_file(local("benchy.gcode")).await,
+        Err(FileDeletionError::Conflict(_))
+    ));
+    assert!(matches!(
+        printer
+            .issue_file_command(select("benchy.gcode", true))
+            .await,
+        Err(FileCommandError::Conflict(_))
+    ));
+}
+
+#[test]
+async fn temperatures() {
+    let printer = FakePrinter::with_tools(2);
+
+    printer
+        .tool_temperature(ToolTempDescriptor::Target {
+            tool: ToolId::new(1),
+            temperature: 210.0,
+        })
+        .await
+        .unwrap();
+    printer
+        .change_bed_temp(BedTempDescriptor::Target { target: 60.0 })
+        .await
+        .unwrap();
+    printer.set_tool_actual(ToolId::new(1), 180.0);
+
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool1"].target, Some(210.0));
+    assert_eq!(tools.tools["tool1"].actual, 180.0);
+    assert_eq!(tools.tools["tool0"].target, Some(0.0));
+
+    let bed = printer.get_bed_state(None).await.unwrap();
+    assert_eq!(bed.bed.target, Some(60.0));
+
+    assert!(matches!(
+        printer
+            .tool_temperature(ToolTempDescriptor::Target {
+                tool: ToolId::new(2),
+                temperature: 210.0,
+            })
+            .await,
+        Err(ToolCommandError::BadRequest(_))
+    ));
+
+    printer
+        .tool_temperature(ToolTempDescriptor::Offsets(BTreeMap::from([
+            (ToolId::new(0), -5.0),
+            (ToolId::new(1), 5.0),
+        ])))
+        .await
+        .unwrap();
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool0"].offset, Some(-5.0));
+    assert_eq!(tools.tools["tool1"].offset, Some(5.0));
+}
+
+/// Sets the temperature of `tool0` to each of `steps` in turn, one every two seconds
+fn ramp(printer: &Arc<FakePrinter>, steps: &'static [f32]) {
+    let printer = printer.clone();
+    tokio::spawn(async move {
+        for actual in steps {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            printer.set_tool_actual(ToolId::new(0), *actual);
+        }
+    });
+}
+
+#[test(start_paused = true)]
+async fn heat_and_wait() {
+    let printer = Arc::new(FakePrinter::new());
+    ramp(&printer, &[120.0, 200.0, 209.0, 210.5]);
+
+    let started = Instant::now();
+    let actual = printer
+        .heat_and_wait(
+            ToolId::new(0),
+            210.0,
+            2.0,
+            Duration::from_secs(3),
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(actual, 210.5);
+    assert!(started.elapsed() >= Duration::from_secs(9));
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool0"].target, Some(210.0));
+}
+
+#[test(start_paused = true)]
+async fn heat_and_wait_fails() {
+    let printer = Arc::new(FakePrinter::new());
+
+    let error = printer
+        .heat_bed_and_wait(60.0, 2.0, Duration::from_secs(5), Duration::from_secs(30))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        HeatError::Timeout { ref heater, actual, .. } if heater == "bed" && actual < 60.0
+    ));
+
+    ramp(&printer, &[150.0, 180.0, 165.0]);
+    let error = printer
+        .heat_and_wait(
+            ToolId::new(0),
+            210.0,
+            2.0,
+            Duration::from_secs(5),
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        HeatError::ThermalRunawaySuspected { actual, .. } if actual == 165.0
+    ));
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool0"].target, Some(0.0));
+
+    let error = printer
+        .heat_bed_and_wait(0.0, 2.0, Duration::from_secs(5), Duration::from_secs(30))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        HeatError::Request(OctoPrintError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+async fn disconnected() {
+    let printer = FakePrinter::new();
+
+    printer
+        .set_connection(ConnectionCommandDescriptor::Disconnect)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        printer.get_printer_telemetry().await,
+        Err(DeviceStateError::Conflict(_))
+    ));
+    assert_eq!(
+        printer.get_connection().await.unwrap().current.state,
+        "Closed"
+    );
+}
+
+#[test]
+async fn temperature_profiles() {
+    let printer = FakePrinter::with_tools(2);
+
+    let profiles = printer.get_temperature_profiles().await.unwrap();
+    assert_eq!(profiles.len(), 2);
+
+    printer.apply_temperature_profile("PLA").await.unwrap();
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool0"].target, Some(180.0));
+    assert_eq!(tools.tools["tool1"].target, Some(180.0));
+    let bed = printer.get_bed_state(None).await.unwrap();
+    assert_eq!(bed.bed.target, Some(60.0));
+
+    assert!(matches!(
+        printer.apply_temperature_profile("PETG").await,
+        Err(TemperatureProfileError::UnknownProfile(ref name)) if name == "PETG"
+    ));
+
+    printer.cool_down().await.unwrap();
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool1"].target, Some(0.0));
+    let bed = printer.get_bed_state(None).await.unwrap();
+    assert_eq!(bed.bed.target, Some(0.0));
+}
+
+#[test]
+async fn plugins() {
+    let printer = FakePrinter::new();
+
+    assert!(matches!(
+        printer.get_pi_support().await,
+        Err(InformationRequestError::NotFound(_))
+    ));
+    printer.set_pi_support(PiSupport {
+        model: "Raspberry Pi 4 Model B Rev 1.1".to_string(),
+        throttle_state: ThrottleState::from_bits(0x50005),
+        octopi_version: Some("1.0.0".to_string()),
+    });
+    let pi_support = printer.get_pi_support().await.unwrap();
+    assert!(pi_support.throttle_state.current_undervoltage);
+
+    assert_eq!(printer.get_action_prompt().await.unwrap(), None);
+    assert!(matches!(
+        printer.select_prompt_choice(0).await,
+        Err(PromptCommandError::Conflict(_))
+    ));
+    printer.show_prompt(ActionPrompt {
+        text: "Filament changed?".to_string(),
+        choices: vec!["Continue".to_string(), "Purge more".to_string()],
+    });
+    let prompt = printer.get_action_prompt().await.unwrap().unwrap();
+    assert_eq!(prompt.choices.len(), 2);
+    assert!(matches!(
+        printer.select_prompt_choice(2).await,
+        Err(PromptCommandError::BadRequest(_))
+    ));
+    printer.select_prompt_choice(1).await.unwrap();
+    assert_eq!(printer.get_action_prompt().await.unwrap(), None);
+    assert_eq!(printer.prompt_answers(), [1]);
+}
+
+#[test]
+async fn snapshot() {
+    let printer = FakePrinter::new();
+
+    assert!(printer.get_webcams().await.unwrap().webcams.is_empty());
+    assert!(matches!(
+        printer.snapshot().await,
+        Err(SnapshotError::NoSnapshotUrl)
+    ));
+
+    printer.set_snapshot(Snapshot {
+        image: bytes::Bytes::from_static(b"\xff\xd8\xff"),
+        content_type: Some("image/jpeg".to_string()),
+        orientation: WebcamOrientation {
+            flip_h: true,
+            flip_v: false,
+            rotate_90: false,
+        },
+    });
+    let webcams = printer.get_webcams().await.unwrap();
+    assert!(webcams.webcams[0].can_snapshot);
+    assert!(webcams.webcams[0].flip_h);
+    let snapshot = printer.snapshot().await.unwrap();
+    assert_eq!(snapshot.image.as_ref(), b"\xff\xd8\xff");
+}
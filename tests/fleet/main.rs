@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use octoprint_rs::api::OctoPrintApi;
+use octoprint_rs::errors::*;
+use octoprint_rs::fake::FakePrinter;
+use octoprint_rs::fleet::Fleet;
+use octoprint_rs::types::*;
+use tokio::test;
+
+fn fleet(names: &[&str]) -> Fleet<FakePrinter> {
+    let mut fleet = Fleet::new();
+    for name in names {
+        fleet.add(*name, FakePrinter::new());
+    }
+    fleet
+}
+
+#[test]
+async fn snapshot_collects_every_printer() {
+    let fleet = fleet(&["prusa-1", "prusa-2", "ender"]);
+    let printing = fleet.get("prusa-2").unwrap();
+    printing.add_file(FileLocation::Local, "benchy.gcode", 1000);
+    printing
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Select { print: true },
+            path: PathDescriptor {
+                location: FileLocation::Local,
+                path: "benchy.gcode".to_string(),
+            },
+        })
+        .await
+        .unwrap();
+    fleet
+        .get("ender")
+        .unwrap()
+        .set_connection(ConnectionCommandDescriptor::Disconnect)
+        .await
+        .unwrap();
+
+    let snapshot = fleet.snapshot().await;
+    assert_eq!(snapshot.len(), 3);
+    assert!(!snapshot.all_ok());
+
+    let states: Vec<_> = snapshot
+        .ok()
+        .map(|(name, s)| (name, s.job.state.as_str()))
+        .collect();
+    assert_eq!(
+        states,
+        [("prusa-1", "Operational"), ("prusa-2", "Printing")]
+    );
+
+    let errors: Vec<_> = snapshot.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "ender");
+    assert_eq!(errors[0].1.status(), Some(reqwest::StatusCode::CONFLICT));
+}
+
+#[test]
+async fn run_respects_concurrency() {
+    let names: Vec<String> = (0..10).map(|i| format!("printer-{}", i)).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let fleet = fleet(&names).concurrency(3);
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let results = fleet
+        .run(|printer| {
+            let running = running.clone();
+            let most = most.clone();
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                printer.get_api_version().await
+            }
+        })
+        .await;
+
+    assert!(results.all_ok());
+    assert_eq!(results.len(), 10);
+    assert_eq!(most.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+async fn run_keeps_typed_errors() {
+    let fleet = fleet(&["a", "b"]);
+    fleet
+        .get("a")
+        .unwrap()
+        .add_file(FileLocation::Local, "part.gcode", 10);
+
+    let results = fleet
+        .run(|printer| async move {
+            printer
+                .delete_file(PathDescriptor {
+                    location: FileLocation::Local,
+                    path: "part.gcode".to_string(),
+                })
+                .await
+        })
+        .await;
+
+    assert!(matches!(results.get("a"), Some(Ok(()))));
+    assert!(matches!(
+        results.get("b"),
+        Some(Err(FileDeletionError::NotFound(_)))
+    ));
+    let names: Vec<String> = results.into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["a", "b"]);
+}
@@ -0,0 +1,383 @@
+use std::path::PathBuf;
+
+use octoprint_rs::api::OctoPrintApi;
+use octoprint_rs::fake::FakePrinter;
+use octoprint_rs::fleet::Fleet;
+use octoprint_rs::history::*;
+use octoprint_rs::types::*;
+use octoprint_rs::PrinterBuilder;
+use tokio::test;
+
+fn history_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("octoprint-history-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Serves `tools` and `bed` as the tool and bed state of a printer, replacing what was served
+/// before
+async fn serve_state(
+    server: &mut mockito::ServerGuard,
+    tools: serde_json::Value,
+    bed: serde_json::Value,
+) -> Vec<mockito::Mock> {
+    server.reset();
+    let mut mocks = vec![];
+    for (path, body) in [("/api/printer/tool", tools), ("/api/printer/bed", bed)] {
+        mocks.push(
+            server
+                .mock("GET", path)
+                .match_query(mockito::Matcher::Any)
+                .with_status(200)
+                .with_body(body.to_string())
+                .create_async()
+                .await,
+        );
+    }
+    mocks
+}
+
+/// Two overlapping windows of OctoPrint's history, the second one a sample later than the first
+fn windows() -> (Vec<TemperatureRecord>, Vec<TemperatureRecord>) {
+    let tools: ToolState = serde_json::from_value(serde_json::json!({
+        "tool0": { "actual": 200.0, "target": 210.0, "offset": 0 },
+        "history": [
+            { "time": 1000, "tool0": { "actual": 180.0, "target": 210.0 } },
+            { "time": 1002, "tool0": { "actual": 190.0, "target": 210.0 } },
+            { "time": 1004, "tool0": { "actual": 200.0, "target": 210.0 } },
+        ]
+    }))
+    .unwrap();
+    let bed: BedState = serde_json::from_value(serde_json::json!({
+        "bed": { "actual": 58.5, "target": 60.0, "offset": 0 },
+        "history": [
+            { "time": 1002, "bed": { "actual": 55.0, "target": 60.0 } },
+            { "time": 1004, "bed": { "actual": 58.5, "target": 60.0 } },
+        ]
+    }))
+    .unwrap();
+    let first = merge_history(&tools, Some(&bed));
+
+    let tools: ToolState = serde_json::from_value(serde_json::json!({
+        "tool0": { "actual": 205.0, "target": 210.0, "offset": 0 },
+        "history": [
+            { "time": 1004, "tool0": { "actual": 200.0, "target": 210.0 } },
+            { "time": 1006, "tool0": { "actual": 205.0, "target": 210.0 } },
+        ]
+    }))
+    .unwrap();
+    (first, merge_history(&tools, None))
+}
+
+#[test]
+async fn merges_tool_and_bed_history() {
+    let (first, second) = windows();
+
+    assert_eq!(
+        first.iter().map(|r| r.time).collect::<Vec<_>>(),
+        [1000, 1002, 1004]
+    );
+    assert!(!first[0].heaters.contains_key("bed"));
+    assert_eq!(first[1].heaters["tool0"].actual, 190.0);
+    assert_eq!(first[1].heaters["bed"].actual, 55.0);
+    assert_eq!(second.len(), 2);
+    assert!(!second[1].heaters.contains_key("bed"));
+}
+
+#[test]
+async fn holds_back_samples_not_in_both_windows() {
+    let bed: BedState = serde_json::from_value(serde_json::json!({
+        "bed": { "actual": 58.5, "target": 60.0, "offset": 0 },
+        "history": [
+            { "time": 1002, "bed": { "actual": 55.0, "target": 60.0 } },
+        ]
+    }))
+    .unwrap();
+    let tools: ToolState = serde_json::from_value(serde_json::json!({
+        "tool0": { "actual": 200.0, "target": 210.0, "offset": 0 },
+        "history": [
+            { "time": 1002, "tool0": { "actual": 190.0, "target": 210.0 } },
+            { "time": 1004, "tool0": { "actual": 200.0, "target": 210.0 } },
+        ]
+    }))
+    .unwrap();
+
+    let records = merge_history(&tools, Some(&bed));
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].time, 1002);
+    assert_eq!(records[0].heaters.len(), 2);
+}
+
+#[test]
+async fn writes_only_new_records() {
+    let (first, second) = windows();
+
+    let mut csv = HistoryWriter::new(vec![], HistoryFormat::Csv);
+    assert_eq!(csv.write(&first).unwrap(), 3);
+    assert_eq!(csv.write(&second).unwrap(), 1);
+    assert_eq!(csv.write(&second).unwrap(), 0);
+    assert_eq!(csv.last_time(), Some(1006));
+    assert_eq!(
+        String::from_utf8(csv.into_inner()).unwrap(),
+        "time,bed_actual,bed_target,tool0_actual,tool0_target\n\
+         1000,,,180,210\n\
+         1002,55,60,190,210\n\
+         1004,58.5,60,200,210\n\
+         1006,,,205,210\n"
+    );
+
+    let mut jsonl = HistoryWriter::new(vec![], HistoryFormat::JsonLines);
+    jsonl.write(&first).unwrap();
+    jsonl.write(&second).unwrap();
+    let output = String::from_utf8(jsonl.into_inner()).unwrap();
+    let records: Vec<TemperatureRecord> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[1], first[1]);
+    assert_eq!(records[3], second[1]);
+}
+
+#[test]
+async fn appends_to_existing_files() {
+    let (first, second) = windows();
+    let dir = history_dir("append");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    for format in [HistoryFormat::Csv, HistoryFormat::JsonLines] {
+        let path = dir.join(format!("printer.{}", format.extension()));
+        let mut writer = HistoryWriter::append(&path, format).unwrap();
+        assert_eq!(writer.write(&first).unwrap(), 3);
+        drop(writer);
+
+        let mut writer = HistoryWriter::append(&path, format).unwrap();
+        assert_eq!(writer.last_time(), Some(1004));
+        assert_eq!(writer.write(&second).unwrap(), 1);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        match format {
+            HistoryFormat::Csv => {
+                assert_eq!(
+                    lines[0],
+                    "time,bed_actual,bed_target,tool0_actual,tool0_target"
+                );
+                assert_eq!(lines[1], "1000,,,180,210");
+                assert_eq!(lines[4], "1006,,,205,210");
+            }
+            HistoryFormat::JsonLines => assert_eq!(lines.len(), 4),
+        }
+    }
+}
+
+#[test]
+async fn csv_rejects_new_heaters() {
+    let (first, _) = windows();
+    let mut later = first[2].clone();
+    later.time = 1006;
+    later
+        .heaters
+        .insert("chamber".to_string(), later.heaters["bed"].clone());
+
+    let mut csv = HistoryWriter::new(vec![], HistoryFormat::Csv);
+    csv.write(&first).unwrap();
+    assert!(!csv.fits(&[later.clone()]));
+    assert_eq!(
+        csv.write(&[later.clone()]).unwrap_err().kind(),
+        std::io::ErrorKind::InvalidData
+    );
+    assert_eq!(csv.last_time(), Some(1004));
+
+    let jsonl = HistoryWriter::new(vec![], HistoryFormat::JsonLines);
+    assert!(jsonl.fits(&[later]));
+}
+
+#[test]
+async fn recorder_paths_stay_in_its_directory() {
+    let dir = history_dir("paths");
+    let recorder = HistoryRecorder::new(&dir);
+
+    assert_eq!(recorder.path("prusa mk3"), dir.join("prusa_mk3.csv"));
+    let outside = recorder.path("../../etc/passwd");
+    assert_eq!(outside.parent(), Some(dir.as_path()));
+}
+
+#[test]
+async fn recorder_skips_printers_without_history() {
+    let disconnected = FakePrinter::new();
+    disconnected
+        .set_connection(ConnectionCommandDescriptor::Disconnect)
+        .await
+        .unwrap();
+    let mut fleet = Fleet::new();
+    fleet.add("connected", FakePrinter::new());
+    fleet.add("disconnected", disconnected);
+
+    let dir = history_dir("recorder");
+    let mut recorder = HistoryRecorder::new(&dir);
+    let results = recorder.record(&fleet).await;
+
+    assert_eq!(results.len(), 2);
+    for (_, written) in results {
+        assert_eq!(written.unwrap(), 0);
+    }
+    assert!(!dir.exists());
+}
+
+#[cfg(feature = "test-server")]
+#[test]
+async fn recorder_writes_a_continuous_log() {
+    use octoprint_rs::test_server::TestServerBuilder;
+
+    let server = TestServerBuilder::new().tools(2).start();
+    let mut fleet = Fleet::new();
+    fleet.add("virtual", server.printer());
+
+    let dir = history_dir("server");
+    let mut recorder = HistoryRecorder::new(&dir).limit(10);
+    let mut written = 0;
+    for _ in 0..2 {
+        written += recorder
+            .record(&fleet)
+            .await
+            .into_iter()
+            .next()
+            .unwrap()
+            .1
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    }
+
+    let content = std::fs::read_to_string(recorder.path("virtual")).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(
+        lines[0],
+        "time,bed_actual,bed_target,tool0_actual,tool0_target,tool1_actual,tool1_target"
+    );
+    assert_eq!(lines.len(), written + 1);
+    let times: Vec<u64> = lines[1..]
+        .iter()
+        .map(|line| line.split(',').next().unwrap().parse().unwrap())
+        .collect();
+    assert!(times.len() >= 2);
+    assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+async fn recorder_moves_files_aside_without_repeating_records() {
+    let mut server = mockito::Server::new_async().await;
+    let mut fleet = Fleet::new();
+    fleet.add(
+        "prusa",
+        PrinterBuilder::from_base_url(server.url(), "KEY")
+            .unwrap()
+            .build(),
+    );
+    let bed = |times: [u64; 2]| {
+        serde_json::json!({
+            "bed": { "actual": 60.0, "target": 60.0, "offset": 0 },
+            "history": times.map(|time| serde_json::json!({
+                "time": time,
+                "bed": { "actual": 60.0, "target": 60.0 }
+            })),
+        })
+    };
+
+    let dir = history_dir("rotation");
+    let mut recorder = HistoryRecorder::new(&dir);
+    let tools = serde_json::json!({
+        "tool0": { "actual": 210.0, "target": 210.0, "offset": 0 },
+        "history": [
+            { "time": 1000, "tool0": { "actual": 205.0, "target": 210.0 } },
+            { "time": 1002, "tool0": { "actual": 210.0, "target": 210.0 } },
+        ]
+    });
+    let mocks = serve_state(&mut server, tools, bed([1000, 1002])).await;
+    let (_, written) = recorder.record(&fleet).await.into_iter().next().unwrap();
+    assert_eq!(written.unwrap(), 2);
+    mocks.iter().for_each(|m| m.assert());
+
+    // tool1 has no column in the first file, which overlaps the second window
+    let tools = serde_json::json!({
+        "tool0": { "actual": 210.0, "target": 210.0, "offset": 0 },
+        "tool1": { "actual": 30.0, "target": null, "offset": 0 },
+        "history": [
+            {
+                "time": 1002,
+                "tool0": { "actual": 210.0, "target": 210.0 },
+                "tool1": { "actual": 25.0, "target": null }
+            },
+            {
+                "time": 1004,
+                "tool0": { "actual": 210.0, "target": 210.0 },
+                "tool1": { "actual": 30.0, "target": null }
+            },
+        ]
+    });
+    let mocks = serve_state(&mut server, tools, bed([1002, 1004])).await;
+    let (_, written) = recorder.record(&fleet).await.into_iter().next().unwrap();
+    assert_eq!(written.unwrap(), 1);
+    mocks.iter().for_each(|m| m.assert());
+
+    let times = |path: PathBuf| -> Vec<String> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(times(dir.join("prusa.1002.csv")), ["1000", "1002"]);
+    assert_eq!(times(recorder.path("prusa")), ["1004"]);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+async fn recorder_without_heated_bed() {
+    let mut server = mockito::Server::new_async().await;
+    let mut fleet = Fleet::new();
+    fleet.add(
+        "delta",
+        PrinterBuilder::from_base_url(server.url(), "KEY")
+            .unwrap()
+            .build(),
+    );
+    let tools = server
+        .mock("GET", "/api/printer/tool")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_body(
+            r#"{
+  "tool0": { "actual": 210.0, "target": 210.0, "offset": 0 },
+  "history": [
+    { "time": 1000, "tool0": { "actual": 205.0, "target": 210.0 } },
+    { "time": 1002, "tool0": { "actual": 210.0, "target": 210.0 } }
+  ]
+}"#,
+        )
+        .create_async()
+        .await;
+    let bed = server
+        .mock("GET", "/api/printer/bed")
+        .match_query(mockito::Matcher::Any)
+        .with_status(409)
+        .with_body("Printer does not have a heated bed")
+        .create_async()
+        .await;
+
+    let dir = history_dir("no-bed");
+    let mut recorder = HistoryRecorder::new(&dir);
+    let (_, written) = recorder.record(&fleet).await.into_iter().next().unwrap();
+    assert_eq!(written.unwrap(), 2);
+
+    let content = std::fs::read_to_string(recorder.path("delta")).unwrap();
+    assert_eq!(
+        content.lines().next(),
+        Some("time,tool0_actual,tool0_target")
+    );
+    tools.assert_async().await;
+    bed.assert_async().await;
+    let _ = std::fs::remove_dir_all(dir);
+}
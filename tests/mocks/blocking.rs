@@ -0,0 +1,33 @@
+use octoprint_rs::types::JobCommand;
+use octoprint_rs::PrinterBuilder;
+
+use crate::mocks::*;
+
+#[test]
+fn blocking_get_api_version() {
+    let mock = mock_get_api_version();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build_blocking();
+
+    let api_version = printer.get_api_version().unwrap();
+
+    assert_eq!(api_version.api, "0.1".to_string());
+    assert_eq!(api_version.text, "OctoPrint 1.9.3".to_string());
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+fn blocking_issue_job_command() {
+    let mock = mock_post_api_job();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build_blocking();
+
+    printer.issue_job_command(JobCommand::Pause).unwrap();
+
+    mock.mock.unwrap().assert();
+}
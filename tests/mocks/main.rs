@@ -1,7 +1,13 @@
+use std::time::Duration;
+
 use octoprint_rs::*;
 use tokio::test;
 
+#[cfg(feature = "blocking")]
+mod blocking;
 mod mocks;
+#[cfg(feature = "tracing")]
+mod tracing;
 use mocks::*;
 
 #[test]
@@ -106,6 +112,35 @@ async fn get_location_recursive() {
     mock.mock.unwrap().assert();
 }
 
+#[test]
+async fn get_location_local() {
+    let mock = mock_get_api_files_local();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let local_files = printer
+        .get_files(types::FilesFetchDescriptor {
+            location: types::FilesLocation::Local,
+            recursive: false,
+            force: false,
+        })
+        .await
+        .unwrap();
+
+    let local_files = serde_json::to_value(local_files).unwrap();
+    assert_eq!(local_files["free"], 423737376768u64);
+    assert_eq!(local_files["total"], 499031998464u64);
+    assert_eq!(local_files["files"][0]["type"], "Folder");
+    assert_eq!(local_files["files"][0]["name"], "folder");
+    assert_eq!(local_files["files"][1]["type"], "File");
+    assert_eq!(local_files["files"][1]["path"], "pushrod.gcode");
+    assert_eq!(local_files["files"][1]["size"], 801365);
+
+    mock.mock.unwrap().assert();
+}
+
 #[test]
 async fn get_file() {
     let mock = mock_get_api_files_local_folder_printed();
@@ -130,3 +165,713 @@ async fn get_file() {
 
     mock.mock.unwrap().assert();
 }
+
+#[test]
+async fn get_tool_state() {
+    let mock = mock_get_api_printer_tool();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let state = printer.get_tool_state(Some(2)).await.unwrap();
+
+    assert_eq!(state.tools.len(), 2);
+    assert_eq!(state.tools["tool0"].target, Some(220.0));
+    assert_eq!(state.tools["tool1"].target, None);
+    let history = state.history.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].time, 1395651927);
+    assert_eq!(history[1].tools["tool0"].actual, 215.1);
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn get_bed_state() {
+    let mock = mock_get_api_printer_bed();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let state = printer.get_bed_state(None).await.unwrap();
+
+    assert_eq!(state.bed.actual, 50.221);
+    assert_eq!(state.bed.target, Some(70.0));
+    assert!(state.history.is_none());
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn get_pi_support() {
+    let mock = mock_get_plugin_pi_support();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let pi_support = printer.get_pi_support().await.unwrap();
+
+    assert_eq!(
+        pi_support.model,
+        "Raspberry Pi 3 Model B Rev 1.2".to_string()
+    );
+    assert_eq!(pi_support.octopi_version, Some("0.14.0".to_string()));
+    assert!(pi_support.throttle_state.current_undervoltage);
+    assert!(pi_support.throttle_state.past_undervoltage);
+    assert!(pi_support.throttle_state.current_overheat);
+    assert!(!pi_support.throttle_state.current_freq_capped);
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn get_action_prompt() {
+    let mock = mock_get_plugin_action_command_prompt();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let prompt = printer.get_action_prompt().await.unwrap().unwrap();
+
+    assert_eq!(prompt.text, "Filament changed?".to_string());
+    assert_eq!(prompt.choices, vec!["Continue", "Purge more"]);
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn select_prompt_choice() {
+    let mock = mock_post_plugin_action_command_prompt();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    printer.select_prompt_choice(0).await.unwrap();
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn select_tool() {
+    let mock = mock_post_api_printer_tool_select();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    printer.select_tool(types::ToolId::new(1)).await.unwrap();
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn send_gcode() {
+    let mock = mock_post_api_printer_command();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    printer
+        .send_gcode(vec!["M115".to_string(), "G28 X".to_string()])
+        .await
+        .unwrap();
+    assert!(matches!(
+        printer.send_gcode(vec![]).await,
+        Err(errors::PrinterCommandError::BadRequest(_))
+    ));
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn temperature_profiles() {
+    let mock = mock_post_api_printer_temperature_profile();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .cache(Duration::from_secs(60))
+        .build();
+
+    let profiles = printer.get_temperature_profiles().await.unwrap();
+    assert_eq!(profiles.len(), 2);
+    assert_eq!(profiles[0].name, "PLA");
+    assert_eq!(profiles[0].chamber, None);
+    assert_eq!(profiles[1].chamber, Some(45.0));
+
+    printer.apply_temperature_profile("ABS").await.unwrap();
+    assert!(matches!(
+        printer.apply_temperature_profile("PETG").await,
+        Err(errors::TemperatureProfileError::UnknownProfile(ref name)) if name == "PETG"
+    ));
+    printer.cool_down().await.unwrap();
+
+    for mock in mock.mocks {
+        mock.assert();
+    }
+}
+
+#[test]
+async fn print_statistics() {
+    let mock = mock_get_api_files_q_recursive_printed();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let report = printer.print_statistics().await.unwrap();
+
+    assert_eq!(report.files.len(), 2);
+    assert_eq!(report.success, 2);
+    assert_eq!(report.failure, 4);
+    assert_eq!(report.last_printed, Some(1707170000));
+    assert_eq!(report.print_time_per_profile.get("_default"), Some(&2400.0));
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn get_language_packs() {
+    let mock = mock_get_api_languages();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let packs = printer.get_language_packs().await.unwrap();
+
+    let core = packs.language_packs.get("_core").unwrap();
+    assert_eq!(core.languages[0].locale, "de".to_string());
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn upload_language_pack() {
+    let mock = mock_post_api_languages();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let packs = printer
+        .upload_language_pack("de.zip", b"PK".to_vec())
+        .await
+        .unwrap();
+
+    assert_eq!(packs.language_packs.len(), 2);
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn delete_language_pack() {
+    let mock = mock_delete_api_languages_de_core();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let packs = printer.delete_language_pack("de", "_core").await.unwrap();
+
+    assert!(packs.language_packs.is_empty());
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn delete_language_pack_encodes_segments() {
+    let mock = mock_delete_api_languages_encoded();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    printer
+        .delete_language_pack("de/../../api", "_core?force=true")
+        .await
+        .unwrap();
+    for (locale, pack) in [("..", "_core"), ("de", "."), ("", "_core")] {
+        assert!(matches!(
+            printer.delete_language_pack(locale, pack).await,
+            Err(errors::LanguagePackError::Unexpected(
+                errors::OctoPrintError::InvalidArgument(_)
+            ))
+        ));
+    }
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn snapshot() {
+    let mock = mock_get_snapshot();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let snapshot = printer.snapshot().await.unwrap();
+
+    assert_eq!(snapshot.image.as_ref(), &[0xFF, 0xD8, 0xFF, 0xD9]);
+    assert_eq!(snapshot.content_type, Some("image/jpeg".to_string()));
+    assert!(snapshot.orientation.flip_h);
+    assert!(snapshot.orientation.rotate_90);
+
+    mock.mock.unwrap().assert();
+    mock.mocks.iter().for_each(|m| m.assert());
+}
+
+#[test]
+async fn snapshot_from_other_origin() {
+    let mock = mock_get_snapshot_other_origin();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let snapshot = printer.snapshot().await.unwrap();
+    assert_eq!(snapshot.image.len(), 4);
+
+    mock.mock.unwrap().assert();
+    mock.mocks.iter().for_each(|m| m.assert());
+}
+
+#[test]
+async fn snapshot_from_loopback_url() {
+    let mock = mock_get_snapshot_loopback();
+
+    // The printer is addressed by another host than the snapshot url
+    let printer = PrinterBuilder::new("localhost", mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let snapshot = printer.snapshot().await.unwrap();
+    assert_eq!(snapshot.image.len(), 4);
+
+    mock.mock.unwrap().assert();
+    mock.mocks.iter().for_each(|m| m.assert());
+}
+
+#[test]
+async fn snapshot_root_relative_to_origin() {
+    let mock = mock_get_prefixed_snapshot();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .path_prefix("/octoprint-3")
+        .build();
+
+    let snapshot = printer.snapshot().await.unwrap();
+    assert_eq!(snapshot.image.len(), 4);
+
+    mock.mock.unwrap().assert();
+    mock.mocks.iter().for_each(|m| m.assert());
+}
+
+#[test]
+async fn snapshot_legacy_settings() {
+    let mock = mock_get_snapshot_legacy();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let snapshot = printer.snapshot().await.unwrap();
+
+    assert_eq!(snapshot.image.len(), 4);
+    assert!(snapshot.orientation.flip_v);
+
+    mock.mock.unwrap().assert();
+    mock.mocks.iter().for_each(|m| m.assert());
+}
+
+#[test]
+async fn get_job() {
+    let mock = mock_get_api_job();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let _ = printer.get_job().await.unwrap();
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn issue_job_command() {
+    let mock = mock_post_api_job();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    printer
+        .issue_job_command(types::JobCommand::Pause)
+        .await
+        .unwrap();
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn base_url_with_path_prefix() {
+    let mock = mock_get_prefixed_api_version();
+
+    let base_url = format!("http://{}:{}/octoprint-3/", mock.address, mock.port);
+    let printer = PrinterBuilder::from_base_url(base_url, mock.api_key)
+        .unwrap()
+        .build();
+
+    let api_version = printer.get_api_version().await.unwrap();
+    assert_eq!(api_version.server, "1.9.3".to_string());
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn path_prefix() {
+    let mock = mock_get_prefixed_api_version();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .path_prefix("/octoprint-3")
+        .build();
+
+    let _ = printer.get_api_version().await.unwrap();
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn base_url_invalid() {
+    assert!(matches!(
+        PrinterBuilder::from_base_url("ftp://farm.example/", "API_KEY"),
+        Err(errors::PrinterBuilderError::UnsupportedScheme(_))
+    ));
+    assert!(matches!(
+        PrinterBuilder::from_base_url("not a url", "API_KEY"),
+        Err(errors::PrinterBuilderError::InvalidUrl(_))
+    ));
+}
+
+fn retry_policy() -> retry::RetryPolicy {
+    retry::RetryPolicy {
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(10),
+        retry_commands: false,
+    }
+}
+
+#[test]
+async fn retry_get() {
+    let mock = mock_get_api_version_flaky();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .retry_policy(retry_policy())
+        .build();
+
+    let api_version = printer.get_api_version().await.unwrap();
+    assert_eq!(api_version.api, "0.1".to_string());
+
+    mock.mock.unwrap().assert();
+    mock.mocks.iter().for_each(|m| m.assert());
+}
+
+#[test]
+async fn no_retry_for_commands() {
+    let mock = mock_post_api_printer_tool_unavailable();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .retry_policy(retry_policy())
+        .build();
+
+    let result = printer
+        .tool_temperature(types::ToolTempDescriptor::Target {
+            tool: types::ToolId::new(0),
+            temperature: 200.0,
+        })
+        .await;
+    let Err(errors::ToolCommandError::ServerError(error)) = result else {
+        panic!("expected a server error, got {:?}", result);
+    };
+    // The status and endpoint survive the round trip through the operation enum
+    let error = errors::OctoPrintError::from(errors::ToolCommandError::ServerError(error));
+    assert_eq!(
+        error.status(),
+        Some(reqwest::StatusCode::SERVICE_UNAVAILABLE)
+    );
+    assert_eq!(error.endpoint(), Some("/api/printer/tool"));
+
+    // Only hit once even though the policy allows retries
+    mock.mock.unwrap().assert();
+    // A single tool is not checked without cached printer profiles
+    mock.mocks.into_iter().for_each(|m| m.expect(0).assert());
+}
+
+#[test]
+async fn tool_outside_printer_profile() {
+    let mut mock = mock_post_api_printer_tool_unavailable();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .cache(Duration::from_secs(60))
+        .build();
+
+    for command in [
+        types::ToolTempDescriptor::Target {
+            tool: types::ToolId::new(2),
+            temperature: 200.0,
+        },
+        types::ToolTempDescriptor::Offset {
+            tool: types::ToolId::new(2),
+            amount: 5.0,
+        },
+    ] {
+        let result = printer.tool_temperature(command).await;
+        assert!(matches!(
+            result,
+            Err(errors::ToolCommandError::BadRequest(ref message)) if message.contains("tool2")
+        ));
+    }
+
+    // Nothing is sent and the printer profiles are only asked for once
+    mock.mock = Some(mock.mock.unwrap().expect(0));
+    mock.mock.unwrap().assert();
+    mock.mocks.iter().for_each(|m| m.assert());
+}
+
+#[test]
+async fn retry_commands_when_enabled() {
+    let mut mock = mock_post_api_printer_tool_unavailable();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .retry_policy(retry::RetryPolicy {
+            retry_commands: true,
+            ..retry_policy()
+        })
+        .build();
+
+    let _ = printer
+        .tool_temperature(types::ToolTempDescriptor::Target {
+            tool: types::ToolId::new(0),
+            temperature: 200.0,
+        })
+        .await;
+
+    mock.mock = Some(mock.mock.unwrap().expect(4));
+    mock.mock.unwrap().assert();
+    mock.mocks.into_iter().for_each(|m| m.expect(0).assert());
+}
+
+#[test]
+async fn tool_check_skipped_without_printer_profiles() {
+    let mut mock = mock_post_api_printer_tool_unavailable();
+    // Fetching the printer profiles fails without their mock
+    mock.mocks.clear();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let result = printer
+        .tool_temperature(types::ToolTempDescriptor::Targets(
+            [
+                (types::ToolId::new(0), 200.0),
+                (types::ToolId::new(1), 210.0),
+            ]
+            .into(),
+        ))
+        .await;
+    assert!(matches!(
+        result,
+        Err(errors::ToolCommandError::ServerError(_))
+    ));
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn request_timeout() {
+    // Accepts connections but never responds
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut connections = vec![];
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(stream);
+        }
+    });
+
+    let printer = PrinterBuilder::new("127.0.0.1", "API_KEY")
+        .port(port)
+        .timeout(Duration::from_millis(100))
+        .build();
+
+    match printer.get_api_version().await {
+        Err(errors::InformationRequestError::ReqwestError(e)) => assert!(e.is_timeout()),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+}
+
+#[test]
+async fn unexpected_status() {
+    let mock = mock_post_api_job_forbidden();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let error = printer
+        .issue_job_command(types::JobCommand::Start)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, errors::JobCommandError::Unexpected(_)));
+
+    let error = errors::OctoPrintError::from(error);
+    assert_eq!(error.status(), Some(reqwest::StatusCode::FORBIDDEN));
+    assert_eq!(error.endpoint(), Some("/api/job"));
+    assert_eq!(error.body(), Some("Forbidden"));
+    assert_eq!(
+        error.to_string(),
+        "/api/job responded with 403 Forbidden: Forbidden".to_string()
+    );
+
+    mock.mock.unwrap().assert();
+}
+
+#[test]
+async fn parse_error_path() {
+    let mock = mock_get_api_version_invalid();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let error = printer.get_api_version().await.unwrap_err();
+    match error {
+        errors::InformationRequestError::ParseError(errors::OctoPrintError::Parse {
+            endpoint,
+            path,
+            ..
+        }) => {
+            assert_eq!(endpoint.as_deref(), Some("/api/version"));
+            assert_eq!(path, "server");
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    mock.mock.unwrap().assert();
+}
+
+fn local_files() -> types::FilesFetchDescriptor {
+    types::FilesFetchDescriptor {
+        location: types::FilesLocation::Local,
+        recursive: false,
+        force: false,
+    }
+}
+
+#[test]
+async fn cached_files_within_ttl() {
+    let mock = mock_get_api_files_local_etag();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .cache(Duration::from_secs(60))
+        .build();
+
+    let first = printer.get_files(local_files()).await.unwrap();
+    let second = printer.get_files(local_files()).await.unwrap();
+    assert_eq!(second.files.len(), first.files.len());
+    assert_eq!(second.free, 31030067200);
+
+    // The second listing is served from the cache
+    mock.mock.unwrap().assert();
+    assert!(!mock.mocks[0].matched());
+}
+
+#[test]
+async fn cached_files_revalidated() {
+    let mut mock = mock_get_api_files_local_etag();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .cache(Duration::ZERO)
+        .build();
+
+    let first = printer.get_files(local_files()).await.unwrap();
+    for _ in 0..2 {
+        let files = printer.get_files(local_files()).await.unwrap();
+        assert_eq!(files.files.len(), first.files.len());
+        assert_eq!(files.total, 31254343680);
+    }
+
+    mock.mock.unwrap().assert();
+    mock.mocks[0] = mock.mocks.remove(0).expect(2);
+    mock.mocks[0].assert();
+}
+
+#[test]
+async fn cached_files_invalidated_by_changes() {
+    let mut mock = mock_get_api_files_local_etag();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .cache(Duration::from_secs(60))
+        .build();
+
+    printer.get_files(local_files()).await.unwrap();
+    printer
+        .delete_file(types::PathDescriptor {
+            location: types::FileLocation::Local,
+            path: "folder".to_string(),
+        })
+        .await
+        .unwrap();
+    printer.get_files(local_files()).await.unwrap();
+
+    mock.mock = Some(mock.mock.unwrap().expect(2));
+    mock.mock.unwrap().assert();
+    mock.mocks[1].assert();
+}
+
+#[test]
+async fn upload_file() {
+    let mock = mock_post_api_files_local();
+
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+
+    let response = printer
+        .upload_file(types::FileUploadDescriptor {
+            path: types::PathDescriptor {
+                location: types::FileLocation::Local,
+                path: "/parts/benchy.gcode".to_string(),
+            },
+            contents: b"G28\nG1 X10\n".to_vec(),
+            select: false,
+            print: true,
+        })
+        .await
+        .unwrap();
+
+    assert!(response.done);
+    assert_eq!(
+        response.files["local"].path.as_deref(),
+        Some("parts/benchy.gcode")
+    );
+
+    mock.mock.unwrap().assert();
+}
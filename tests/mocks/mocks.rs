@@ -1,6 +1,11 @@
 pub struct MockFrame {
     pub server: mockito::ServerGuard,
     pub mock: Option<mockito::Mock>,
+    /// Additional mocks for requests that hit more than one endpoint
+    pub mocks: Vec<mockito::Mock>,
+    /// A second server for requests that go to another origin, only held to keep it running
+    #[allow(dead_code)]
+    pub other: Option<mockito::ServerGuard>,
     pub address: String,
     pub port: u16,
     pub api_key: String,
@@ -36,6 +41,8 @@ fn mock_base() -> MockFrame {
         port,
         api_key,
         mock: None,
+        mocks: vec![],
+        other: None,
     }
 }
 
@@ -547,3 +554,913 @@ pub fn mock_get_api_files_local_folder_printed() -> MockFrame {
 
     MockFrame { mock, ..server }
 }
+
+pub fn mock_get_plugin_pi_support() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/plugin/pi_support")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(
+                r#"{
+  "model": "Raspberry Pi 3 Model B Rev 1.2",
+  "throttle_state": "0x50005",
+  "octopi_version": "0.14.0"
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_get_plugin_action_command_prompt() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/plugin/action_command_prompt")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(
+                r#"{
+  "text": "Filament changed?",
+  "choices": ["Continue", "Purge more"]
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_post_plugin_action_command_prompt() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("POST", "/plugin/action_command_prompt")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({"command": "select", "choice": 0}),
+            ))
+            .with_status(204)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_post_api_printer_tool_select() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("POST", "/api/printer/tool")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({"command": "select", "tool": "tool1"}),
+            ))
+            .with_status(204)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_get_api_files_q_recursive_printed() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/api/files?recursive=true")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(
+                r#"{
+  "files": [
+    {
+      "children": [
+        {
+          "date": 1707166498,
+          "display": "printed.gcode",
+          "name": "printed.gcode",
+          "origin": "local",
+          "path": "folder/printed.gcode",
+          "print": {
+            "failure": 1,
+            "last": {
+              "date": 1707170000,
+              "printTime": 1200.5,
+              "success": true
+            },
+            "success": 2
+          },
+          "statistics": {
+            "averagePrintTime": {
+              "_default": 1200.0
+            },
+            "lastPrintTime": {
+              "_default": 1200.5
+            }
+          },
+          "type": "machinecode",
+          "typePath": [
+            "machinecode",
+            "gcode"
+          ]
+        }
+      ],
+      "display": "folder",
+      "name": "folder",
+      "origin": "local",
+      "path": "folder",
+      "type": "folder",
+      "typePath": [
+        "folder"
+      ]
+    },
+    {
+      "date": 1707166400,
+      "display": "failed.gcode",
+      "name": "failed.gcode",
+      "origin": "local",
+      "path": "failed.gcode",
+      "print": {
+        "failure": 3,
+        "last": {
+          "date": 1707160000,
+          "success": false
+        },
+        "success": 0
+      },
+      "type": "machinecode",
+      "typePath": [
+        "machinecode",
+        "gcode"
+      ]
+    },
+    {
+      "date": 1707166300,
+      "display": "never.gcode",
+      "name": "never.gcode",
+      "origin": "local",
+      "path": "never.gcode",
+      "type": "machinecode",
+      "typePath": [
+        "machinecode",
+        "gcode"
+      ]
+    }
+  ],
+  "free": 423737376768,
+  "total": 499031998464
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+const LANGUAGE_PACKS: &str = r#"{
+  "language_packs": {
+    "_core": {
+      "identifier": "_core",
+      "display": "Core OctoPrint",
+      "languages": [
+        {
+          "locale": "de",
+          "locale_display": "Deutsch",
+          "locale_english": "German",
+          "last_update": 1474574597,
+          "author": "The OctoPrint Project"
+        }
+      ]
+    },
+    "some_plugin": {
+      "identifier": "some_plugin",
+      "display": "Some Plugin",
+      "languages": []
+    }
+  }
+}"#;
+
+pub fn mock_get_api_languages() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/api/languages")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(LANGUAGE_PACKS)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_post_api_languages() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("POST", "/api/languages")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_header(
+                "content-type",
+                mockito::Matcher::Regex("^multipart/form-data".to_string()),
+            )
+            .match_body(mockito::Matcher::Regex(
+                r#"name="file"; filename="de.zip""#.to_string(),
+            ))
+            .with_status(200)
+            .with_body(LANGUAGE_PACKS)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_delete_api_languages_de_core() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("DELETE", "/api/languages/de/_core")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(r#"{"language_packs": {}}"#)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_delete_api_languages_encoded() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock(
+                "DELETE",
+                "/api/languages/de%2F..%2F..%2Fapi/_core%3Fforce=true",
+            )
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(r#"{"language_packs": {}}"#)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_get_snapshot() -> MockFrame {
+    let mut server = mock_base();
+
+    let webcams = server
+        .server
+        .mock("GET", "/api/webcams")
+        .match_header("X-Api-Key", server.api_key.as_str())
+        .with_status(200)
+        .with_body(
+            r#"{
+  "webcams": [
+    {
+      "name": "classic",
+      "displayName": "Classic Webcam",
+      "canSnapshot": true,
+      "flipH": true,
+      "flipV": false,
+      "rotate90": true,
+      "compat": {
+        "snapshot": "/webcam/?action=snapshot",
+        "stream": "/webcam/?action=stream"
+      }
+    }
+  ]
+}"#,
+        )
+        .create();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/webcam/?action=snapshot")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_header("content-type", "image/jpeg")
+            .with_body([0xFF, 0xD8, 0xFF, 0xD9])
+            .create(),
+    );
+
+    MockFrame {
+        mock,
+        mocks: vec![webcams],
+        ..server
+    }
+}
+
+/// A webcam that is served from the same server, but reached through `localhost` instead of the
+/// address of the printer
+pub fn mock_get_snapshot_other_origin() -> MockFrame {
+    let mut server = mock_base();
+    // Same host, but another port
+    let mut other = mockito::Server::new();
+
+    let body = r#"{
+  "webcams": [
+    {
+      "name": "classic",
+      "displayName": "Classic Webcam",
+      "canSnapshot": true,
+      "compat": { "snapshot": "http://HOST/webcam/?action=snapshot" }
+    }
+  ]
+}"#
+    .replace("HOST", &other.host_with_port());
+    let webcams = server
+        .server
+        .mock("GET", "/api/webcams")
+        .match_header("X-Api-Key", server.api_key.as_str())
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let mock = Some(
+        other
+            .mock("GET", "/webcam/?action=snapshot")
+            .match_header("X-Api-Key", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_body([0xFF, 0xD8, 0xFF, 0xD9])
+            .create(),
+    );
+
+    MockFrame {
+        mock,
+        mocks: vec![webcams],
+        other: Some(other),
+        ..server
+    }
+}
+
+/// The default snapshot url of OctoPi, which is only reachable on the Pi itself
+pub fn mock_get_snapshot_loopback() -> MockFrame {
+    let mut server = mock_base();
+
+    let webcams = server
+        .server
+        .mock("GET", "/api/webcams")
+        .match_header("X-Api-Key", server.api_key.as_str())
+        .with_status(200)
+        .with_body(
+            r#"{
+  "webcams": [
+    {
+      "name": "classic",
+      "displayName": "Classic Webcam",
+      "canSnapshot": true,
+      "compat": { "snapshot": "http://127.0.0.1:8080/?action=snapshot" }
+    }
+  ]
+}"#,
+        )
+        .create();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/webcam/?action=snapshot")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body([0xFF, 0xD8, 0xFF, 0xD9])
+            .create(),
+    );
+
+    MockFrame {
+        mock,
+        mocks: vec![webcams],
+        ..server
+    }
+}
+
+/// A printer behind a reverse proxy at `/octoprint-3/` with the webcam proxied at `/webcam/`
+pub fn mock_get_prefixed_snapshot() -> MockFrame {
+    let mut server = mock_base();
+
+    let webcams = server
+        .server
+        .mock("GET", "/octoprint-3/api/webcams")
+        .match_header("X-Api-Key", server.api_key.as_str())
+        .with_status(200)
+        .with_body(
+            r#"{
+  "webcams": [
+    {
+      "name": "classic",
+      "displayName": "Classic Webcam",
+      "canSnapshot": true,
+      "compat": { "snapshot": "/webcam/?action=snapshot" }
+    }
+  ]
+}"#,
+        )
+        .create();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/webcam/?action=snapshot")
+            .with_status(200)
+            .with_body([0xFF, 0xD8, 0xFF, 0xD9])
+            .create(),
+    );
+
+    MockFrame {
+        mock,
+        mocks: vec![webcams],
+        ..server
+    }
+}
+
+pub fn mock_get_snapshot_legacy() -> MockFrame {
+    let mut server = mock_base();
+
+    let webcams = server
+        .server
+        .mock("GET", "/api/webcams")
+        .with_status(404)
+        .create();
+
+    let settings = server
+        .server
+        .mock("GET", "/api/settings")
+        .match_header("X-Api-Key", server.api_key.as_str())
+        .with_status(200)
+        .with_body(
+            r#"{
+  "webcam": {
+    "snapshotUrl": "/webcam/?action=snapshot",
+    "flipH": false,
+    "flipV": true,
+    "rotate90": false,
+    "webcamEnabled": true
+  }
+}"#,
+        )
+        .create();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/webcam/?action=snapshot")
+            .with_status(200)
+            .with_body([0xFF, 0xD8, 0xFF, 0xD9])
+            .create(),
+    );
+
+    MockFrame {
+        mock,
+        mocks: vec![webcams, settings],
+        ..server
+    }
+}
+
+pub fn mock_get_api_job() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/api/job")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(
+                r#"{
+  "job": {
+    "file": {
+      "name": "whistle_v2.gcode",
+      "origin": "local",
+      "size": 1468987,
+      "date": 1378847754
+    },
+    "estimatedPrintTime": 8811,
+    "filament": {
+      "length": 810,
+      "volume": 5.36
+    }
+  },
+  "progress": {
+    "completion": 0.2298468264184775,
+    "filepos": 337942,
+    "printTime": 276,
+    "printTimeLeft": 912
+  },
+  "state": "Printing"
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_post_api_job() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("POST", "/api/job")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({"command": "pause", "action": "pause"}),
+            ))
+            .with_status(204)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_get_prefixed_api_version() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/octoprint-3/api/version")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(
+                r#"{
+  "api": "0.1",
+  "server": "1.9.3",
+  "text": "OctoPrint 1.9.3"
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+/// Responds with `503` twice before answering with the api version
+pub fn mock_get_api_version_flaky() -> MockFrame {
+    let mut server = mock_base();
+
+    let unavailable = server
+        .server
+        .mock("GET", "/api/version")
+        .with_status(503)
+        .expect(2)
+        .create();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/api/version")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(
+                r#"{
+  "api": "0.1",
+  "server": "1.9.3",
+  "text": "OctoPrint 1.9.3"
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame {
+        mock,
+        mocks: vec![unavailable],
+        ..server
+    }
+}
+
+pub fn mock_post_api_printer_tool_unavailable() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("POST", "/api/printer/tool")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(503)
+            .create(),
+    );
+    let profiles = mock_dual_extruder_profiles(&mut server);
+
+    MockFrame {
+        mock,
+        mocks: vec![profiles],
+        ..server
+    }
+}
+
+pub fn mock_post_api_job_forbidden() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("POST", "/api/job")
+            .with_status(403)
+            .with_body("Forbidden")
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_get_api_version_invalid() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/api/version")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .with_status(200)
+            .with_body(r#"{"api": "0.1", "server": 193, "text": "OctoPrint 1.9.3"}"#)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_get_api_files_local_etag() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/api/files/local")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_header("If-None-Match", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("ETag", "\"files-1\"")
+            .with_header("Last-Modified", "Mon, 05 Feb 2024 20:54:09 GMT")
+            .with_body(
+                r#"{
+  "files": [
+    {
+      "children": [],
+      "display": "folder",
+      "name": "folder",
+      "origin": "local",
+      "path": "folder",
+      "size": 0,
+      "type": "folder",
+      "typePath": ["folder"]
+    }
+  ],
+  "free": 31030067200,
+  "total": 31254343680
+}"#,
+            )
+            .create(),
+    );
+
+    let not_modified = server
+        .server
+        .mock("GET", "/api/files/local")
+        .match_header("X-Api-Key", server.api_key.as_str())
+        .match_header("If-None-Match", "\"files-1\"")
+        .match_header("If-Modified-Since", "Mon, 05 Feb 2024 20:54:09 GMT")
+        .with_status(304)
+        .create();
+
+    let delete = server
+        .server
+        .mock("DELETE", "/api/files/local/folder")
+        .match_header("X-Api-Key", server.api_key.as_str())
+        .with_status(204)
+        .create();
+
+    MockFrame {
+        mock,
+        mocks: vec![not_modified, delete],
+        ..server
+    }
+}
+
+pub fn mock_post_api_files_local() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("POST", "/api/files/local")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::Regex(r#"name="file"; filename="benchy.gcode""#.to_string()),
+                mockito::Matcher::Regex(r#"name="path"\r\n\r\nparts\r\n"#.to_string()),
+                mockito::Matcher::Regex(r#"name="print"\r\n\r\ntrue\r\n"#.to_string()),
+                mockito::Matcher::Regex("G28\nG1 X10".to_string()),
+            ]))
+            .with_status(201)
+            .with_body(
+                r#"{
+  "files": {
+    "local": {
+      "name": "benchy.gcode",
+      "origin": "local",
+      "path": "parts/benchy.gcode",
+      "refs": {
+        "resource": "http://127.0.0.1:5000/api/files/local/parts/benchy.gcode",
+        "download": "http://127.0.0.1:5000/downloads/files/local/parts/benchy.gcode"
+      }
+    }
+  },
+  "done": true
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_post_api_printer_command() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("POST", "/api/printer/command")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_body(mockito::Matcher::Json(
+                serde_json::json!({"commands": ["M115", "G28 X"]}),
+            ))
+            .with_status(204)
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+/// The printer profiles of a printer with two extruders, which commands to the tools are checked
+/// against
+fn mock_dual_extruder_profiles(server: &mut MockFrame) -> mockito::Mock {
+    server
+        .server
+        .mock("GET", "/api/printerprofiles")
+        .match_header("X-Api-Key", server.api_key.as_str())
+        .with_status(200)
+        .with_body(
+            r#"{
+  "profiles": {
+    "_default": {
+      "id": "_default",
+      "name": "Dual extruder",
+      "model": "Generic",
+      "current": true,
+      "default": true,
+      "heatedBed": true,
+      "heatedChamber": true,
+      "volume": { "formFactor": "rectangular", "origin": "lowerleft", "width": 200, "depth": 200, "height": 200 },
+      "extruder": { "count": 2, "nozzleDiameter": 0.4, "sharedNozzle": false, "offsets": [[0, 0], [18, 0]] }
+    }
+  }
+}"#,
+        )
+        .create()
+}
+
+/// A printer with two extruders and a heated chamber, and the temperatures the ABS profile and
+/// cooling down set
+pub fn mock_post_api_printer_temperature_profile() -> MockFrame {
+    let mut server = mock_base();
+    let api_key = server.api_key.clone();
+
+    let settings = server
+        .server
+        .mock("GET", "/api/settings")
+        .match_header("X-Api-Key", api_key.as_str())
+        .with_status(200)
+        .with_body(
+            r#"{
+  "temperature": {
+    "cutoff": 30,
+    "profiles": [
+      { "bed": 60, "chamber": null, "extruder": 200, "name": "PLA" },
+      { "bed": 100, "chamber": 45, "extruder": 240, "name": "ABS" }
+    ],
+    "sendAutomatically": false,
+    "sendAutomaticallyAfter": 1
+  }
+}"#,
+        )
+        .expect(1)
+        .create();
+
+    let profiles = mock_dual_extruder_profiles(&mut server).expect(1);
+
+    let mut mocks = vec![settings, profiles];
+    for (path, body, temperature) in [
+        (
+            "/api/printer/tool",
+            r#"{"targets": {"tool0": TEMP, "tool1": TEMP}}"#,
+            "240.0",
+        ),
+        ("/api/printer/bed", r#"{"target": TEMP}"#, "100.0"),
+        ("/api/printer/chamber", r#"{"target": TEMP}"#, "45.0"),
+        (
+            "/api/printer/tool",
+            r#"{"targets": {"tool0": TEMP, "tool1": TEMP}}"#,
+            "0.0",
+        ),
+        ("/api/printer/bed", r#"{"target": TEMP}"#, "0.0"),
+        ("/api/printer/chamber", r#"{"target": TEMP}"#, "0.0"),
+    ] {
+        let body: serde_json::Value =
+            serde_json::from_str(&body.replace("TEMP", temperature)).unwrap();
+        mocks.push(
+            server
+                .server
+                .mock("POST", path)
+                .match_header("X-Api-Key", api_key.as_str())
+                .match_body(mockito::Matcher::PartialJson(body))
+                .with_status(204)
+                .expect(1)
+                .create(),
+        );
+    }
+
+    MockFrame { mocks, ..server }
+}
+
+pub fn mock_get_api_printer_tool() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/api/printer/tool")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("history".to_string(), "true".to_string()),
+                mockito::Matcher::UrlEncoded("limit".to_string(), "2".to_string()),
+            ]))
+            .with_status(200)
+            .with_body(
+                r#"{
+  "tool0": { "actual": 214.8821, "target": 220.0, "offset": 0 },
+  "tool1": { "actual": 25.3, "target": null, "offset": 0 },
+  "history": [
+    {
+      "time": 1395651926,
+      "tool0": { "actual": 214.8821, "target": 220.0 },
+      "tool1": { "actual": 25.3, "target": null }
+    },
+    {
+      "time": 1395651927,
+      "tool0": { "actual": 215.1, "target": 220.0 },
+      "tool1": { "actual": 25.3, "target": null }
+    }
+  ]
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
+
+pub fn mock_get_api_printer_bed() -> MockFrame {
+    let mut server = mock_base();
+
+    let mock = Some(
+        server
+            .server
+            .mock("GET", "/api/printer/bed")
+            .match_header("X-Api-Key", server.api_key.as_str())
+            .match_query(mockito::Matcher::UrlEncoded(
+                "history".to_string(),
+                "false".to_string(),
+            ))
+            .with_status(200)
+            .with_body(
+                r#"{
+  "bed": { "actual": 50.221, "target": 70.0, "offset": 5 }
+}"#,
+            )
+            .create(),
+    );
+
+    MockFrame { mock, ..server }
+}
@@ -0,0 +1,94 @@
+use std::sync::{Arc, Mutex};
+
+use octoprint_rs::PrinterBuilder;
+use tokio::test;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+use crate::mocks::*;
+
+/// What was recorded for `octoprint_request` spans and their events.
+#[derive(Default)]
+struct Captured {
+    fields: Vec<(String, String)>,
+    entered: usize,
+}
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Captured>>);
+
+impl Visit for Captured {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.fields
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, _: &span::Id, _: Context<'_, S>) {
+        if attrs.metadata().name() == "octoprint_request" {
+            attrs.record(&mut *self.0.lock().unwrap());
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if is_request(&ctx, id) {
+            values.record(&mut *self.0.lock().unwrap());
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if is_request(&ctx, id) {
+            self.0.lock().unwrap().entered += 1;
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        event.record(&mut *self.0.lock().unwrap());
+    }
+}
+
+fn is_request<S: Subscriber + for<'a> LookupSpan<'a>>(ctx: &Context<'_, S>, id: &span::Id) -> bool {
+    ctx.span(id)
+        .is_some_and(|span| span.name() == "octoprint_request")
+}
+
+#[test]
+async fn request_span() {
+    let capture = Capture::default();
+    let _guard = tracing_subscriber::registry()
+        .with(capture.clone())
+        .set_default();
+
+    let mock = mock_get_api_version();
+    let api_key = mock.api_key.clone();
+    let printer = PrinterBuilder::new(mock.address, mock.api_key)
+        .port(mock.port)
+        .build();
+    printer.get_api_version().await.unwrap();
+
+    let captured = capture.0.lock().unwrap();
+    let field = |name: &str| {
+        captured
+            .fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert!(field("printer").unwrap().starts_with("http://127.0.0.1:"));
+    assert_eq!(field("method"), Some("GET"));
+    assert_eq!(field("endpoint"), Some("/api/version"));
+    assert_eq!(field("status"), Some("200"));
+    assert_eq!(field("retries"), Some("0"));
+    assert!(field("latency_ms").is_some());
+    assert!(captured.entered > 0);
+    assert!(captured
+        .fields
+        .iter()
+        .all(|(name, value)| !value.contains(&api_key) && !name.contains("api")));
+
+    mock.mock.unwrap().assert();
+}
@@ -0,0 +1,91 @@
+#![cfg(all(feature = "cli", feature = "test-server"))]
+
+use std::process::{Command, Output};
+
+use octoprint_rs::test_server::{TestServer, TestServerBuilder};
+
+fn octoctl(server: &TestServer, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_octoctl"))
+        .args(["--url", &server.url(), "--api-key", server.api_key()])
+        .args(args)
+        .env_remove("OCTOCTL_CONFIG")
+        .output()
+        .expect("failed to run octoctl")
+}
+
+fn stdout(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn status_as_json_and_table() {
+    let server = TestServerBuilder::new().tools(2).start();
+    stdout(octoctl(&server, &["temp", "tool1", "210"]));
+    stdout(octoctl(&server, &["gcode", "M140 S60"]));
+
+    let status: serde_json::Value =
+        serde_json::from_str(&stdout(octoctl(&server, &["--json", "status"]))).unwrap();
+    assert_eq!(status["printer"]["state"]["text"], "Operational");
+    assert_eq!(status["printer"]["temperature"]["tool1"]["target"], 210.0);
+    assert_eq!(status["printer"]["temperature"]["bed"]["target"], 60.0);
+
+    let table = stdout(octoctl(&server, &["status"]));
+    assert!(table.contains("State:     Operational"), "{}", table);
+    assert!(table
+        .lines()
+        .any(|l| l.starts_with("HEATER  ACTUAL  TARGET")));
+    assert!(table
+        .lines()
+        .any(|l| l.starts_with("tool1") && l.ends_with("210.0")));
+}
+
+#[test]
+fn files_and_job() {
+    let server = TestServer::start();
+    let file = std::env::temp_dir().join(format!("octoctl-benchy-{}.gcode", std::process::id()));
+    std::fs::write(&file, b"G28\n".repeat(1000)).unwrap();
+
+    stdout(octoctl(
+        &server,
+        &[
+            "files",
+            "upload",
+            file.to_str().unwrap(),
+            "parts/benchy.gcode",
+        ],
+    ));
+    std::fs::remove_file(&file).unwrap();
+    stdout(octoctl(
+        &server,
+        &["files", "cp", "parts/benchy.gcode", "/"],
+    ));
+
+    let listing = stdout(octoctl(&server, &["files", "ls"]));
+    let rows: Vec<_> = listing.lines().skip(1).take(3).collect();
+    assert_eq!(
+        rows,
+        [
+            "local   benchy.gcode        4.0 KB",
+            "local   parts/              -",
+            "local   parts/benchy.gcode  4.0 KB",
+        ]
+    );
+
+    stdout(octoctl(&server, &["job", "start", "benchy.gcode"]));
+    stdout(octoctl(&server, &["job", "pause"]));
+    let job = stdout(octoctl(&server, &["job"]));
+    assert!(
+        job.starts_with("State:     Paused\nFile:      benchy.gcode\n"),
+        "{}",
+        job
+    );
+
+    let failed = octoctl(&server, &["files", "rm", "benchy.gcode"]);
+    assert!(!failed.status.success());
+    assert!(String::from_utf8_lossy(&failed.stderr).contains("409 Conflict"));
+}
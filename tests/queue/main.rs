@@ -0,0 +1,234 @@
+use std::path::PathBuf;
+
+use octoprint_rs::api::OctoPrintApi;
+use octoprint_rs::errors::*;
+use octoprint_rs::fake::FakePrinter;
+use octoprint_rs::fleet::Fleet;
+use octoprint_rs::queue::*;
+use octoprint_rs::types::*;
+use tokio::test;
+
+fn queue_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("octoprint-queue-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// A printer with a 300 mm cube build volume and a 0.6 mm nozzle
+async fn large_printer() -> FakePrinter {
+    let printer = FakePrinter::new();
+    let mut profile = printer.get_printer_profiles().await.unwrap().profiles["_default"].clone();
+    profile.id = "large".to_string();
+    profile.volume.width = 300.0;
+    profile.volume.depth = 300.0;
+    profile.volume.height = 300.0;
+    profile.extruder.nozzle_diameter = 0.6;
+    printer.set_printer_profile(profile);
+    printer
+}
+
+fn tall() -> Requirements {
+    Requirements {
+        dimensions: Some(Dimension {
+            width: 100.0,
+            height: 250.0,
+            depth: 100.0,
+        }),
+        ..Requirements::default()
+    }
+}
+
+#[test]
+async fn dispatches_by_requirements() {
+    let mut fleet = Fleet::new();
+    fleet.add("large", large_printer().await);
+    fleet.add("small", FakePrinter::new());
+
+    let dir = queue_dir("requirements");
+    let mut queue = PrintQueue::open(&dir).unwrap();
+    let fine = queue
+        .submit(
+            "fine.gcode",
+            "G28\n",
+            Requirements {
+                nozzle_diameter: Some(0.4),
+                ..Requirements::default()
+            },
+        )
+        .unwrap();
+    let first = queue.submit("tall.gcode", "G28\n", tall()).unwrap();
+    let second = queue.submit("tall.gcode", "G28\n", tall()).unwrap();
+
+    let events = queue.tick(&fleet).await.unwrap();
+    assert_eq!(
+        events,
+        [
+            QueueEvent::Dispatched {
+                job: fine,
+                printer: "small".to_string()
+            },
+            QueueEvent::Dispatched {
+                job: first,
+                printer: "large".to_string()
+            },
+        ]
+    );
+    assert_eq!(queue.job(second).unwrap().status, JobStatus::Pending);
+
+    let large = fleet.get("large").unwrap();
+    let job = large.get_job().await.unwrap();
+    assert_eq!(job.state, "Printing");
+    assert_eq!(
+        job.job.file.path.as_deref(),
+        Some(queue.job(first).unwrap().path.as_str())
+    );
+
+    large.set_progress(40.0);
+    assert!(queue.tick(&fleet).await.unwrap().is_empty());
+    assert_eq!(
+        queue.job(first).unwrap().status,
+        JobStatus::Printing {
+            printer: "large".to_string(),
+            completion: Some(40.0)
+        }
+    );
+
+    large.set_progress(100.0);
+    let events = queue.tick(&fleet).await.unwrap();
+    assert_eq!(
+        events,
+        [
+            QueueEvent::PrintDone {
+                job: first,
+                printer: "large".to_string()
+            },
+            QueueEvent::Dispatched {
+                job: second,
+                printer: "large".to_string()
+            },
+        ]
+    );
+    assert!(!queue.is_finished());
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+async fn resumes_after_restart() {
+    let dir = queue_dir("restart");
+    let mut fleet = Fleet::new();
+    fleet.add("prusa", FakePrinter::new());
+
+    let id = {
+        let mut queue = PrintQueue::open(&dir).unwrap();
+        let id = queue
+            .submit("benchy.gcode", "G28\n", Requirements::default())
+            .unwrap();
+        queue.tick(&fleet).await.unwrap();
+        id
+    };
+
+    let printer = fleet.get("prusa").unwrap();
+    printer.set_progress(30.0);
+    printer.issue_job_command(JobCommand::Cancel).await.unwrap();
+
+    let mut queue = PrintQueue::open(&dir).unwrap();
+    assert!(matches!(
+        queue.job(id).unwrap().status,
+        JobStatus::Printing { .. }
+    ));
+    assert!(matches!(queue.remove(id), Err(QueueError::InvalidState(_))));
+
+    let events = queue.tick(&fleet).await.unwrap();
+    assert_eq!(
+        events,
+        [QueueEvent::PrintFailed {
+            job: id,
+            printer: "prusa".to_string(),
+            reason: "the print stopped at 30.0%".to_string()
+        }]
+    );
+    assert!(queue.is_finished());
+
+    queue.retry(id).unwrap();
+    let events = queue.tick(&fleet).await.unwrap();
+    assert!(matches!(events[..], [QueueEvent::Dispatched { job, .. }] if job == id));
+
+    let queue = PrintQueue::open(&dir).unwrap();
+    assert!(matches!(
+        queue.job(id).unwrap().status,
+        JobStatus::Printing { .. }
+    ));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+async fn missing_gcode_fails_only_its_job() {
+    let dir = queue_dir("missing");
+    let mut fleet = Fleet::new();
+    fleet.add("prusa", FakePrinter::new());
+
+    let mut queue = PrintQueue::open(&dir).unwrap();
+    let missing = queue
+        .submit("lost.gcode", "G28\n", Requirements::default())
+        .unwrap();
+    let next = queue
+        .submit("benchy.gcode", "G28\n", Requirements::default())
+        .unwrap();
+    std::fs::remove_file(dir.join("gcode").join(format!("{}.gcode", missing))).unwrap();
+
+    let events = queue.tick(&fleet).await.unwrap();
+    assert!(matches!(
+        &events[..],
+        [
+            QueueEvent::DispatchFailed { job, .. },
+            QueueEvent::Dispatched { job: dispatched, printer },
+        ] if *job == missing && *dispatched == next && printer == "prusa"
+    ));
+    assert!(matches!(
+        queue.job(missing).unwrap().status,
+        JobStatus::Failed { .. }
+    ));
+
+    // The failure was saved, so it is not tried again after a restart
+    let queue = PrintQueue::open(&dir).unwrap();
+    assert!(matches!(
+        queue.job(missing).unwrap().status,
+        JobStatus::Failed { .. }
+    ));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+async fn follows_sanitized_file_names() {
+    let dir = queue_dir("sanitized");
+    let mut fleet = Fleet::new();
+    fleet.add("prusa", FakePrinter::new());
+
+    let mut queue = PrintQueue::open(&dir).unwrap();
+    let id = queue
+        .submit("my benchy.gcode", "G28\n", Requirements::default())
+        .unwrap();
+
+    let events = queue.tick(&fleet).await.unwrap();
+    assert!(matches!(events[..], [QueueEvent::Dispatched { job, .. }] if job == id));
+    // The printer stored the file with an underscore and the queue follows it
+    let path = format!("queue/{}-my_benchy.gcode", id);
+    assert_eq!(queue.job(id).unwrap().path, path);
+
+    let printer = fleet.get("prusa").unwrap();
+    printer.set_progress(50.0);
+    let events = queue.tick(&fleet).await.unwrap();
+    assert!(events.is_empty(), "{:?}", events);
+    assert_eq!(
+        queue.job(id).unwrap().status,
+        JobStatus::Printing {
+            printer: "prusa".to_string(),
+            completion: Some(50.0),
+        }
+    );
+
+    // The path is saved along with the status
+    let queue = PrintQueue::open(&dir).unwrap();
+    assert_eq!(queue.job(id).unwrap().path, path);
+    let _ = std::fs::remove_dir_all(dir);
+}
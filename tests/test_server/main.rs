@@ -0,0 +1,448 @@
+#![cfg(feature = "test-server")]
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use octoprint_rs::errors::*;
+use octoprint_rs::test_server::{TestServer, TestServerBuilder};
+use octoprint_rs::types::printer_files::Entry;
+use octoprint_rs::types::*;
+use octoprint_rs::PrinterBuilder;
+use tokio::test;
+
+fn local(path: &str) -> PathDescriptor {
+    PathDescriptor {
+        location: FileLocation::Local,
+        path: path.to_string(),
+    }
+}
+
+fn all_files() -> FilesFetchDescriptor {
+    FilesFetchDescriptor {
+        location: FilesLocation::Local,
+        recursive: true,
+        force: false,
+    }
+}
+
+#[test]
+async fn rejects_wrong_api_key() {
+    let server = TestServer::start();
+
+    let printer = PrinterBuilder::new(server.address(), "WRONG")
+        .port(server.port())
+        .build();
+
+    let error = printer.get_api_version().await.unwrap_err();
+    assert!(matches!(
+        error,
+        InformationRequestError::Unexpected(OctoPrintError::Status { .. })
+    ));
+}
+
+#[test]
+async fn connection_state_machine() {
+    let server = TestServer::start();
+    let printer = server.printer();
+
+    printer
+        .set_connection(ConnectionCommandDescriptor::Disconnect)
+        .await
+        .unwrap();
+    assert_eq!(
+        printer.get_connection().await.unwrap().current.state,
+        "Closed"
+    );
+    assert!(matches!(
+        printer.get_printer_telemetry().await,
+        Err(DeviceStateError::Conflict(_))
+    ));
+
+    let wrong_port = ConnectionCommandDescriptor::Connect {
+        port: "/dev/ttyUSB9".to_string(),
+        baudrate: 115200,
+        printer_profile: "_default".to_string(),
+        save: false,
+        autoconnect: false,
+    };
+    assert!(matches!(
+        printer.set_connection(wrong_port).await,
+        Err(SetConnectionError::BadRequest(_))
+    ));
+
+    let connection = printer.get_connection().await.unwrap();
+    printer
+        .set_connection(ConnectionCommandDescriptor::Connect {
+            port: connection.options.ports[0].clone(),
+            baudrate: connection.options.baudrates[0],
+            printer_profile: "_default".to_string(),
+            save: false,
+            autoconnect: false,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        printer.get_printer_telemetry().await.unwrap().state.text,
+        "Operational"
+    );
+}
+
+#[test]
+async fn file_storage() {
+    let server = TestServer::start();
+    let printer = server.printer();
+
+    let form = reqwest::multipart::Form::new().text("path", "parts").part(
+        "file",
+        reqwest::multipart::Part::bytes(b"G28\n".to_vec()).file_name("benchy.gcode"),
+    );
+    let response = reqwest::Client::new()
+        .post(format!("{}api/files/local", server.url()))
+        .header("X-Api-Key", server.api_key())
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+
+    printer
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Copy {
+                destination: "/".to_string(),
+            },
+            path: local("parts/benchy.gcode"),
+        })
+        .await
+        .unwrap();
+    printer
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Move {
+                destination: "archive".to_string(),
+            },
+            path: local("parts/benchy.gcode"),
+        })
+        .await
+        .unwrap();
+
+    let files = printer.get_files(all_files()).await.unwrap();
+    let mut paths = vec![];
+    let mut pending: Vec<&Entry> = files.files.iter().collect();
+    while let Some(entry) = pending.pop() {
+        match entry {
+            Entry::File { path, size, .. } => {
+                assert_eq!(*size, Some(4));
+                paths.push(path.clone());
+            }
+            Entry::Folder { children, .. } => pending.extend(children),
+        }
+    }
+    paths.sort();
+    assert_eq!(paths, ["archive/benchy.gcode", "benchy.gcode"]);
+
+    printer.delete_file(local("benchy.gcode")).await.unwrap();
+    assert!(matches!(
+        printer.delete_file(local("benchy.gcode")).await,
+        Err(FileDeletionError::NotFound(_))
+    ));
+}
+
+#[test]
+async fn job_with_simulated_progress() {
+    let server = TestServerBuilder::new().print_speed(100).start();
+    server.add_file("benchy.gcode", vec![b'\n'; 1000]);
+    let printer = server.printer();
+
+    printer
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Select { print: true },
+            path: local("benchy.gcode"),
+        })
+        .await
+        .unwrap();
+    assert!(matches!(
+        printer.delete_file(local("benchy.gcode")).await,
+        Err(FileDeletionError::Conflict(_))
+    ));
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    printer.issue_job_command(JobCommand::Pause).await.unwrap();
+
+    let paused = printer.get_job().await.unwrap();
+    assert_eq!(paused.state, "Paused");
+    let filepos = paused.progress.filepos.unwrap();
+    assert!(filepos > 0 && filepos < 1000, "filepos {}", filepos);
+    assert!(paused.progress.print_time_left.unwrap() > 0);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        printer.get_job().await.unwrap().progress.filepos,
+        Some(filepos)
+    );
+
+    printer.issue_job_command(JobCommand::Cancel).await.unwrap();
+    assert_eq!(printer.get_job().await.unwrap().state, "Operational");
+    assert!(matches!(
+        printer.issue_job_command(JobCommand::Pause).await,
+        Err(JobCommandError::Conflict(_))
+    ));
+}
+
+#[test]
+async fn temperature_ramps() {
+    let server = TestServerBuilder::new()
+        .tools(2)
+        .heating_rate(1000.0)
+        .start();
+    let printer = server.printer();
+
+    printer
+        .tool_temperature(ToolTempDescriptor::Target {
+            tool: ToolId::new(1),
+            temperature: 210.0,
+        })
+        .await
+        .unwrap();
+    printer
+        .change_bed_temp(BedTempDescriptor::Target { target: 60.0 })
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let tools = printer.get_tool_state(Some(10)).await.unwrap();
+    assert_eq!(tools.tools.len(), 2);
+    assert_eq!(tools.tools["tool1"].actual, 210.0);
+    assert_eq!(tools.tools["tool0"].actual, 21.0);
+    assert!(!tools.history.unwrap().is_empty());
+
+    let bed = printer.get_bed_state(None).await.unwrap();
+    assert_eq!(bed.bed.actual, 60.0);
+    assert_eq!(bed.bed.target, Some(60.0));
+
+    printer
+        .send_gcode(vec!["M104 T1 S0".to_string(), "M140 S0".to_string()])
+        .await
+        .unwrap();
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool1"].target, Some(0.0));
+    let bed = printer.get_bed_state(None).await.unwrap();
+    assert_eq!(bed.bed.target, Some(0.0));
+
+    // Without cached printer profiles a single tool is rejected by the printer itself
+    assert!(matches!(
+        printer
+            .tool_temperature(ToolTempDescriptor::Target {
+                tool: ToolId::new(7),
+                temperature: 210.0,
+            })
+            .await,
+        Err(ToolCommandError::BadRequest(_))
+    ));
+}
+
+#[test]
+async fn many_tools_in_one_command() {
+    let server = TestServerBuilder::new().tools(2).start();
+    let printer = server.printer();
+
+    let targets = BTreeMap::from([(ToolId::new(0), 200.0), (ToolId::new(1), 215.0)]);
+    printer
+        .tool_temperature(ToolTempDescriptor::Targets(targets))
+        .await
+        .unwrap();
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool0"].target, Some(200.0));
+    assert_eq!(tools.tools["tool1"].target, Some(215.0));
+
+    // tool2 is past the two extruders of the printer profile, so nothing is sent
+    let offsets = BTreeMap::from([(ToolId::new(1), 5.0), (ToolId::new(2), 5.0)]);
+    let error = printer
+        .tool_temperature(ToolTempDescriptor::Offsets(offsets))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, ToolCommandError::BadRequest(ref message) if message.contains("tool2"))
+    );
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool1"].offset, Some(0.0));
+
+    assert!(matches!(
+        printer
+            .tool_temperature(ToolTempDescriptor::Targets(BTreeMap::new()))
+            .await,
+        Err(ToolCommandError::BadRequest(_))
+    ));
+}
+
+#[test]
+async fn heat_and_wait_for_ramps() {
+    let server = TestServerBuilder::new().heating_rate(100.0).start();
+    let printer = server.printer();
+
+    let (tool, bed) = tokio::join!(
+        printer.heat_and_wait(
+            ToolId::new(0),
+            200.0,
+            1.0,
+            Duration::from_millis(300),
+            Duration::from_secs(10),
+        ),
+        printer.heat_bed_and_wait(
+            60.0,
+            1.0,
+            Duration::from_millis(300),
+            Duration::from_secs(10)
+        )
+    );
+    assert!((tool.unwrap() - 200.0).abs() <= 1.0);
+    assert!((bed.unwrap() - 60.0).abs() <= 1.0);
+
+    let error = printer
+        .heat_and_wait(
+            ToolId::new(0),
+            260.0,
+            1.0,
+            Duration::from_millis(300),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(error, HeatError::Timeout { .. }));
+}
+
+const GCODE: &str = "M140 S60 ; bed
+M104 S200
+M190 S60
+M109 S200
+G28
+G1 Z0.2 F600
+G1 X50 Y0 E5 F3000
+G1 Z0.4
+G1 X0 Y50 E10
+M104 S0
+";
+
+#[test]
+async fn virtual_printer_waits_for_heaters() {
+    let server = TestServerBuilder::new().simulate_gcode(true).start();
+    server.add_file("part.gcode", GCODE);
+    let printer = server.printer();
+
+    printer
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Select { print: true },
+            path: local("part.gcode"),
+        })
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Heating the bed from 21°C to 60°C at 10°C per second holds the print at the M190
+    let job = printer.get_job().await.unwrap();
+    assert_eq!(job.state, "Printing");
+    assert_eq!(
+        job.progress.filepos,
+        Some(GCODE.find("M109").unwrap() as u64)
+    );
+
+    let bed = printer.get_bed_state(None).await.unwrap();
+    assert_eq!(bed.bed.target, Some(60.0));
+    assert!(bed.bed.actual > 21.0 && bed.bed.actual < 60.0);
+
+    let tools = printer.get_tool_state(None).await.unwrap();
+    assert_eq!(tools.tools["tool0"].target, Some(200.0));
+}
+
+#[test]
+async fn virtual_printer_runs_to_completion() {
+    let server = TestServerBuilder::new()
+        .simulate_gcode(true)
+        .heating_rate(1000.0)
+        .time_factor(100.0)
+        .start();
+    server.add_file("part.gcode", GCODE);
+    let printer = server.printer();
+
+    let file = printer
+        .get_file(FileFetchDescriptor {
+            path: local("part.gcode"),
+            recursive: false,
+            force: false,
+        })
+        .await
+        .unwrap();
+    assert!(matches!(
+        file,
+        Entry::File {
+            gcode_analysis: Some(_),
+            ..
+        }
+    ));
+
+    printer
+        .issue_file_command(FileCommandDescriptor {
+            command: FileCommand::Select { print: true },
+            path: local("part.gcode"),
+        })
+        .await
+        .unwrap();
+
+    let mut job = printer.get_job().await.unwrap();
+    for _ in 0..50 {
+        if job.state == "Operational" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        job = printer.get_job().await.unwrap();
+    }
+
+    assert_eq!(job.state, "Operational");
+    assert_eq!(job.progress.completion, Some(100.0));
+    assert_eq!(job.progress.filepos, Some(GCODE.len() as u64));
+    assert_eq!(job.progress.print_time_left, Some(0));
+
+    let events = server.events();
+    let types: Vec<_> = events.iter().map(|e| e.event_type.as_str()).collect();
+    assert_eq!(types, ["PrintStarted", "ZChange", "ZChange", "PrintDone"]);
+    assert_eq!(events[1].payload["new"], 0.2f32 as f64);
+    assert_eq!(events[2].payload["old"], 0.2f32 as f64);
+}
+
+#[test]
+async fn upload_and_print() {
+    let server = TestServerBuilder::new().tools(2).start();
+    let printer = server.printer();
+
+    let profiles = printer.get_printer_profiles().await.unwrap();
+    let profile = profiles.current().unwrap();
+    assert_eq!(profile.extruder.count, 2);
+    assert_eq!(profile.volume.height, 200.0);
+
+    let response = printer
+        .upload_file(FileUploadDescriptor {
+            path: local("parts/benchy.gcode"),
+            contents: b"G28\n".repeat(1000),
+            select: false,
+            print: true,
+        })
+        .await
+        .unwrap();
+    assert!(response.done);
+    assert_eq!(response.files["local"].name, "benchy.gcode");
+
+    let job = printer.get_job().await.unwrap();
+    assert_eq!(job.state, "Printing");
+    assert_eq!(job.job.file.path.as_deref(), Some("parts/benchy.gcode"));
+
+    assert!(matches!(
+        printer
+            .upload_file(FileUploadDescriptor {
+                path: local("parts/benchy.gcode"),
+                contents: b"G28\n".to_vec(),
+                select: false,
+                print: false,
+            })
+            .await,
+        Err(FileUploadError::Conflict(_))
+    ));
+}
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use octoprint_rs::*;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa, SanType};
+use rustls::server::AllowAnyAuthenticatedClient;
+use sha2::{Digest, Sha256};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    test,
+};
+use tokio_rustls::TlsAcceptor;
+
+/// A CA and a server certificate for `localhost` signed by it
+struct Pki {
+    ca: Certificate,
+    server: Certificate,
+    server_der: Vec<u8>,
+}
+
+impl Pki {
+    fn new() -> Pki {
+        let mut ca_params = CertificateParams::new(vec![]);
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = Certificate::from_params(ca_params).unwrap();
+
+        let mut server_params = CertificateParams::new(vec!["localhost".to_string()]);
+        server_params
+            .subject_alt_names
+            .push(SanType::IpAddress("127.0.0.1".parse().unwrap()));
+        let server = Certificate::from_params(server_params).unwrap();
+        let server_der = server.serialize_der_with_signer(&ca).unwrap();
+
+        Pki {
+            ca,
+            server,
+            server_der,
+        }
+    }
+
+    fn ca_pem(&self) -> String {
+        self.ca.serialize_pem().unwrap()
+    }
+
+    /// Issues a client certificate signed by the CA, returning the certificate and key PEM
+    fn client_pem(&self) -> (String, String) {
+        let client = Certificate::from_params(CertificateParams::new(vec![])).unwrap();
+        (
+            client.serialize_pem_with_signer(&self.ca).unwrap(),
+            client.serialize_private_key_pem(),
+        )
+    }
+}
+
+/// Starts a TLS stand-in for OctoPrint that answers every request with the api version.
+/// Returns the port it is listening on.
+async fn serve(pki: &Pki, require_client_cert: bool) -> u16 {
+    let builder = rustls::ServerConfig::builder().with_safe_defaults();
+    let builder = if require_client_cert {
+        let mut roots = rustls::RootCertStore::empty();
+        roots
+            .add(&rustls::Certificate(pki.ca.serialize_der().unwrap()))
+            .unwrap();
+        builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+    } else {
+        builder.with_no_client_auth()
+    };
+    let config = builder
+        .with_single_cert(
+            vec![rustls::Certificate(pki.server_der.clone())],
+            rustls::PrivateKey(pki.server.serialize_private_key_der()),
+        )
+        .unwrap();
+
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+
+            tokio::spawn(async move {
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+
+                let body = r#"{"api": "0.1", "server": "1.9.3", "text": "OctoPrint 1.9.3"}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    port
+}
+
+#[test]
+async fn untrusted_certificate_is_rejected() {
+    let pki = Pki::new();
+    let port = serve(&pki, false).await;
+
+    let printer = PrinterBuilder::new("localhost", "API_KEY")
+        .https(true)
+        .port(port)
+        .build();
+
+    assert!(matches!(
+        printer.get_api_version().await,
+        Err(errors::InformationRequestError::ReqwestError(_))
+    ));
+}
+
+#[test]
+async fn root_certificate() {
+    let pki = Pki::new();
+    let port = serve(&pki, false).await;
+
+    let printer = PrinterBuilder::new("localhost", "API_KEY")
+        .https(true)
+        .port(port)
+        .add_root_certificate_pem(pki.ca_pem())
+        .unwrap()
+        .build();
+
+    let api_version = printer.get_api_version().await.unwrap();
+    assert_eq!(api_version.server, "1.9.3".to_string());
+}
+
+#[test]
+async fn pinned_certificate() {
+    let pki = Pki::new();
+    let port = serve(&pki, false).await;
+
+    let fingerprint = Sha256::digest(&pki.server_der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    let printer = PrinterBuilder::new("127.0.0.1", "API_KEY")
+        .https(true)
+        .port(port)
+        .pin_certificate_sha256(&fingerprint)
+        .unwrap()
+        .build();
+
+    printer.get_api_version().await.unwrap();
+}
+
+#[test]
+async fn pinned_certificate_mismatch() {
+    let pki = Pki::new();
+    let port = serve(&pki, false).await;
+
+    let printer = PrinterBuilder::new("localhost", "API_KEY")
+        .https(true)
+        .port(port)
+        .pin_certificate_sha256(&"00".repeat(32))
+        .unwrap()
+        .build();
+
+    assert!(printer.get_api_version().await.is_err());
+
+    let accepting = PrinterBuilder::new("localhost", "API_KEY")
+        .https(true)
+        .port(port)
+        .pin_certificate_sha256(&"00".repeat(32))
+        .unwrap()
+        .danger_accept_invalid_certs(true)
+        .build();
+
+    assert!(accepting.get_api_version().await.is_err());
+}
+
+#[test]
+async fn accept_invalid_certs() {
+    let pki = Pki::new();
+    let port = serve(&pki, false).await;
+
+    let printer = PrinterBuilder::new("localhost", "API_KEY")
+        .https(true)
+        .port(port)
+        .danger_accept_invalid_certs(true)
+        .build();
+
+    printer.get_api_version().await.unwrap();
+}
+
+#[test]
+async fn client_certificate() {
+    let pki = Pki::new();
+    let port = serve(&pki, true).await;
+    let (certificate, key) = pki.client_pem();
+
+    let without_identity = PrinterBuilder::new("localhost", "API_KEY")
+        .https(true)
+        .port(port)
+        .add_root_certificate_pem(pki.ca_pem())
+        .unwrap()
+        .build();
+
+    assert!(without_identity.get_api_version().await.is_err());
+
+    let printer = PrinterBuilder::new("localhost", "API_KEY")
+        .https(true)
+        .port(port)
+        .add_root_certificate_pem(pki.ca_pem())
+        .unwrap()
+        .client_identity_pem(certificate, key)
+        .unwrap()
+        .build();
+
+    printer.get_api_version().await.unwrap();
+}
+
+#[test]
+async fn invalid_tls_options() {
+    assert!(matches!(
+        PrinterBuilder::new("localhost", "API_KEY").add_root_certificate_pem("not a pem"),
+        Err(errors::PrinterBuilderError::InvalidCertificate(_))
+    ));
+    assert!(matches!(
+        PrinterBuilder::new("localhost", "API_KEY").pin_certificate_sha256("abcd"),
+        Err(errors::PrinterBuilderError::InvalidFingerprint(_))
+    ));
+    assert!(matches!(
+        PrinterBuilder::new("localhost", "API_KEY")
+            .pin_certificate_sha256(&format!("+a{}", "00".repeat(31))),
+        Err(errors::PrinterBuilderError::InvalidFingerprint(_))
+    ));
+}